@@ -25,7 +25,7 @@ fn main() {
     
     // 检查是否已安装 Node.js
     let node_check = Command::new("node")
-        .args(&["--version"])
+        .args(["--version"])
         .output();
     
     if node_check.is_err() {
@@ -36,7 +36,7 @@ fn main() {
     // 安装依赖
     println!("cargo:warning=Installing frontend dependencies...");
     let npm_install = Command::new("npm")
-        .args(&["install"])
+        .args(["install"])
         .current_dir("web")
         .output()
         .expect("Failed to run npm install");
@@ -49,7 +49,7 @@ fn main() {
     println!("cargo:warning=Building frontend...");
     let mut npm_build = Command::new("npm");
     npm_build
-        .args(&["run", "build"])
+        .args(["run", "build"])
         .current_dir("web");
 
     // 传递 VITE_ 开头的环境变量到前端构建过程
@@ -0,0 +1,7 @@
+CREATE TABLE bot_dialogues (
+    chat_id BIGINT NOT NULL PRIMARY KEY,
+    state TEXT NOT NULL, -- serde_json serialized bot::State
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_bot_dialogues_updated_at ON bot_dialogues (updated_at);
@@ -0,0 +1 @@
+DROP TABLE episode_notifications;
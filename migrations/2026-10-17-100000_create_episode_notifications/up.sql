@@ -0,0 +1,13 @@
+CREATE TABLE episode_notifications (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    series_id TEXT NOT NULL, -- Emby SeriesId
+    series_name TEXT NOT NULL,
+    production_year INTEGER,
+    season_number INTEGER,
+    episode_number INTEGER,
+    created_at TEXT NOT NULL,
+    notified_at TEXT, -- NULL: 等待汇总推送
+    UNIQUE(series_id, season_number, episode_number)
+);
+
+CREATE INDEX idx_episode_notifications_notified_at ON episode_notifications (notified_at);
@@ -0,0 +1,3 @@
+ALTER TABLE episode_notifications DROP COLUMN library;
+
+DROP TABLE notification_subscriptions;
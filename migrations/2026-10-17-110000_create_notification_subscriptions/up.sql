@@ -0,0 +1,13 @@
+CREATE TABLE notification_subscriptions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    chat_id BIGINT NOT NULL,
+    category TEXT NOT NULL, -- movies / episodes / requests
+    library TEXT NOT NULL DEFAULT '', -- Emby 媒体库名称，空字符串表示全部媒体库
+    created_by BIGINT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(chat_id, category, library)
+);
+
+CREATE INDEX idx_notification_subscriptions_category ON notification_subscriptions (category);
+
+ALTER TABLE episode_notifications ADD COLUMN library TEXT;
@@ -0,0 +1,3 @@
+DROP TABLE media_request_notes;
+
+ALTER TABLE media_requests DROP COLUMN status_reason;
@@ -0,0 +1,11 @@
+ALTER TABLE media_requests ADD COLUMN status_reason TEXT; -- 最近一次状态变更的原因，会展示给请求者
+
+CREATE TABLE media_request_notes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    media_request_id INTEGER NOT NULL REFERENCES media_requests(id) ON DELETE CASCADE,
+    author BIGINT NOT NULL, -- 管理员 Telegram ID
+    content TEXT NOT NULL, -- 仅管理员可见
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_media_request_notes_media_request_id ON media_request_notes (media_request_id);
@@ -0,0 +1,15 @@
+CREATE TABLE media_request_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    media_request_id INTEGER NOT NULL REFERENCES media_requests(id) ON DELETE CASCADE,
+    actor BIGINT, -- 操作者 Telegram ID，NULL 表示系统（如 Emby 入库自动完成）
+    old_status INTEGER, -- NULL 表示请求创建
+    new_status INTEGER NOT NULL,
+    reason TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_media_request_events_media_request_id ON media_request_events (media_request_id);
+
+-- 为已有请求补一条创建记录
+INSERT INTO media_request_events (media_request_id, actor, old_status, new_status, reason, created_at)
+SELECT id, request_user, NULL, 0, NULL, created_at FROM media_requests;
@@ -0,0 +1,6 @@
+CREATE TABLE media_request_followers (
+    media_request_id INTEGER NOT NULL REFERENCES media_requests(id) ON DELETE CASCADE,
+    telegram_id BIGINT NOT NULL, -- 重复请求同一媒体的用户
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (media_request_id, telegram_id)
+);
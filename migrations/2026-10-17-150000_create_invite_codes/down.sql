@@ -0,0 +1,2 @@
+DROP TABLE invite_redemptions;
+DROP TABLE invite_codes;
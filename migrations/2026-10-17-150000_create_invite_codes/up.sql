@@ -0,0 +1,21 @@
+CREATE TABLE invite_codes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE,
+    created_by BIGINT NOT NULL, -- 创建邀请码的管理员 Telegram ID
+    max_uses INTEGER NOT NULL DEFAULT 1,
+    uses INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT, -- NULL 表示永不过期
+    revoked BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE invite_redemptions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    invite_code_id INTEGER NOT NULL REFERENCES invite_codes(id) ON DELETE CASCADE,
+    telegram_id BIGINT NOT NULL,
+    emby_user_id TEXT, -- 注册成功后写入
+    redeemed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_invite_redemptions_invite_code_id ON invite_redemptions (invite_code_id);
+CREATE INDEX idx_invite_redemptions_telegram_id ON invite_redemptions (telegram_id);
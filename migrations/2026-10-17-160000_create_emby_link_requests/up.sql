@@ -0,0 +1,12 @@
+CREATE TABLE emby_link_requests (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    telegram_id BIGINT NOT NULL,
+    emby_user_id TEXT NOT NULL,
+    emby_username TEXT NOT NULL,
+    status INTEGER NOT NULL DEFAULT 0, -- 0: 待审核, 1: 已通过, 2: 已拒绝
+    reviewed_by BIGINT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    reviewed_at TEXT
+);
+
+CREATE INDEX idx_emby_link_requests_telegram_id ON emby_link_requests (telegram_id);
@@ -0,0 +1,7 @@
+CREATE TABLE password_reset_attempts (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    telegram_id BIGINT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_password_reset_attempts_telegram_id ON password_reset_attempts (telegram_id, created_at);
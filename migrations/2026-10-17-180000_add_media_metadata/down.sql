@@ -0,0 +1,10 @@
+ALTER TABLE media DROP COLUMN bgm_id;
+ALTER TABLE media DROP COLUMN tmdb_id;
+ALTER TABLE media DROP COLUMN tvdb_id;
+ALTER TABLE media DROP COLUMN imdb_id;
+ALTER TABLE media DROP COLUMN episode_count;
+ALTER TABLE media DROP COLUMN season_count;
+ALTER TABLE media DROP COLUMN runtime;
+ALTER TABLE media DROP COLUMN genres;
+ALTER TABLE media DROP COLUMN original_title;
+ALTER TABLE media DROP COLUMN year;
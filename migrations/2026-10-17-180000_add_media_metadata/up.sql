@@ -0,0 +1,10 @@
+ALTER TABLE media ADD COLUMN year INTEGER; -- 首映/发行年份
+ALTER TABLE media ADD COLUMN original_title TEXT;
+ALTER TABLE media ADD COLUMN genres TEXT; -- 逗号分隔
+ALTER TABLE media ADD COLUMN runtime INTEGER; -- 单集或电影时长，单位分钟
+ALTER TABLE media ADD COLUMN season_count INTEGER;
+ALTER TABLE media ADD COLUMN episode_count INTEGER;
+ALTER TABLE media ADD COLUMN imdb_id TEXT;
+ALTER TABLE media ADD COLUMN tvdb_id TEXT;
+ALTER TABLE media ADD COLUMN tmdb_id TEXT;
+ALTER TABLE media ADD COLUMN bgm_id TEXT;
@@ -0,0 +1,8 @@
+CREATE TABLE scrape_cache (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    source TEXT NOT NULL, -- 与 media_requests.source 相同
+    media_id TEXT NOT NULL,
+    payload TEXT NOT NULL, -- JSON 序列化的刮削结果
+    fetched_at TEXT NOT NULL,
+    UNIQUE(source, media_id)
+);
@@ -0,0 +1 @@
+DROP INDEX idx_episode_notifications_episode;
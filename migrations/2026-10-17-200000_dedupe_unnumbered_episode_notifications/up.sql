@@ -0,0 +1,9 @@
+-- SQLite 中 NULL 互不相等，原有的 UNIQUE(series_id, season_number, episode_number) 无法去重没有季号或集号的剧集
+DELETE FROM episode_notifications
+WHERE id NOT IN (
+    SELECT MIN(id) FROM episode_notifications
+    GROUP BY series_id, COALESCE(season_number, -1), COALESCE(episode_number, -1)
+);
+
+CREATE UNIQUE INDEX idx_episode_notifications_episode
+    ON episode_notifications (series_id, COALESCE(season_number, -1), COALESCE(episode_number, -1));
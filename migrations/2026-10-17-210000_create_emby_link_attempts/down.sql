@@ -0,0 +1 @@
+DROP TABLE emby_link_attempts;
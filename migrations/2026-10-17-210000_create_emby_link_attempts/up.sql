@@ -0,0 +1,7 @@
+CREATE TABLE emby_link_attempts (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    telegram_id BIGINT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_emby_link_attempts_telegram_id ON emby_link_attempts (telegram_id, created_at);
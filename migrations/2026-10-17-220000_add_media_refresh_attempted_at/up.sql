@@ -0,0 +1,2 @@
+-- 定时刷新最近一次尝试的时间，刷新失败的媒体据此推迟重试，不会一直占用每批的名额
+ALTER TABLE media ADD COLUMN refresh_attempted_at TEXT;
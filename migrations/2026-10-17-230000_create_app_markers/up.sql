@@ -0,0 +1,9 @@
+CREATE TABLE app_markers (
+    name TEXT NOT NULL PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 已有订阅说明旧配置导入过，避免升级后再次导入
+INSERT INTO app_markers (name)
+SELECT 'legacy_notify_chats_imported'
+WHERE EXISTS (SELECT 1 FROM notification_subscriptions);
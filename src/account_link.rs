@@ -0,0 +1,224 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::emby::{EmbyApi, EmbyError};
+use crate::models::{link_request_status, EmbyLinkRequest, NewEmbyLinkAttempt, NewEmbyLinkRequest, NewTelegramUser};
+use crate::rate_limit::{self, RollingWindow, TIME_FORMAT};
+use crate::schema::{emby_link_attempts, emby_link_requests, telegram_users};
+
+/// `/link` 密码验证失败次数限制
+#[derive(Debug, Clone)]
+pub struct LinkAttemptConfig {
+    /// 滚动窗口内允许的失败次数，0 表示不限制
+    pub max_failures: i64,
+    pub window_secs: i64,
+}
+
+impl LinkAttemptConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_failures: rate_limit::env_i64("LINK_MAX_FAILED_ATTEMPTS", 5),
+            window_secs: rate_limit::env_i64("LINK_FAILED_ATTEMPT_WINDOW_SECONDS", 3600),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LinkError {
+    NotFound(String),
+    /// 账户已绑定、申请已处理等无法继续的情况
+    Conflict(String),
+    /// Emby 用户名或密码错误
+    Unauthorized(String),
+    /// 窗口内验证失败次数已达上限
+    TooManyAttempts(String),
+    Emby(EmbyError),
+    Internal(String),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::NotFound(message)
+            | LinkError::Conflict(message)
+            | LinkError::Unauthorized(message)
+            | LinkError::TooManyAttempts(message)
+            | LinkError::Internal(message) => write!(f, "{}", message),
+            LinkError::Emby(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for LinkError {
+    fn from(err: diesel::result::Error) -> Self {
+        LinkError::Internal(format!("数据库操作失败: {}", err))
+    }
+}
+
+/// 检查 Telegram 用户和 Emby 账户都还没有绑定
+pub fn ensure_linkable(conn: &mut SqliteConnection, telegram_id: i64, emby_user_id: &str) -> Result<(), LinkError> {
+    let registered = telegram_users::table
+        .filter(telegram_users::telegram_id.eq(telegram_id))
+        .count()
+        .get_result::<i64>(conn)?;
+    if registered > 0 {
+        return Err(LinkError::Conflict("您已经注册或绑定过 Emby 账户了。".to_string()));
+    }
+
+    let linked = telegram_users::table
+        .filter(telegram_users::emby_user_id.eq(emby_user_id))
+        .count()
+        .get_result::<i64>(conn)?;
+    if linked > 0 {
+        return Err(LinkError::Conflict("该 Emby 账户已经绑定了其他 Telegram 用户。".to_string()));
+    }
+
+    Ok(())
+}
+
+/// 将已有的 Emby 账户绑定到 Telegram 用户，不会在 Emby 中创建新用户
+pub fn link_account(
+    conn: &mut SqliteConnection,
+    telegram_id: i64,
+    emby_username: &str,
+    emby_user_id: &str,
+) -> Result<(), LinkError> {
+    conn.transaction(|conn| {
+        ensure_linkable(conn, telegram_id, emby_user_id)?;
+
+        diesel::insert_into(telegram_users::table)
+            .values(&NewTelegramUser {
+                telegram_id,
+                username: emby_username.to_string(),
+                emby_user_id: emby_user_id.to_string(),
+            })
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// 检查用户在窗口内的验证失败次数，达到上限时说明何时可以再试
+pub fn check_attempt_limit(conn: &mut SqliteConnection, config: &LinkAttemptConfig, telegram_id: i64) -> Result<(), LinkError> {
+    let window = RollingWindow::new(config.max_failures, config.window_secs);
+    let retry_at = window.check(|window_start| {
+        emby_link_attempts::table
+            .filter(emby_link_attempts::telegram_id.eq(telegram_id))
+            .filter(emby_link_attempts::created_at.gt(window_start))
+            .order(emby_link_attempts::created_at.asc())
+            .select(emby_link_attempts::created_at)
+            .load::<String>(conn)
+    })?;
+    if let Some(retry_at) = retry_at {
+        return Err(LinkError::TooManyAttempts(format!("验证失败次数过多，请于 {} 之后再试。", retry_at)));
+    }
+
+    Ok(())
+}
+
+/// 记录一次验证失败
+pub fn record_failed_attempt(conn: &mut SqliteConnection, telegram_id: i64) -> Result<(), LinkError> {
+    diesel::insert_into(emby_link_attempts::table)
+        .values(&NewEmbyLinkAttempt {
+            telegram_id,
+            created_at: Utc::now().format(TIME_FORMAT).to_string(),
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+/// 用 Emby 用户名和密码验证账户归属后绑定。
+/// 验证失败计入次数，达到上限后返回 `TooManyAttempts`，调用方应结束本次绑定
+pub async fn link_with_password<E: EmbyApi>(
+    api: &E,
+    conn: &mut SqliteConnection,
+    config: &LinkAttemptConfig,
+    telegram_id: i64,
+    username: &str,
+    password: &str,
+) -> Result<String, LinkError> {
+    check_attempt_limit(conn, config, telegram_id)?;
+
+    let user = match api.authenticate_by_name(username, password).await {
+        Ok(user) => user,
+        Err(EmbyError::Unauthorized(_)) => {
+            record_failed_attempt(conn, telegram_id)?;
+            check_attempt_limit(conn, config, telegram_id)?;
+            return Err(LinkError::Unauthorized("用户名或密码错误。".to_string()));
+        }
+        Err(e) => return Err(LinkError::Emby(e)),
+    };
+
+    link_account(conn, telegram_id, &user.name, &user.id)?;
+    Ok(user.id)
+}
+
+/// 提交由管理员审核的绑定申请，同一用户同时只能有一个待审核的申请
+pub fn create_request(
+    conn: &mut SqliteConnection,
+    telegram_id: i64,
+    emby_user_id: &str,
+    emby_username: &str,
+) -> Result<EmbyLinkRequest, LinkError> {
+    conn.transaction(|conn| {
+        ensure_linkable(conn, telegram_id, emby_user_id)?;
+
+        let pending = emby_link_requests::table
+            .filter(emby_link_requests::telegram_id.eq(telegram_id))
+            .filter(emby_link_requests::status.eq(link_request_status::PENDING))
+            .count()
+            .get_result::<i64>(conn)?;
+        if pending > 0 {
+            return Err(LinkError::Conflict("您已有一个待审核的绑定申请，请等待管理员处理。".to_string()));
+        }
+
+        diesel::insert_into(emby_link_requests::table)
+            .values(&NewEmbyLinkRequest {
+                telegram_id,
+                emby_user_id: emby_user_id.to_string(),
+                emby_username: emby_username.to_string(),
+            })
+            .execute(conn)?;
+
+        emby_link_requests::table
+            .filter(emby_link_requests::telegram_id.eq(telegram_id))
+            .order(emby_link_requests::id.desc())
+            .select(EmbyLinkRequest::as_select())
+            .first(conn)
+            .map_err(LinkError::from)
+    })
+}
+
+/// 审核绑定申请，通过时写入绑定关系
+pub fn review_request(
+    conn: &mut SqliteConnection,
+    request_id: i32,
+    approve: bool,
+    reviewer: i64,
+) -> Result<EmbyLinkRequest, LinkError> {
+    conn.transaction(|conn| {
+        let request = emby_link_requests::table
+            .find(request_id)
+            .select(EmbyLinkRequest::as_select())
+            .first(conn)
+            .optional()?
+            .ok_or_else(|| LinkError::NotFound(format!("绑定申请 #{} 不存在", request_id)))?;
+        if request.status != link_request_status::PENDING {
+            return Err(LinkError::Conflict(format!("绑定申请 #{} 已经处理过了", request_id)));
+        }
+
+        if approve {
+            link_account(conn, request.telegram_id, &request.emby_username, &request.emby_user_id)?;
+        }
+
+        let status = if approve { link_request_status::APPROVED } else { link_request_status::REJECTED };
+        diesel::update(emby_link_requests::table.find(request_id))
+            .set((
+                emby_link_requests::status.eq(status),
+                emby_link_requests::reviewed_by.eq(reviewer),
+                emby_link_requests::reviewed_at.eq(Utc::now().format(TIME_FORMAT).to_string()),
+            ))
+            .execute(conn)?;
+
+        Ok(EmbyLinkRequest { status, reviewed_by: Some(reviewer), ..request })
+    })
+}
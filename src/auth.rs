@@ -10,7 +10,7 @@ pub fn check_admin(tg_id: i64) -> bool {
         .limit(1)
         .load::<TelegramUser>(conn)
         .expect("Error loading users");
-    if results.len() > 0 {
+    if !results.is_empty() {
         return results[0].admin;
     }
     false
@@ -24,7 +24,7 @@ pub fn check_registered(tg_id: i64) -> bool {
         .limit(1)
         .load::<TelegramUser>(conn)
         .expect("Error loading users");
-    if results.len() > 0 {
+    if !results.is_empty() {
         return true;
     }
     false
@@ -66,7 +66,7 @@ pub fn get_emby_id(tg_id: i64) -> String {
         .limit(1)
         .load::<TelegramUser>(conn)
         .expect("Error loading users");
-    if results.len() > 0 {
+    if !results.is_empty() {
         return results[0].emby_user_id.clone().unwrap_or_else(|| "".to_string());
     }
     "".to_string()
@@ -80,7 +80,7 @@ pub fn get_username(tg_id: i64) -> String {
         .limit(1)
         .load::<TelegramUser>(conn)
         .expect("Error loading users");
-    if results.len() > 0 {
+    if !results.is_empty() {
         return results[0].username.clone();
     }
     "".to_string()
@@ -1,11 +1,10 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::fs;
 use std::collections::HashMap;
 use std::error::Error;
 use std::env;
 use serde::{Deserialize, Serialize};
 use regex::Regex;
-use reqwest;
 use serde_json::Value;
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -47,7 +46,7 @@ fn load_config(path: &str) -> Result<Config, Box<dyn Error>> {
         println!("Config file not found. Creating a new one.");
         String::from("source_dir = \"\"\ntmdb_api_key = \"\"\n")
     });
-    let mut config: Config = toml::from_str(&content)?;
+    let config: Config = toml::from_str(&content)?;
     if config.source_dir.is_empty() {
         return Err("source_dir cannot be empty".into());
     }
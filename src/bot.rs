@@ -1,43 +1,46 @@
 use std::env;
 use chrono::Timelike;
 use teloxide::{
-    dispatching::{dialogue, dialogue::InMemStorage, UpdateHandler},
+    dispatching::{dialogue, UpdateHandler},
     prelude::*,
     utils::command::BotCommands,
 };
-use teloxide::types::{ChatKind, InlineKeyboardButton, InlineKeyboardMarkup, MediaKind, MessageKind};
+use teloxide::types::{ChatKind, ChatMemberUpdated, InlineKeyboardButton, InlineKeyboardMarkup, InputFile, InputMedia, InputMediaPhoto, MediaKind, MessageKind};
 use serde::{Serialize, Deserialize};
-use reqwest::Client;
 
 use crate::{auth, establish_connection};
-use crate::models::{NewMediaRequest, MediaRequest, media_request_status};
+use crate::models::{MediaRequest, media_request_status};
 use crate::schema::media_requests;
 use crate::scraper;
+use crate::notification;
+use crate::media_request;
+use crate::request_quota::{self, RequestQuotaConfig};
+use crate::invite::service as invite;
+use crate::group_membership::{self, GroupMembershipConfig};
+use crate::emby::{self, EmbyApi, EmbyClient};
+use crate::account_link::{self, LinkAttemptConfig, LinkError};
+use crate::password_reset::{self, PasswordResetConfig};
+use crate::models::link_request_status;
+use crate::dialogue_storage::DieselStorage;
 use diesel::prelude::*;
 
-type MyDialogue = Dialogue<State, InMemStorage<State>>;
+type MyDialogue = Dialogue<State, DieselStorage<State>>;
 type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-#[derive(Serialize)]
-struct EmbyRegisterPayload {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "CopyFromUserId")]
-    copy_from_user_id: String,
-    #[serde(rename = "UserCopyOptions")]
-    user_copy_options: Vec<String>,
-}
-
-#[derive(Deserialize)]
-struct EmbyUserDto {
-    Id: String,
-}
-
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub enum State {
     #[default]
     Start,
-    WaitingRegistrationUsername,
+    WaitingRegistrationInviteCode,
+    WaitingRegistrationUsername {
+        invite_code: String,
+    },
+    WaitingNewPassword,
+    WaitingLinkUsername,
+    WaitingLinkPassword {
+        emby_user_id: String,
+        username: String,
+    },
     WaitingRequestDatasource,
     WaitingRequestMediaType {
         data_source: String,
@@ -46,6 +49,10 @@ pub enum State {
         data_source: String,
         media_type: String,
     },
+    WaitingRequestSearchSelection {
+        data_source: String,
+        media_type: String,
+    },
     WaitingRequestConfirmation {
         data_source: String,
         media_type: String,
@@ -64,13 +71,17 @@ enum Command {
     /// NOOOOOO Check Out,
     CheckOut,
     /// Start the purchase procedure.
-    Start,
+    Start(String),
     /// Check the Chat ID,
     ChatID,
-    /// Register a new user.
-    Register,
+    /// Register a new user with an invite code.
+    Register(String),
+    /// Link an existing Emby account.
+    Link,
     /// Request a password reset.
     PasswordReset,
+    /// Set a new password.
+    SetPassword,
     /// Delete user account
     DeleteUser,
     /// Request a new media,
@@ -79,6 +90,22 @@ enum Command {
     Cancel,
     /// List all media requests.
     RequestList,
+    /// List your own media requests.
+    MyRequests,
+    /// Subscribe this chat to notifications.
+    Subscribe(String),
+    /// Unsubscribe this chat from notifications.
+    Unsubscribe(String),
+    /// List notification subscriptions of this chat.
+    Subscriptions,
+    /// Add or list internal notes of a media request.
+    Note(String),
+    /// Show the timeline of a media request.
+    Timeline(String),
+    /// Reject a media request with a reason.
+    Reject(String),
+    /// Create an invite code for registration.
+    Invite(String),
 }
 
 fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>> {
@@ -88,24 +115,71 @@ fn schema() -> UpdateHandler<Box<dyn std::error::Error + Send + Sync + 'static>>
         .branch(case![Command::CheckIn].endpoint(check_in))
         .branch(case![Command::CheckOut].endpoint(check_out))
         .branch(case![Command::ChatID].endpoint(chat_id))
-        .branch(case![Command::Register].endpoint(register_start))
+        .branch(case![Command::Start(args)].endpoint(start))
+        .branch(case![Command::Register(args)].endpoint(register_start))
+        .branch(case![Command::Link].endpoint(link_start))
         .branch(case![Command::PasswordReset].endpoint(password_reset))
+        .branch(case![Command::SetPassword].endpoint(set_password_start))
         .branch(case![Command::Request].endpoint(request_start))
         .branch(case![Command::DeleteUser].endpoint(delete_user_start))
         .branch(case![Command::Cancel].endpoint(cancel))
-        .branch(case![Command::RequestList].endpoint(request_list));
+        .branch(case![Command::RequestList].endpoint(request_list))
+        .branch(case![Command::MyRequests].endpoint(my_requests))
+        .branch(case![Command::Subscribe(args)].endpoint(subscribe))
+        .branch(case![Command::Unsubscribe(args)].endpoint(unsubscribe))
+        .branch(case![Command::Subscriptions].endpoint(list_subscriptions))
+        .branch(case![Command::Note(args)].endpoint(request_note))
+        .branch(case![Command::Timeline(args)].endpoint(request_timeline))
+        .branch(case![Command::Reject(args)].endpoint(reject_request))
+        .branch(case![Command::Invite(args)].endpoint(create_invite));
     let message_handler = Update::filter_message()
         .branch(command_handler)
-        .branch(case![State::WaitingRegistrationUsername].endpoint(register_username))
+        .branch(case![State::WaitingRegistrationInviteCode].endpoint(register_invite_code))
+        .branch(case![State::WaitingRegistrationUsername { invite_code }].endpoint(register_username))
+        .branch(case![State::WaitingNewPassword].endpoint(set_password_submit))
+        .branch(case![State::WaitingLinkUsername].endpoint(link_username))
+        .branch(case![State::WaitingLinkPassword { emby_user_id, username }].endpoint(link_password))
         .branch(case![State::WaitingRequestMediaID { data_source, media_type }].endpoint(request_confirmation))
+        .branch(case![State::WaitingRequestSearchSelection { data_source, media_type }].endpoint(request_confirmation))
         .branch(case![State::WaitingDeleteConfirmation].endpoint(delete_user_confirm))
         .branch(dptree::endpoint(invalid_state));
     let callback_query_handler = Update::filter_callback_query()
+        .branch(dptree::filter(|q: CallbackQuery| has_callback_prefix(&q, MY_REQUESTS_CALLBACK_PREFIX)).endpoint(handle_my_requests_callback))
+        .branch(dptree::filter(|q: CallbackQuery| has_callback_prefix(&q, REVIEW_CALLBACK_PREFIX)).endpoint(handle_review_callback))
+        .branch(dptree::filter(|q: CallbackQuery| has_callback_prefix(&q, LINK_REVIEW_CALLBACK_PREFIX)).endpoint(handle_link_review_callback))
+        .branch(case![State::WaitingLinkPassword { emby_user_id, username }].endpoint(link_request_approval))
         .branch(case![State::WaitingRequestDatasource].endpoint(request_media_type))
         .branch(case![State::WaitingRequestMediaType { data_source }].endpoint(request_media_id))
+        .branch(case![State::WaitingRequestSearchSelection { data_source, media_type }].endpoint(handle_search_selection))
         .branch(case![State::WaitingRequestConfirmation { data_source, media_type, media_id }].endpoint(handle_request_confirmation));
-    dialogue::enter::<Update, InMemStorage<State>, State, _>()
-        .branch(message_handler).branch(callback_query_handler)
+    // 群组成员变更不属于任何对话，放在对话状态之前处理
+    let chat_member_handler = Update::filter_chat_member().endpoint(handle_chat_member);
+    dptree::entry()
+        .branch(chat_member_handler)
+        .branch(
+            dialogue::enter::<Update, DieselStorage<State>, State, _>()
+                .branch(message_handler).branch(callback_query_handler)
+        )
+}
+
+async fn handle_chat_member(update: ChatMemberUpdated) -> HandlerResult {
+    // 机器人所在的其他群组也会收到成员变更，先过滤掉，避免每次都创建 Emby 客户端
+    let config = GroupMembershipConfig::from_env();
+    if config.group_chat_id != Some(update.chat.id) {
+        return Ok(());
+    }
+
+    let client = match EmbyClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("处理群组成员变更失败: {}", e);
+            return Ok(());
+        }
+    };
+    if let Err(e) = group_membership::handle_member_update(&client, &update, &config).await {
+        log::warn!("处理群组成员变更失败: {}", e);
+    }
+    Ok(())
 }
 
 async fn help(bot: Bot, msg: Message) -> HandlerResult {
@@ -141,7 +215,7 @@ async fn help(bot: Bot, msg: Message) -> HandlerResult {
             }
         }
         _ => {
-            bot.send_message(msg.chat.id, "可用命令：\n/help - 显示此帮助\n/register - 注册新用户\n/passwordreset - 将密码重置为空\n/request - 请求新媒体资源").await?;
+            bot.send_message(msg.chat.id, "可用命令：\n/help - 显示此帮助\n/register <邀请码> - 使用邀请码注册新用户\n/link - 绑定已有的 Emby 账户\n/setpassword - 设置新密码\n/passwordreset - 将密码重置为空\n/request - 请求新媒体资源\n/myrequests - 查看我的媒体请求\n/timeline - 查看请求处理记录").await?;
         }
     }
     Ok(())
@@ -173,7 +247,7 @@ async fn check_in(bot: Bot, msg: Message) -> HandlerResult {
                             }
                         };
                         if disabled_users.contains(&user.id.0) {
-                            let toronto_time = chrono::Utc::now().with_timezone(&chrono::FixedOffset::west(5*3600));
+                            let toronto_time = chrono::Utc::now().with_timezone(&chrono::FixedOffset::west_opt(5*3600).unwrap());
                             println!("Toronto Time: {}", toronto_time);
                             if toronto_time.hour() < 16 {
                                 let reply = bot.send_message(msg.chat.id, "抱歉，您不是我们的常旅客会员，我们无法在当地时间16:00之前为您办理登记入住。").await?;
@@ -230,7 +304,26 @@ async fn chat_id(bot: Bot, msg: Message) -> HandlerResult {
     Ok(())
 }
 
-async fn register_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+/// 处理 `/start`，带参数时视为邀请链接 `https://t.me/<bot>?start=<邀请码>`
+async fn start(bot: Bot, dialogue: MyDialogue, msg: Message, args: String) -> HandlerResult {
+    match msg.chat.kind {
+        ChatKind::Private(_) if !args.trim().is_empty() => {
+            if auth::check_registered(msg.chat.id.0) {
+                let username = auth::get_username(msg.chat.id.0);
+                bot.send_message(msg.chat.id, format!("您已经注册过了。用户名：{}", username)).await?;
+            } else if ensure_group_member(&bot, msg.chat.id).await? {
+                begin_registration(&bot, &dialogue, msg.chat.id, args.trim()).await?;
+            }
+        }
+        ChatKind::Private(_) => {
+            bot.send_message(msg.chat.id, "欢迎使用，请使用 /help 查看可用命令。").await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn register_start(bot: Bot, dialogue: MyDialogue, msg: Message, args: String) -> HandlerResult {
     match msg.chat.kind {
         ChatKind::Public(_) => {
             let reply = bot.send_message(msg.chat.id, "请在私聊中使用此命令。").await?;
@@ -242,16 +335,60 @@ async fn register_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> Handler
             if auth::check_registered(msg.chat.id.0) {
                 let username = auth::get_username(msg.chat.id.0);
                 bot.send_message(msg.chat.id, format!("您已经注册过了。用户名：{}", username)).await?;
+            } else if !ensure_group_member(&bot, msg.chat.id).await? {
+                return Ok(());
+            } else if args.trim().is_empty() {
+                bot.send_message(msg.chat.id, "注册需要邀请码，请输入您的邀请码：").await?;
+                dialogue.update(State::WaitingRegistrationInviteCode).await?;
             } else {
-                bot.send_message(msg.chat.id, "请输入您的用户名：").await?;
-                dialogue.update(State::WaitingRegistrationUsername).await?;
+                begin_registration(&bot, &dialogue, msg.chat.id, args.trim()).await?;
             }
         }
     }
     Ok(())
 }
 
-async fn register_username(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+async fn register_invite_code(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+    match msg.text() {
+        Some(code) if !code.starts_with('/') => {
+            begin_registration(&bot, &dialogue, msg.chat.id, code).await?;
+        }
+        _ => {
+            bot.send_message(msg.chat.id, "无效的邀请码，请重新输入，或使用 /cancel 取消。").await?;
+        }
+    }
+    Ok(())
+}
+
+/// 开启 `REGISTER_REQUIRE_GROUP_MEMBERSHIP` 时检查用户是否在群组中，不满足时回复原因并返回 false
+async fn ensure_group_member(bot: &Bot, chat_id: ChatId) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    // 私聊中 chat_id 即用户 ID
+    match group_membership::check_register_allowed(bot, &GroupMembershipConfig::from_env(), UserId(chat_id.0 as u64)).await {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            bot.send_message(chat_id, e).await?;
+            Ok(false)
+        }
+    }
+}
+
+/// 校验邀请码，通过后进入输入用户名的步骤。此时还不消耗使用次数
+async fn begin_registration(bot: &Bot, dialogue: &MyDialogue, chat_id: ChatId, code: &str) -> HandlerResult {
+    let mut conn = establish_connection();
+    match invite::validate(&mut conn, code) {
+        Ok(invite_code) => {
+            bot.send_message(chat_id, "邀请码有效，请输入您的用户名：").await?;
+            dialogue.update(State::WaitingRegistrationUsername { invite_code: invite_code.code }).await?;
+        }
+        Err(e) => {
+            bot.send_message(chat_id, format!("{}，请重新输入邀请码，或使用 /cancel 取消。", e)).await?;
+            dialogue.update(State::WaitingRegistrationInviteCode).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn register_username(bot: Bot, dialogue: MyDialogue, msg: Message, invite_code: String) -> HandlerResult {
     if let MessageKind::Common(common) = msg.kind {
         match common.media_kind {
             MediaKind::Text(text) => {
@@ -259,11 +396,21 @@ async fn register_username(bot: Bot, dialogue: MyDialogue, msg: Message) -> Hand
                     bot.send_message(msg.chat.id, "用户名不能以 / 开头，请重新输入。").await?;
                     return Ok(());
                 }
-                match submit_emby_register(text.text.clone()).await {
-                    Ok(emby_user_id) => {
-                        auth::register(msg.chat.id.0, text.text, emby_user_id);
+
+                let client = match EmbyClient::from_env() {
+                    Ok(client) => client,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        bot.send_message(msg.chat.id, "注册失败，请联系管理员。").await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
+                };
+                let mut conn = establish_connection();
+                match invite::register_with_invite(&client, &mut conn, &invite_code, msg.chat.id.0, &text.text).await {
+                    Ok(_) => {
                         bot.send_message(msg.chat.id, "注册成功。默认密码为空，请登录后自行修改。").await?;
-                    },
+                    }
                     Err(e) => {
                         bot.send_message(msg.chat.id, format!("注册失败。\n{}\n请重新使用 /register 开始注册流程。", e)).await?;
                     }
@@ -278,6 +425,228 @@ async fn register_username(bot: Bot, dialogue: MyDialogue, msg: Message) -> Hand
     Ok(())
 }
 
+async fn link_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+    match msg.chat.kind {
+        ChatKind::Public(_) => {
+            let reply = bot.send_message(msg.chat.id, "请在私聊中使用此命令。").await?;
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            bot.delete_message(msg.chat.id, msg.id).await?;
+            bot.delete_message(msg.chat.id, reply.id).await?;
+        }
+        _ => {
+            if auth::check_registered(msg.chat.id.0) {
+                let username = auth::get_username(msg.chat.id.0);
+                bot.send_message(msg.chat.id, format!("您已经绑定了 Emby 账户。用户名：{}", username)).await?;
+            } else if let Err(e) = account_link::check_attempt_limit(&mut establish_connection(), &LinkAttemptConfig::from_env(), msg.chat.id.0) {
+                bot.send_message(msg.chat.id, e.to_string()).await?;
+            } else {
+                bot.send_message(msg.chat.id, "请输入您已有的 Emby 用户名：").await?;
+                dialogue.update(State::WaitingLinkUsername).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn link_username(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+    let username = match msg.text() {
+        Some(text) if !text.starts_with('/') => text.trim().to_string(),
+        _ => {
+            bot.send_message(msg.chat.id, "无效的用户名，请重新输入，或使用 /cancel 取消。").await?;
+            return Ok(());
+        }
+    };
+
+    let client = match EmbyClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("{}", e);
+            bot.send_message(msg.chat.id, "绑定失败，请联系管理员。").await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+    // 用户不存在或已被绑定时同样要求输入密码，不透露 Emby 中有哪些账户
+    let (emby_user_id, emby_username) = match emby::client::find_user_by_name(&client, &username).await {
+        Ok(Some(user)) => (user.id, user.name),
+        Ok(None) => (String::new(), username.clone()),
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("绑定失败。\n{}\n请联系管理员。", e)).await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+
+    let keyboard = InlineKeyboardMarkup::new([[InlineKeyboardButton::callback("申请管理员审核", LINK_APPROVAL_CALLBACK)]]);
+    bot.send_message(msg.chat.id, format!(
+        "请发送 Emby 账户 {} 的密码完成验证，密码消息会被立即删除。\n如果无法提供密码，可以申请管理员审核。",
+        username,
+    ))
+        .reply_markup(keyboard)
+        .await?;
+    dialogue.update(State::WaitingLinkPassword { emby_user_id, username: emby_username }).await?;
+    Ok(())
+}
+
+async fn link_password(bot: Bot, dialogue: MyDialogue, msg: Message, (_emby_user_id, username): (String, String)) -> HandlerResult {
+    // 不在聊天记录中保留密码，无论内容是否有效都先删除
+    if let Err(e) = bot.delete_message(msg.chat.id, msg.id).await {
+        log::warn!("删除密码消息失败: {}", e);
+    }
+    let password = match msg.text() {
+        Some(text) if !text.starts_with('/') => text.to_string(),
+        _ => {
+            bot.send_message(msg.chat.id, "请发送密码，或使用 /cancel 取消。").await?;
+            return Ok(());
+        }
+    };
+
+    let client = match EmbyClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("{}", e);
+            bot.send_message(msg.chat.id, "绑定失败，请联系管理员。").await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    match account_link::link_with_password(&client, &mut conn, &LinkAttemptConfig::from_env(), msg.chat.id.0, &username, &password).await {
+        Ok(_) => {
+            bot.send_message(msg.chat.id, format!("绑定成功，已关联 Emby 账户 {}。", username)).await?;
+            dialogue.exit().await?;
+        }
+        Err(LinkError::Unauthorized(message)) => {
+            bot.send_message(msg.chat.id, format!("{}请重新发送密码，或使用 /cancel 取消。", message)).await?;
+        }
+        Err(LinkError::TooManyAttempts(message)) => {
+            bot.send_message(msg.chat.id, message).await?;
+            dialogue.exit().await?;
+        }
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("绑定失败。\n{}", e)).await?;
+            dialogue.exit().await?;
+        }
+    }
+    Ok(())
+}
+
+const LINK_APPROVAL_CALLBACK: &str = "link:approval";
+const LINK_REVIEW_CALLBACK_PREFIX: &str = "linkreview:";
+
+/// 无法提供密码时提交绑定申请，推送到管理员会话审核
+async fn link_request_approval(bot: Bot, dialogue: MyDialogue, q: CallbackQuery, (emby_user_id, username): (String, String)) -> HandlerResult {
+    if q.data.as_deref() != Some(LINK_APPROVAL_CALLBACK) {
+        bot.answer_callback_query(q.id).await?;
+        return Ok(());
+    }
+    bot.answer_callback_query(q.id.clone()).await?;
+
+    let chat_id = match review_chat_id() {
+        Some(chat_id) => chat_id,
+        None => {
+            bot.send_message(dialogue.chat_id(), "暂未开放管理员审核，请通过密码验证绑定。").await?;
+            return Ok(());
+        }
+    };
+
+    let telegram_id = q.from.id.0 as i64;
+    let mut conn = establish_connection();
+    if let Err(e) = account_link::check_attempt_limit(&mut conn, &LinkAttemptConfig::from_env(), telegram_id) {
+        bot.send_message(dialogue.chat_id(), e.to_string()).await?;
+        dialogue.exit().await?;
+        return Ok(());
+    }
+    // 用户名不存在时无法提交申请，计入失败次数，避免借审核申请探测账户
+    if emby_user_id.is_empty() {
+        if let Err(e) = account_link::record_failed_attempt(&mut conn, telegram_id) {
+            log::warn!("记录绑定验证失败次数失败: {}", e);
+        }
+        bot.send_message(dialogue.chat_id(), "无法提交审核申请，请确认用户名后使用 /link 重新开始。").await?;
+        dialogue.exit().await?;
+        return Ok(());
+    }
+
+    let request = match account_link::create_request(&mut conn, telegram_id, &emby_user_id, &username) {
+        Ok(request) => request,
+        Err(e) => {
+            bot.send_message(dialogue.chat_id(), e.to_string()).await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+
+    let keyboard = InlineKeyboardMarkup::new([[
+        InlineKeyboardButton::callback("同意绑定", format!("{}{}:approve", LINK_REVIEW_CALLBACK_PREFIX, request.id)),
+        InlineKeyboardButton::callback("拒绝", format!("{}{}:reject", LINK_REVIEW_CALLBACK_PREFIX, request.id)),
+    ]]);
+    let text = format!(
+        "绑定申请 #{}\n\n👤 Telegram：{}\n🎬 Emby 账户：{} ({})",
+        request.id,
+        user_display(&q.from),
+        request.emby_username,
+        request.emby_user_id,
+    );
+    if let Err(e) = bot.send_message(chat_id, text).reply_markup(keyboard).await {
+        log::warn!("Failed to post link request {}: {}", request.id, e);
+    }
+
+    bot.send_message(dialogue.chat_id(), "已提交绑定申请，管理员审核后会通知您。").await?;
+    dialogue.exit().await?;
+    Ok(())
+}
+
+async fn handle_link_review_callback(bot: Bot, q: CallbackQuery) -> HandlerResult {
+    if !auth::check_admin(q.from.id.0 as i64) {
+        bot.answer_callback_query(q.id).text("抱歉，您没有权限执行此操作。").show_alert(true).await?;
+        return Ok(());
+    }
+
+    let parsed = q.data.as_deref()
+        .and_then(|data| data.strip_prefix(LINK_REVIEW_CALLBACK_PREFIX))
+        .and_then(|data| data.split_once(':'))
+        .and_then(|(request_id, action)| Some((request_id.parse::<i32>().ok()?, action == "approve")));
+    let (request_id, approve) = match parsed {
+        Some(parsed) => parsed,
+        None => {
+            bot.answer_callback_query(q.id).text("未知的操作").await?;
+            return Ok(());
+        }
+    };
+
+    let request = match account_link::review_request(&mut establish_connection(), request_id, approve, q.from.id.0 as i64) {
+        Ok(request) => request,
+        Err(e) => {
+            bot.answer_callback_query(q.id).text(e.to_string()).show_alert(true).await?;
+            return Ok(());
+        }
+    };
+    let result = if request.status == link_request_status::APPROVED { "已同意" } else { "已拒绝" };
+    bot.answer_callback_query(q.id.clone()).text(format!("绑定申请 #{} {}", request_id, result)).await?;
+
+    let notice = if approve {
+        format!("您绑定 Emby 账户 {} 的申请已通过。", request.emby_username)
+    } else {
+        format!("您绑定 Emby 账户 {} 的申请未通过，如有疑问请联系管理员。", request.emby_username)
+    };
+    if bot.send_message(ChatId(request.telegram_id), notice).await.is_err() {
+        log::warn!("Failed to notify user {} about link request {}", request.telegram_id, request_id);
+    }
+
+    if let Some(message) = q.message {
+        let text = format!(
+            "{}\n\n📊 {} — 由 {} 于 {} 处理",
+            message.text().unwrap_or_default(),
+            result,
+            user_display(&q.from),
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        );
+        bot.edit_message_text(message.chat.id, message.id, text).await?;
+    }
+    Ok(())
+}
+
 async fn delete_user_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
     match msg.chat.kind {
         ChatKind::Public(_) => {
@@ -312,7 +681,11 @@ async fn delete_user_confirm(bot: Bot, dialogue: MyDialogue, msg: Message) -> Ha
                     let emby_user_id = auth::get_emby_id(msg.chat.id.0);
 
                     // Call Emby API to delete the user
-                    match crate::delete_emby_user(&emby_user_id).await {
+                    let result = match EmbyClient::from_env() {
+                        Ok(client) => client.delete_user(&emby_user_id).await,
+                        Err(e) => Err(e),
+                    };
+                    match result {
                         Ok(_) => {
                             // If Emby deletion successful, delete from database
                             match auth::delete_user(msg.chat.id.0) {
@@ -352,8 +725,10 @@ async fn request_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerR
             bot.delete_message(msg.chat.id, reply.id).await?;
         }
         _ => {
-            let data_sources = ["TMDB", "BGM.TV"]
-                .map(|product| InlineKeyboardButton::callback(product, product));
+            let data_sources: Vec<InlineKeyboardButton> = scraper::provider::data_sources()
+                .into_iter()
+                .map(|product| InlineKeyboardButton::callback(product, product))
+                .collect();
             bot.send_message(msg.chat.id, "请选择您的数据来源")
                 .reply_markup(InlineKeyboardMarkup::new([data_sources]))
                 .await?;
@@ -363,7 +738,7 @@ async fn request_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerR
     Ok(())
 }
 
-async fn password_reset(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+async fn password_reset(bot: Bot, _dialogue: MyDialogue, msg: Message) -> HandlerResult {
     match msg.chat.kind {
         ChatKind::Public(_) => {
             let reply = bot.send_message(msg.chat.id, "请在私聊中使用此命令。").await?;
@@ -377,7 +752,17 @@ async fn password_reset(bot: Bot, dialogue: MyDialogue, msg: Message) -> Handler
                 return Ok(());
             }
             let emby_user_id = auth::get_emby_id(msg.chat.id.0);
-            match submit_emby_password_update(emby_user_id).await {
+            let result = match EmbyClient::from_env() {
+                Ok(client) => password_reset::reset_password(
+                    &client,
+                    &mut establish_connection(),
+                    &PasswordResetConfig::from_env(),
+                    msg.chat.id.0,
+                    &emby_user_id,
+                ).await,
+                Err(e) => Err(e.to_string()),
+            };
+            match result {
                 Ok(_) => {
                     bot.send_message(msg.chat.id, "密码重置成功，现在密码为空。").await?;
                 },
@@ -390,10 +775,79 @@ async fn password_reset(bot: Bot, dialogue: MyDialogue, msg: Message) -> Handler
     Ok(())
 }
 
+async fn set_password_start(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+    match msg.chat.kind {
+        ChatKind::Public(_) => {
+            let reply = bot.send_message(msg.chat.id, "请在私聊中使用此命令。").await?;
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            bot.delete_message(msg.chat.id, msg.id).await?;
+            bot.delete_message(msg.chat.id, reply.id).await?;
+        }
+        _ => {
+            if !auth::check_registered(msg.chat.id.0) {
+                bot.send_message(msg.chat.id, "您还没有注册，无法使用该命令。").await?;
+                return Ok(());
+            }
+            let config = PasswordResetConfig::from_env();
+            if let Err(e) = password_reset::check_rate_limit(&mut establish_connection(), &config, msg.chat.id.0) {
+                bot.send_message(msg.chat.id, e).await?;
+                return Ok(());
+            }
+            bot.send_message(msg.chat.id, format!(
+                "请发送新密码，消息会在读取后立即删除。\n{}\n使用 /cancel 取消。",
+                config.requirements(),
+            )).await?;
+            dialogue.update(State::WaitingNewPassword).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn set_password_submit(bot: Bot, dialogue: MyDialogue, msg: Message) -> HandlerResult {
+    // 不在聊天记录中保留密码，无论内容是否有效都先删除
+    if let Err(e) = bot.delete_message(msg.chat.id, msg.id).await {
+        log::warn!("删除密码消息失败: {}", e);
+    }
+    let password = match msg.text() {
+        Some(text) if !text.starts_with('/') => text.to_string(),
+        _ => {
+            bot.send_message(msg.chat.id, "请发送新密码，或使用 /cancel 取消。").await?;
+            return Ok(());
+        }
+    };
+
+    let config = PasswordResetConfig::from_env();
+    if let Err(e) = password_reset::validate_strength(&config, &password) {
+        bot.send_message(msg.chat.id, format!("{}\n请重新发送新密码，或使用 /cancel 取消。", e)).await?;
+        return Ok(());
+    }
+
+    let client = match EmbyClient::from_env() {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("{}", e);
+            bot.send_message(msg.chat.id, "密码设置失败，请联系管理员。").await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+    let emby_user_id = auth::get_emby_id(msg.chat.id.0);
+    match password_reset::set_password(&client, &mut establish_connection(), &config, msg.chat.id.0, &emby_user_id, &password).await {
+        Ok(()) => {
+            bot.send_message(msg.chat.id, "新密码设置成功。").await?;
+        }
+        Err(e) => {
+            bot.send_message(msg.chat.id, format!("密码设置失败。\n{}", e)).await?;
+        }
+    }
+    dialogue.exit().await?;
+    Ok(())
+}
+
 async fn request_media_type(bot: Bot, dialogue: MyDialogue, q: CallbackQuery) -> HandlerResult {
     if let Some(media_source) = &q.data {
         match media_source.as_str() {
-            "TMDB" | "BGM.TV" => {
+            source if scraper::provider::data_sources().contains(&source) => {
                 let media_types = ["电影", "电视剧"]
                     .map(|product| InlineKeyboardButton::callback(product, product));
                 bot.send_message(dialogue.chat_id(), "请选择您要请求的媒体类型")
@@ -417,7 +871,7 @@ async fn request_media_id(bot: Bot, dialogue: MyDialogue, data_source: String, q
         match media_type.as_str() {
             "电影" | "电视剧" => {
                 let media_type = media_type.clone();
-                bot.send_message(dialogue.chat_id(), format!("请输入您要从 {} 请求的 {} ID: ", data_source, media_type)).await?;
+                bot.send_message(dialogue.chat_id(), format!("请输入您要从 {} 请求的 {} ID，或直接输入标题进行搜索: ", data_source, media_type)).await?;
                 dialogue.update(State::WaitingRequestMediaID { data_source, media_type }).await?;
             },
             _ => {
@@ -433,94 +887,184 @@ async fn request_confirmation(bot: Bot, dialogue: MyDialogue, msg: Message, data
     if let MessageKind::Common(common) = msg.kind {
         match common.media_kind {
             MediaKind::Text(text) => {
-                if text.text.chars().any(|c| !c.is_digit(10)) {
-                    bot.send_message(msg.chat.id, "媒体ID应为纯数字，请重新输入。").await?;
+                let input = text.text.trim().to_string();
+                if input.is_empty() || input.starts_with('/') {
+                    bot.send_message(msg.chat.id, "请输入媒体ID或标题关键词。").await?;
                     return Ok(());
                 }
 
-                // 发送"正在获取媒体信息..."消息
-                let loading_msg = bot.send_message(msg.chat.id, "正在获取媒体信息...").await?;
-
-                // 根据数据源和媒体类型确定API参数
-                let (api_source, api_media_type) = match data.0.as_str() {
-                    "TMDB" => {
-                        let media_type = match data.1.as_str() {
-                            "电影" => "movie",
-                            "电视剧" => "tv",
-                            _ => {
-                                bot.edit_message_text(msg.chat.id, loading_msg.id, "未知的媒体类型，请重新开始。").await?;
-                                return Ok(());
-                            }
-                        };
-                        ("tmdb", media_type)
-                    },
-                    "BGM.TV" => ("bgm", "subject"),
-                    _ => {
-                        bot.edit_message_text(msg.chat.id, loading_msg.id, "未知的数据源，请重新开始。").await?;
-                        return Ok(());
-                    }
-                };
+                if input.chars().all(|c| c.is_ascii_digit()) {
+                    show_request_confirmation(bot, dialogue, data, input).await?;
+                } else {
+                    request_search(bot, dialogue, data, input).await?;
+                }
+            }
+            _ => {
+                bot.send_message(msg.chat.id, "无效的输入，请输入媒体ID或标题关键词。").await?;
+            }
+        }
+    }
+    Ok(())
+}
 
-                // 调用刮削API获取媒体信息
-                match scraper::scrape_media_info(api_source, api_media_type, &text.text).await {
-                    Ok(media_info) => {
-                        // 删除加载消息
-                        bot.delete_message(msg.chat.id, loading_msg.id).await.ok();
-
-                        // 构建媒体链接
-                        let media_link = match data.0.as_str() {
-                            "TMDB" => {
-                                match data.1.as_str() {
-                                    "电影" => format!("https://www.themoviedb.org/movie/{}", text.text),
-                                    "电视剧" => format!("https://www.themoviedb.org/tv/{}", text.text),
-                                    _ => format!("https://www.themoviedb.org/{}/{}", api_media_type, text.text)
-                                }
-                            },
-                            "BGM.TV" => format!("https://bgm.tv/subject/{}", text.text),
-                            _ => format!("{}/{}", data.0, text.text)
-                        };
+async fn request_search(bot: Bot, dialogue: MyDialogue, data: (String, String), query: String) -> HandlerResult {
+    let chat_id = dialogue.chat_id();
+    let loading_msg = bot.send_message(chat_id, "正在搜索...").await?;
 
-                        let keyboard = InlineKeyboardMarkup::new(vec![
-                            vec![InlineKeyboardButton::callback("确认", "confirm")],
-                            vec![InlineKeyboardButton::callback("取消", "cancel")],
-                        ]);
-
-                        let confirmation_text = format!(
-                            "您要请求的媒体信息：\n\n📺 标题：{}\n🔗 链接：{}\n📝 简介：{}\n\n请确认是否提交请求：",
-                            media_info.title,
-                            media_link,
-                            if media_info.summary.is_empty() { "暂无简介" } else { &media_info.summary }
-                        );
-
-                        bot.send_message(msg.chat.id, confirmation_text)
-                            .reply_markup(keyboard)
-                            .await?;
-
-                        dialogue.update(State::WaitingRequestConfirmation {
-                            data_source: data.0,
-                            media_type: data.1,
-                            media_id: text.text
-                        }).await?;
-                    },
-                    Err(error) => {
-                        // 删除加载消息并显示错误
-                        bot.edit_message_text(
-                            msg.chat.id, 
-                            loading_msg.id, 
-                            format!("获取媒体信息失败：{}\n\n请检查媒体ID是否正确，或稍后重试。", error)
-                        ).await?;
-                        dialogue.exit().await?;
-                    }
-                }
+    let provider = match scraper::provider::provider_for_selection(&data.0, &data.1) {
+        Some(provider) => provider,
+        None => {
+            bot.edit_message_text(chat_id, loading_msg.id, "未知的数据源或媒体类型，请重新开始。").await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+
+    let results = match provider.search(&scraper::ScraperConfig::from_env(), &query).await {
+        Ok(results) => results,
+        Err(error) => {
+            bot.edit_message_text(chat_id, loading_msg.id, format!("搜索失败：{}\n\n请稍后重试，或直接输入媒体ID。", error)).await?;
+            return Ok(());
+        }
+    };
+
+    if results.is_empty() {
+        bot.edit_message_text(chat_id, loading_msg.id, format!("没有找到与「{}」相关的结果，请换个关键词或直接输入媒体ID。", query)).await?;
+        return Ok(());
+    }
+
+    bot.delete_message(chat_id, loading_msg.id).await.ok();
+
+    let labels: Vec<String> = results.iter().enumerate().map(|(index, result)| {
+        match &result.year {
+            Some(year) => format!("{}. {} ({})", index + 1, result.title, year),
+            None => format!("{}. {}", index + 1, result.title),
+        }
+    }).collect();
+
+    // 先发送海报，再发送选择按钮
+    let mut posters: Vec<(InputFile, String)> = results.iter().zip(labels.iter())
+        .filter_map(|(result, label)| {
+            let url = reqwest::Url::parse(&result.poster).ok()?;
+            Some((InputFile::url(url), label.clone()))
+        })
+        .collect();
+    let poster_result = match posters.len() {
+        0 => Ok(()),
+        1 => {
+            let (photo, caption) = posters.remove(0);
+            bot.send_photo(chat_id, photo).caption(caption).await.map(|_| ())
+        }
+        _ => {
+            let media = posters.into_iter()
+                .map(|(photo, caption)| InputMedia::Photo(InputMediaPhoto::new(photo).caption(caption)))
+                .collect::<Vec<_>>();
+            bot.send_media_group(chat_id, media).await.map(|_| ())
+        }
+    };
+    if let Err(e) = poster_result {
+        log::warn!("Failed to send search result posters: {:?}", e);
+    }
+
+    let buttons: Vec<Vec<InlineKeyboardButton>> = results.iter().zip(labels)
+        .map(|(result, label)| vec![InlineKeyboardButton::callback(label, format!("select:{}", result.id))])
+        .collect();
+    bot.send_message(chat_id, format!("找到以下与「{}」相关的结果，请选择：", query))
+        .reply_markup(InlineKeyboardMarkup::new(buttons))
+        .await?;
+
+    dialogue.update(State::WaitingRequestSearchSelection {
+        data_source: data.0,
+        media_type: data.1,
+    }).await?;
+    Ok(())
+}
+
+async fn handle_search_selection(bot: Bot, dialogue: MyDialogue, q: CallbackQuery, data: (String, String)) -> HandlerResult {
+    bot.answer_callback_query(q.id.clone()).await?;
+    if let Some(choice) = &q.data {
+        match choice.strip_prefix("select:") {
+            Some(media_id) if !media_id.is_empty() && media_id.chars().all(|c| c.is_ascii_digit()) => {
+                show_request_confirmation(bot, dialogue, data, media_id.to_string()).await?;
             }
             _ => {
-                bot.send_message(msg.chat.id, "无效的ID，ID应为纯数字，请重新输入。").await?;
+                bot.send_message(dialogue.chat_id(), "无效的选择，请重新选择或输入媒体ID。").await?;
             }
         }
     }
     Ok(())
 }
 
+async fn show_request_confirmation(bot: Bot, dialogue: MyDialogue, data: (String, String), media_id: String) -> HandlerResult {
+    let chat_id = dialogue.chat_id();
+
+    // 发送"正在获取媒体信息..."消息
+    let loading_msg = bot.send_message(chat_id, "正在获取媒体信息...").await?;
+
+    let provider = match scraper::provider::provider_for_selection(&data.0, &data.1) {
+        Some(provider) => provider,
+        None => {
+            bot.edit_message_text(chat_id, loading_msg.id, "未知的数据源或媒体类型，请重新开始。").await?;
+            dialogue.exit().await?;
+            return Ok(());
+        }
+    };
+
+    // 调用刮削API获取媒体信息
+    let mut conn = establish_connection();
+    match scraper::cache::lookup(&mut conn, &scraper::ScraperConfig::from_env(), provider, &media_id).await {
+        Ok(media_info) => {
+            // 书籍、游戏等条目不能请求，让用户重新输入
+            if let Err(reason) = provider.check_requestable(&media_info) {
+                bot.edit_message_text(chat_id, loading_msg.id, format!("{}\n\n请输入其他媒体ID或标题关键词。", reason)).await?;
+                dialogue.update(State::WaitingRequestMediaID {
+                    data_source: data.0,
+                    media_type: data.1,
+                }).await?;
+                return Ok(());
+            }
+
+            // 删除加载消息
+            bot.delete_message(chat_id, loading_msg.id).await.ok();
+
+            // 构建媒体链接
+            let media_link = provider.link(&media_id);
+
+            let keyboard = InlineKeyboardMarkup::new(vec![
+                vec![InlineKeyboardButton::callback("确认", "confirm")],
+                vec![InlineKeyboardButton::callback("取消", "cancel")],
+            ]);
+
+            let confirmation_text = format!(
+                "您要请求的媒体信息：\n\n📺 标题：{}\n🔗 链接：{}\n📝 简介：{}\n\n请确认是否提交请求：",
+                media_info.display_title(),
+                media_link,
+                if media_info.summary.is_empty() { "暂无简介" } else { &media_info.summary }
+            );
+
+            bot.send_message(chat_id, confirmation_text)
+                .reply_markup(keyboard)
+                .await?;
+
+            dialogue.update(State::WaitingRequestConfirmation {
+                data_source: data.0,
+                media_type: data.1,
+                media_id,
+            }).await?;
+        },
+        Err(error) => {
+            // 删除加载消息并显示错误
+            bot.edit_message_text(
+                chat_id,
+                loading_msg.id,
+                format!("获取媒体信息失败：{}\n\n请检查媒体ID是否正确，或稍后重试。", error)
+            ).await?;
+            dialogue.exit().await?;
+        }
+    }
+    Ok(())
+}
+
 async fn handle_request_confirmation(bot: Bot, dialogue: MyDialogue, q: CallbackQuery, (data_source, media_type, media_id): (String, String, String), ) -> HandlerResult {
     if let Some(choice) = q.data {
         match choice.as_str() {
@@ -532,70 +1076,87 @@ async fn handle_request_confirmation(bot: Bot, dialogue: MyDialogue, q: Callback
                 let mut conn = establish_connection();
                 
                 // 根据data_source和media_type确定实际的source字段值
-                let actual_source = match data_source.as_str() {
-                    "TMDB" => {
-                        match media_type.as_str() {
-                            "电影" => "TMDB/MV".to_string(),
-                            "电视剧" => "TMDB/TV".to_string(),
-                            _ => data_source.clone(), // 兜底，保持原值
-                        }
-                    },
-                    "BGM.TV" => data_source.clone(), // BGM.TV不需要区分
-                    _ => data_source.clone(), // 其他情况保持原值
+                let provider = match scraper::provider::provider_for_selection(&data_source, &media_type) {
+                    Some(provider) => provider,
+                    None => {
+                        bot.send_message(dialogue.chat_id(), "未知的数据源或媒体类型，请重新开始。").await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
                 };
+                let actual_source = provider.source().to_string();
                 
-                // 检查是否已经存在相同的请求
-                let existing_request = media_requests::table
-                    .filter(media_requests::source.eq(&actual_source))
-                    .filter(media_requests::media_id.eq(&media_id))
-                    .select(MediaRequest::as_select())
-                    .first(&mut conn)
-                    .optional()?;
-                
-                if existing_request.is_some() {
-                    bot.send_message(dialogue.chat_id(), "该媒体资源已经有人提交过请求了，请勿重复提交。").await?;
-                    dialogue.exit().await?;
-                    return Ok(());
-                }
-                
-                // 创建新的媒体请求
-                let new_request = NewMediaRequest {
-                    source: actual_source,
-                    media_id: media_id.clone(),
-                    request_user: request_user_id,
-                    status: media_request_status::SUBMITTED,
+                // 新提交或重新提交需要检查配额，管理员和关注已有请求不受限制
+                let quota = (!auth::check_admin(request_user_id)).then(RequestQuotaConfig::from_env);
+                let outcome = match media_request::submit(&mut conn, &actual_source, &media_id, request_user_id, quota.as_ref()) {
+                    Ok(outcome) => outcome,
+                    Err(e @ media_request::MediaRequestError::QuotaExceeded(_)) => {
+                        bot.send_message(dialogue.chat_id(), e.to_string()).await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        bot.send_message(dialogue.chat_id(), format!("提交请求失败：{}", e)).await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
                 };
-                
-                // 插入到数据库并获取ID
-                diesel::insert_into(media_requests::table)
-                    .values(&new_request)
-                    .execute(&mut conn)?;
-                
-                // 获取刚插入的请求ID
-                let inserted_request: MediaRequest = media_requests::table
-                    .filter(media_requests::source.eq(&new_request.source))
-                    .filter(media_requests::media_id.eq(&new_request.media_id))
-                    .filter(media_requests::request_user.eq(&new_request.request_user))
-                    .order(media_requests::created_at.desc())
-                    .first(&mut conn)?;
-                
-                // 重新获取媒体信息并保存到media表
-                let (api_source, api_media_type) = match data_source.as_str() {
-                    "TMDB" => {
-                        let media_type_api = match media_type.as_str() {
-                            "电影" => "movie",
-                            "电视剧" => "tv",
-                            _ => "movie", // 兜底
-                        };
-                        ("tmdb", media_type_api)
-                    },
-                    "BGM.TV" => ("bgm", "subject"),
-                    _ => ("unknown", "unknown"),
+
+                let inserted_request = match outcome {
+                    // 已取消、不符合规范或已移除的请求重新打开
+                    media_request::SubmitOutcome::Reopened { previous, request } => {
+                        // 原请求者已转为关注者，用更新后的记录通知，跳过新请求者本人
+                        media_request::notify_followers(&mut conn, &bot, &request, media_request_status::SUBMITTED, None).await;
+                        media_request::notify_subscribers(&bot, &previous, media_request_status::SUBMITTED, None).await;
+                        request
+                    }
+                    // 处理中的请求：把后来的用户记为关注者，状态变更时一并通知
+                    media_request::SubmitOutcome::Followed { request, newly } => {
+                        bot.send_message(
+                            dialogue.chat_id(),
+                            format!(
+                                "该媒体资源已经有人提交过请求了，{}状态变更时会通知您。\n当前共有 {} 人想看。",
+                                if newly { "已将您加入关注，" } else { "您已在关注列表中，" },
+                                media_request::vote_count(&mut conn, request.id).unwrap_or(1),
+                            ),
+                        ).await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
+                    media_request::SubmitOutcome::AlreadyRequested(_) => {
+                        bot.send_message(dialogue.chat_id(), "您已经提交过该媒体的请求了，请耐心等待处理。").await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
+                    media_request::SubmitOutcome::Unavailable(existing) => {
+                        bot.send_message(
+                            dialogue.chat_id(),
+                            format!("该媒体资源已经有人提交过请求了（当前状态：{}），请勿重复提交。", media_request::status_text(existing.status)),
+                        ).await?;
+                        dialogue.exit().await?;
+                        return Ok(());
+                    }
+                    media_request::SubmitOutcome::Created(request) => {
+                        // 重新提交已通过 notify_subscribers 通知，这里只通知新创建的请求
+                        notification::broadcast(
+                            &bot,
+                            notification::category::REQUEST_STATUS,
+                            None,
+                            &format!(
+                                "收到新的媒体请求 #{}：\n\n📁 来源：{}\n🎬 媒体ID：{}",
+                                request.id, request.source, request.media_id
+                            ),
+                        ).await;
+                        request
+                    }
                 };
-                
+
                 // 刮削并保存媒体信息
-                if let Ok(media_info) = scraper::scrape_media_info(api_source, api_media_type, &media_id).await {
-                    match scraper::save_media_to_db(&mut conn, inserted_request.id, &media_info) {
+                // 重新获取媒体信息并保存到media表
+                // 确认前刚刮削过，通常会命中缓存
+                let media_info = scraper::cache::lookup(&mut conn, &scraper::ScraperConfig::from_env(), provider, &media_id).await.ok();
+                if let Some(media_info) = &media_info {
+                    match scraper::save_media_to_db(&mut conn, inserted_request.id, media_info) {
                         Ok(_) => {
                             // 媒体信息保存成功
                         },
@@ -605,7 +1166,11 @@ async fn handle_request_confirmation(bot: Bot, dialogue: MyDialogue, q: Callback
                         }
                     }
                 }
-                
+
+                // 推送审核卡片到管理员会话
+                let link = provider.link(&media_id);
+                post_review_card(&bot, &inserted_request, media_info.as_ref(), &link, &q.from).await;
+
                 bot.send_message(dialogue.chat_id(), "请求已提交成功！我们会尽快处理您的请求。").await?;
                 dialogue.exit().await?;
             }
@@ -650,13 +1215,7 @@ async fn request_list(bot: Bot, msg: Message) -> HandlerResult {
 
             let mut csv_content = "ID,Source,Media ID,Request User,Status,Created At,Updated At\n".to_string();
             for request in &requests {
-                let status_text = match request.status {
-                    0 => "已提交",
-                    1 => "已入库", 
-                    2 => "被取消",
-                    3 => "不符合规范",
-                    _ => "未知状态",
-                };
+                let status_text = media_request::status_text(request.status);
                 csv_content.push_str(&format!(
                     "{},{},{},{},{},{},{}\n",
                     request.id,
@@ -685,75 +1244,567 @@ async fn request_list(bot: Bot, msg: Message) -> HandlerResult {
     Ok(())
 }
 
-async fn invalid_state(bot: Bot, msg: Message) -> HandlerResult {
+const SUBSCRIBE_USAGE: &str = "用法：/subscribe <类别> [媒体库] [chat:<会话ID>]\n类别：movies（新电影入库）、episodes（新剧集入库）、requests（媒体请求状态变更）\n不指定媒体库则接收全部媒体库的通知，不指定会话则订阅当前会话。";
+const UNSUBSCRIBE_USAGE: &str = "用法：/unsubscribe <类别|all> [媒体库] [chat:<会话ID>]";
+
+struct SubscriptionArgs {
+    category: Option<&'static str>,
+    library: Option<String>,
+    chat_id: Option<i64>,
+}
+
+/// 解析 `<类别> [媒体库] [chat:<会话ID>]`，类别为 all 时 category 为 None
+fn parse_subscription_args(args: &str) -> Option<SubscriptionArgs> {
+    let mut tokens = args.split_whitespace();
+    let category = match tokens.next()? {
+        "all" => None,
+        category => Some(notification::parse_category(category)?),
+    };
+
+    let mut chat_id = None;
+    let mut library = Vec::new();
+    for token in tokens {
+        match token.strip_prefix("chat:") {
+            Some(id) => chat_id = Some(id.parse::<i64>().ok()?),
+            None => library.push(token),
+        }
+    }
+
+    Some(SubscriptionArgs {
+        category,
+        library: (!library.is_empty()).then(|| library.join(" ")),
+        chat_id,
+    })
+}
+
+/// 订阅管理命令仅限管理员使用，返回发送者 ID
+async fn require_admin(bot: &Bot, msg: &Message) -> Result<Option<i64>, Box<dyn std::error::Error + Send + Sync>> {
+    match msg.from() {
+        Some(user) if auth::check_admin(user.id.0 as i64) => Ok(Some(user.id.0 as i64)),
+        _ => {
+            bot.send_message(msg.chat.id, "抱歉，您没有权限使用这个命令。").await?;
+            Ok(None)
+        }
+    }
+}
+
+const INVITE_USAGE: &str = "用法：/invite [可用次数] [有效天数]\n默认可用 1 次、永不过期。";
+
+async fn create_invite(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    let admin_id = match require_admin(&bot, &msg).await? {
+        Some(admin_id) => admin_id,
+        None => return Ok(()),
+    };
+
+    let mut parts = args.split_whitespace();
+    let max_uses = match parts.next().map(str::parse::<i32>) {
+        None => 1,
+        Some(Ok(max_uses)) => max_uses,
+        Some(Err(_)) => {
+            bot.send_message(msg.chat.id, INVITE_USAGE).await?;
+            return Ok(());
+        }
+    };
+    let expires_in_secs = match parts.next().map(str::parse::<i64>) {
+        None => None,
+        Some(Ok(days)) => Some(days.saturating_mul(86400)),
+        Some(Err(_)) => {
+            bot.send_message(msg.chat.id, INVITE_USAGE).await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    let reply = match invite::create_invite(&mut conn, admin_id, max_uses, expires_in_secs) {
+        Ok(invite_code) => {
+            let mut lines = vec![
+                format!("邀请码：{}", invite_code.code),
+                format!("可用次数：{}", invite_code.max_uses),
+                format!("过期时间：{}", invite_code.expires_at.as_deref().map(|at| format!("{} (UTC)", at)).unwrap_or_else(|| "永不过期".to_string())),
+            ];
+            if let Some(link) = invite::deep_link(&invite_code.code) {
+                lines.push(format!("邀请链接：{}", link));
+            }
+            lines.join("\n")
+        }
+        Err(e) => format!("创建邀请码失败：{}", e),
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+async fn subscribe(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    let admin_id = match require_admin(&bot, &msg).await? {
+        Some(admin_id) => admin_id,
+        None => return Ok(()),
+    };
+
+    let (category, library, chat_id) = match parse_subscription_args(&args) {
+        Some(SubscriptionArgs { category: Some(category), library, chat_id }) => (category, library, chat_id.unwrap_or(msg.chat.id.0)),
+        _ => {
+            bot.send_message(msg.chat.id, SUBSCRIBE_USAGE).await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    let reply = match notification::subscribe(&mut conn, chat_id, category, library.as_deref(), admin_id) {
+        Ok(true) => format!(
+            "会话 {} 已订阅：{}（{}）",
+            chat_id,
+            notification::category_label(category),
+            library.as_deref().unwrap_or("全部媒体库")
+        ),
+        Ok(false) => "该订阅已存在。".to_string(),
+        Err(e) => e,
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+async fn unsubscribe(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    if require_admin(&bot, &msg).await?.is_none() {
+        return Ok(());
+    }
+
+    let args = match parse_subscription_args(&args) {
+        Some(args) => args,
+        None => {
+            bot.send_message(msg.chat.id, UNSUBSCRIBE_USAGE).await?;
+            return Ok(());
+        }
+    };
+    let chat_id = args.chat_id.unwrap_or(msg.chat.id.0);
+
+    let mut conn = establish_connection();
+    let reply = match notification::unsubscribe(&mut conn, chat_id, args.category, args.library.as_deref()) {
+        Ok(0) => "没有找到匹配的订阅。".to_string(),
+        Ok(count) => format!("已取消会话 {} 的 {} 条订阅。", chat_id, count),
+        Err(e) => e,
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+async fn list_subscriptions(bot: Bot, msg: Message) -> HandlerResult {
+    if require_admin(&bot, &msg).await?.is_none() {
+        return Ok(());
+    }
+
+    let mut conn = establish_connection();
+    let reply = match notification::list_for_chat(&mut conn, msg.chat.id.0) {
+        Ok(subscriptions) if subscriptions.is_empty() => "当前会话没有任何通知订阅。".to_string(),
+        Ok(subscriptions) => {
+            let lines: Vec<String> = subscriptions
+                .iter()
+                .map(|subscription| format!(
+                    "• {}（{}）",
+                    notification::category_label(&subscription.category),
+                    if subscription.library.is_empty() { "全部媒体库" } else { &subscription.library }
+                ))
+                .collect();
+            format!("当前会话的通知订阅：\n{}", lines.join("\n"))
+        }
+        Err(e) => e,
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+const MY_REQUESTS_CALLBACK_PREFIX: &str = "myreq:";
+const MY_REQUESTS_PAGE_SIZE: i64 = 5;
+
+fn has_callback_prefix(q: &CallbackQuery, prefix: &str) -> bool {
+    q.data.as_deref().is_some_and(|data| data.starts_with(prefix))
+}
+
+/// 渲染 /myrequests 的某一页，返回消息文本和按钮
+fn my_requests_page(user_id: i64, page: i64) -> Result<(String, InlineKeyboardMarkup), media_request::MediaRequestError> {
+    let mut conn = establish_connection();
+    let (requests, total) = media_request::list_for_user(&mut conn, user_id, page, MY_REQUESTS_PAGE_SIZE)?;
+
+    if total == 0 {
+        return Ok(("您还没有提交过媒体请求，使用 /request 提交新的请求。".to_string(), InlineKeyboardMarkup::default()));
+    }
+
+    let total_pages = (total + MY_REQUESTS_PAGE_SIZE - 1) / MY_REQUESTS_PAGE_SIZE;
+    let mut text = format!("您的媒体请求（第 {}/{} 页，共 {} 条）：\n", page + 1, total_pages, total);
+    let mut rows = Vec::new();
+    for (request, title) in &requests {
+        text.push_str(&format!(
+            "\n#{} {}\n📁 {} / {}\n📊 {}\n🕒 提交于 {}，更新于 {}\n",
+            request.id,
+            title.as_deref().unwrap_or("（未获取到标题）"),
+            request.source,
+            request.media_id,
+            media_request::status_text(request.status),
+            request.created_at,
+            request.updated_at,
+        ));
+        if media_request_status::can_transition(request.status, media_request_status::CANCELLED) {
+            rows.push(vec![InlineKeyboardButton::callback(
+                format!("取消 #{}", request.id),
+                format!("{}cancel:{}:{}", MY_REQUESTS_CALLBACK_PREFIX, request.id, page),
+            )]);
+        } else if media_request_status::can_transition(request.status, media_request_status::SUBMITTED) {
+            rows.push(vec![InlineKeyboardButton::callback(
+                format!("重新提交 #{}", request.id),
+                format!("{}reopen:{}:{}", MY_REQUESTS_CALLBACK_PREFIX, request.id, page),
+            )]);
+        }
+    }
+
+    let mut navigation = Vec::new();
+    if page > 0 {
+        navigation.push(InlineKeyboardButton::callback("◀ 上一页", format!("{}page:{}", MY_REQUESTS_CALLBACK_PREFIX, page - 1)));
+    }
+    if page + 1 < total_pages {
+        navigation.push(InlineKeyboardButton::callback("下一页 ▶", format!("{}page:{}", MY_REQUESTS_CALLBACK_PREFIX, page + 1)));
+    }
+    if !navigation.is_empty() {
+        rows.push(navigation);
+    }
+
+    Ok((text, InlineKeyboardMarkup::new(rows)))
+}
+
+async fn my_requests(bot: Bot, msg: Message) -> HandlerResult {
     match msg.chat.kind {
-        ChatKind::Private(_) => {
-            bot.send_message(msg.chat.id, "无效的bot命令，请使用 /help 查看可用命令。").await?;
-        },
+        ChatKind::Public(_) => {
+            let reply = bot.send_message(msg.chat.id, "请在私聊中使用此命令。").await?;
+            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            bot.delete_message(msg.chat.id, msg.id).await?;
+            bot.delete_message(msg.chat.id, reply.id).await?;
+        }
         _ => {
-            // 当 bot 拥有 admin 权限的时候就会收到每一条消息。
+            match my_requests_page(msg.chat.id.0, 0) {
+                Ok((text, keyboard)) => {
+                    bot.send_message(msg.chat.id, text).reply_markup(keyboard).await?;
+                }
+                Err(e) => {
+                    bot.send_message(msg.chat.id, format!("获取请求列表失败：{}", e)).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_my_requests_callback(bot: Bot, q: CallbackQuery) -> HandlerResult {
+    let user_id = q.from.id.0 as i64;
+    let action = q.data.as_deref().and_then(|data| data.strip_prefix(MY_REQUESTS_CALLBACK_PREFIX)).unwrap_or_default();
+    let parts: Vec<&str> = action.split(':').collect();
+
+    let (page, notice) = match parts.as_slice() {
+        ["page", page] => (page.parse::<i64>().unwrap_or(0), None),
+        [action @ ("cancel" | "reopen"), request_id, page] => {
+            let page = page.parse::<i64>().unwrap_or(0);
+            let new_status = if *action == "cancel" { media_request_status::CANCELLED } else { media_request_status::SUBMITTED };
+            let notice = match request_id.parse::<i32>() {
+                Ok(request_id) => {
+                    let mut conn = establish_connection();
+                    let result = if new_status == media_request_status::CANCELLED {
+                        media_request::cancel_by_requester(&bot, &mut conn, request_id, user_id).await
+                    } else if auth::check_admin(user_id) {
+                        media_request::reopen_by_requester(&bot, &mut conn, request_id, user_id).await
+                    } else {
+                        match request_quota::check(&mut conn, &RequestQuotaConfig::from_env(), user_id) {
+                            Ok(()) => media_request::reopen_by_requester(&bot, &mut conn, request_id, user_id).await,
+                            Err(e) => Err(e),
+                        }
+                    };
+                    match result {
+                        Ok(_) => format!("请求 #{} 已{}", request_id, if *action == "cancel" { "取消" } else { "重新提交" }),
+                        Err(e) => e.to_string(),
+                    }
+                }
+                Err(_) => "无效的请求ID".to_string(),
+            };
+            (page, Some(notice))
+        }
+        _ => (0, Some("未知的操作".to_string())),
+    };
+
+    let mut answer = bot.answer_callback_query(q.id.clone());
+    if let Some(notice) = notice {
+        answer = answer.text(notice);
+    }
+    answer.await?;
+
+    if let Some(message) = q.message {
+        match my_requests_page(user_id, page) {
+            Ok((text, keyboard)) => {
+                // 内容没有变化时 Telegram 会返回错误，忽略即可
+                bot.edit_message_text(message.chat.id, message.id, text).reply_markup(keyboard).await.ok();
+            }
+            Err(e) => {
+                bot.send_message(message.chat.id, format!("获取请求列表失败：{}", e)).await?;
+            }
         }
     }
     Ok(())
 }
 
-async fn submit_emby_register(username: String) -> Result<String, String> {
-    let client = Client::new();
-    let emby_url = env::var("EMBY_URL").expect("EMBY_URL must be set");
-    let copy_from_user_id = env::var("EMBY_COPY_FROM_USER_ID").expect("EMBY_COPY_FROM_USER_ID must be set");
-    let emby_token = env::var("EMBY_TOKEN").expect("EMBY_TOKEN must be set");
+const REVIEW_CALLBACK_PREFIX: &str = "review:";
+
+fn user_display(user: &teloxide::types::User) -> String {
+    match &user.username {
+        Some(username) => format!("@{} ({})", username, user.id),
+        None => format!("{} ({})", user.full_name(), user.id),
+    }
+}
+
+/// 管理员会话，读取 `REQUEST_ADMIN_CHAT_ID`，未配置时不推送审核卡片
+fn review_chat_id() -> Option<ChatId> {
+    env::var("REQUEST_ADMIN_CHAT_ID")
+        .ok()
+        .and_then(|chat_id| chat_id.trim().parse::<i64>().ok())
+        .map(ChatId)
+}
 
-    let user = EmbyRegisterPayload {
-        name: username,
-        copy_from_user_id: copy_from_user_id,
-        user_copy_options: vec!["UserPolicy".to_string()],
+fn review_keyboard(request_id: i32) -> InlineKeyboardMarkup {
+    let button = |label: &str, status: i32| {
+        InlineKeyboardButton::callback(label, format!("{}{}:{}", REVIEW_CALLBACK_PREFIX, request_id, status))
     };
+    InlineKeyboardMarkup::new(vec![vec![
+        button("入库", media_request_status::ARCHIVED),
+        button("不符合规范", media_request_status::INVALID),
+        button("取消", media_request_status::CANCELLED),
+    ]])
+}
 
-    let res = client.post(format!("{}/Users/New", emby_url))
-        .json(&user)
-        .header("X-Emby-Token", emby_token)
-        .send()
-        .await
-        .map_err(|e| format!("请联系管理员。[Error: Emby API]"))?;
+/// 新请求提交后向管理员会话推送带操作按钮的审核卡片
+async fn post_review_card(
+    bot: &Bot,
+    request: &MediaRequest,
+    media_info: Option<&scraper::MediaInfo>,
+    link: &str,
+    requester: &teloxide::types::User,
+) {
+    let chat_id = match review_chat_id() {
+        Some(chat_id) => chat_id,
+        None => return,
+    };
 
-    if res.status().is_success() {
-        let user_dto: EmbyUserDto = res.json().await.map_err(|_| "请联系管理员。[Error: Parsing JSON]".to_string())?;
-        Ok(user_dto.Id)
-    } else {
-        let error_message = res.text().await.map_err(|e| format!("请联系管理员。[Error: res.text]"))?;
-        Err(error_message)
+    let text = format!(
+        "新的媒体请求 #{}\n\n📺 标题：{}\n📁 来源：{} / {}\n🔗 链接：{}\n👤 请求者：{}\n\n需要说明原因时请使用 /reject {} <原因>",
+        request.id,
+        media_info.map(|info| info.display_title()).unwrap_or_else(|| "（未获取到标题）".to_string()),
+        request.source,
+        request.media_id,
+        link,
+        user_display(requester),
+        request.id,
+    );
+
+    if let Some(poster) = media_info.map(|info| info.poster.as_str()).filter(|poster| !poster.is_empty()) {
+        if let Ok(url) = poster.parse() {
+            let sent = bot.send_photo(chat_id, InputFile::url(url))
+                .caption(text.clone())
+                .reply_markup(review_keyboard(request.id))
+                .await;
+            if sent.is_ok() {
+                return;
+            }
+        }
+    }
+
+    if let Err(e) = bot.send_message(chat_id, text).reply_markup(review_keyboard(request.id)).await {
+        log::warn!("Failed to post review card for request {}: {}", request.id, e);
+    }
+}
+
+async fn handle_review_callback(bot: Bot, q: CallbackQuery) -> HandlerResult {
+    if !auth::check_admin(q.from.id.0 as i64) {
+        bot.answer_callback_query(q.id).text("抱歉，您没有权限执行此操作。").show_alert(true).await?;
+        return Ok(());
     }
+
+    let parsed = q.data.as_deref()
+        .and_then(|data| data.strip_prefix(REVIEW_CALLBACK_PREFIX))
+        .and_then(|data| data.split_once(':'))
+        .and_then(|(request_id, status)| Some((request_id.parse::<i32>().ok()?, status.parse::<i32>().ok()?)));
+    let (request_id, new_status) = match parsed {
+        Some(parsed) => parsed,
+        None => {
+            bot.answer_callback_query(q.id).text("未知的操作").await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    if let Err(e) = media_request::transition(&bot, &mut conn, request_id, new_status, Some(q.from.id.0 as i64), None).await {
+        bot.answer_callback_query(q.id).text(e.to_string()).show_alert(true).await?;
+        return Ok(());
+    }
+
+    bot.answer_callback_query(q.id.clone())
+        .text(format!("请求 #{} 已更新为：{}", request_id, media_request::status_text(new_status)))
+        .await?;
+
+    // 更新卡片并移除按钮，标注处理人
+    if let Some(message) = q.message {
+        let handled = format!(
+            "\n\n📊 {} — 由 {} 于 {} 处理",
+            media_request::status_text(new_status),
+            user_display(&q.from),
+            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        );
+        if message.photo().is_some() {
+            let caption = format!("{}{}", message.caption().unwrap_or_default(), handled);
+            bot.edit_message_caption(message.chat.id, message.id).caption(caption).await?;
+        } else {
+            let text = format!("{}{}", message.text().unwrap_or_default(), handled);
+            bot.edit_message_text(message.chat.id, message.id, text).await?;
+        }
+    }
+    Ok(())
+}
+
+const REJECT_USAGE: &str = "用法：/reject <请求ID> <原因>\n将请求标记为不符合规范，原因会发送给请求者。";
+
+/// `/reject <请求ID> <原因>`：审核按钮无法附带原因，需要说明时用此命令
+async fn reject_request(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    let admin_id = match require_admin(&bot, &msg).await? {
+        Some(admin_id) => admin_id,
+        None => return Ok(()),
+    };
+
+    let parsed = args.trim().split_once(char::is_whitespace)
+        .map(|(request_id, reason)| (request_id.trim_start_matches('#').parse::<i32>(), reason.trim()));
+    let (request_id, reason) = match parsed {
+        Some((Ok(request_id), reason)) if !reason.is_empty() => (request_id, reason),
+        _ => {
+            bot.send_message(msg.chat.id, REJECT_USAGE).await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    let reply = match media_request::transition(&bot, &mut conn, request_id, media_request_status::INVALID, Some(admin_id), Some(reason)).await {
+        Ok(_) => format!("请求 #{} 已标记为：{}\n原因：{}", request_id, media_request::status_text(media_request_status::INVALID), reason),
+        Err(e) => e.to_string(),
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
 }
 
-async fn submit_emby_password_update(user_id: String) -> Result<(), String> {
-    let client = Client::new();
-    let emby_url = env::var("EMBY_URL").expect("EMBY_URL must be set");
-    let emby_token = env::var("EMBY_TOKEN").expect("EMBY_TOKEN must be set");
+/// `/note <请求ID> [内容]`：带内容时添加内部备注，否则列出该请求的全部备注。仅限私聊或管理员会话
+async fn request_note(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    // 内部备注只能在私聊或管理员会话中查看，避免在公开群组中泄露
+    if matches!(msg.chat.kind, ChatKind::Public(_)) && review_chat_id() != Some(msg.chat.id) {
+        let reply = bot.send_message(msg.chat.id, "请在私聊或管理员会话中使用此命令。").await?;
+        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+        bot.delete_message(msg.chat.id, msg.id).await?;
+        bot.delete_message(msg.chat.id, reply.id).await?;
+        return Ok(());
+    }
 
-    let password_payload = serde_json::json!({
-        "ResetPassword": true
-    });
+    let admin_id = match require_admin(&bot, &msg).await? {
+        Some(admin_id) => admin_id,
+        None => return Ok(()),
+    };
 
-    let res = client.post(format!("{}/Users/{}/Password", emby_url, user_id))
-        .json(&password_payload)
-        .header("X-Emby-Token", emby_token)
-        .send()
-        .await
-        .map_err(|e| format!("请联系管理员。[Error: Emby API]"))?;
+    let (request_id, content) = match args.trim().split_once(char::is_whitespace) {
+        Some((request_id, content)) => (request_id, content.trim()),
+        None => (args.trim(), ""),
+    };
+    let request_id = match request_id.trim_start_matches('#').parse::<i32>() {
+        Ok(request_id) => request_id,
+        Err(_) => {
+            bot.send_message(msg.chat.id, "用法：/note <请求ID> [备注内容]\n备注仅管理员可见，不带内容时列出已有备注。").await?;
+            return Ok(());
+        }
+    };
 
-    if res.status().is_success() {
-        Ok(())
+    let mut conn = establish_connection();
+    let reply = if content.is_empty() {
+        match media_request::list_notes(&mut conn, request_id) {
+            Ok(notes) if notes.is_empty() => format!("请求 #{} 没有内部备注。", request_id),
+            Ok(notes) => {
+                let lines: Vec<String> = notes
+                    .iter()
+                    .map(|note| format!("• [{}] {}：{}", note.created_at, note.author, note.content))
+                    .collect();
+                format!("请求 #{} 的内部备注：\n{}", request_id, lines.join("\n"))
+            }
+            Err(e) => e.to_string(),
+        }
     } else {
-        let error_message = res.text().await.map_err(|e| format!("请联系管理员。[Error: res.text]"))?;
-        Err(error_message)
+        match media_request::add_note(&mut conn, request_id, admin_id, content) {
+            Ok(_) => format!("已为请求 #{} 添加备注。", request_id),
+            Err(e) => e.to_string(),
+        }
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+/// `/timeline <请求ID>`：管理员可查看任意请求，普通用户只能查看自己的请求
+async fn request_timeline(bot: Bot, msg: Message, args: String) -> HandlerResult {
+    let user_id = match msg.from() {
+        Some(user) => user.id.0 as i64,
+        None => return Ok(()),
+    };
+
+    let request_id = match args.trim().trim_start_matches('#').parse::<i32>() {
+        Ok(request_id) => request_id,
+        Err(_) => {
+            bot.send_message(msg.chat.id, "用法：/timeline <请求ID>").await?;
+            return Ok(());
+        }
+    };
+
+    let mut conn = establish_connection();
+    let request = media_requests::table
+        .filter(media_requests::id.eq(request_id))
+        .first::<MediaRequest>(&mut conn)
+        .optional()?;
+    let request = match request {
+        Some(request) if request.request_user == user_id || auth::check_admin(user_id) => request,
+        _ => {
+            bot.send_message(msg.chat.id, "请求不存在。").await?;
+            return Ok(());
+        }
+    };
+
+    let reply = match media_request::list_events(&mut conn, request_id) {
+        Ok(events) if events.is_empty() => format!("请求 #{} 暂无记录。", request_id),
+        Ok(events) => {
+            let lines: Vec<String> = events.iter().map(media_request::describe_event).collect();
+            format!(
+                "请求 #{}（{} / {}）当前状态：{}\n\n{}",
+                request.id,
+                request.source,
+                request.media_id,
+                media_request::status_text(request.status),
+                lines.join("\n"),
+            )
+        }
+        Err(e) => e.to_string(),
+    };
+    bot.send_message(msg.chat.id, reply).await?;
+    Ok(())
+}
+
+async fn invalid_state(bot: Bot, msg: Message) -> HandlerResult {
+    match msg.chat.kind {
+        ChatKind::Private(_) => {
+            bot.send_message(msg.chat.id, "无效的bot命令，请使用 /help 查看可用命令。").await?;
+        },
+        _ => {
+            // 当 bot 拥有 admin 权限的时候就会收到每一条消息。
+        }
     }
+    Ok(())
 }
 
 pub async fn bot_start() {
     log::info!("Starting bot...");
     let bot = Bot::from_env();
+    let storage = DieselStorage::<State>::from_env().expect("Failed to initialize dialogue storage");
     Dispatcher::builder(bot, schema())
-        .dependencies(dptree::deps![InMemStorage::<State>::new()])
+        .dependencies(dptree::deps![storage])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
@@ -219,7 +219,11 @@ pub fn verify_telegram_login(
     let config = CliAuthConfig::from_env()?;
     config.validate_client_id(client_id)?;
 
-    verify_telegram_payload(&config, &telegram_login)?;
+    verify_telegram_payload(
+        &config.bot_token,
+        config.telegram_auth_max_age_secs,
+        &telegram_login,
+    )?;
 
     let mut conn = database::establish_connection()
         .map_err(|err| ServiceError::Internal(format!("数据库连接失败: {}", err)))?;
@@ -382,8 +386,9 @@ pub fn exchange_authorization_code(
     })
 }
 
-fn verify_telegram_payload(
-    config: &CliAuthConfig,
+pub(crate) fn verify_telegram_payload(
+    bot_token: &str,
+    max_age_secs: i64,
     payload: &TelegramLoginPayload,
 ) -> Result<(), ServiceError> {
     let now = Utc::now().timestamp();
@@ -391,11 +396,11 @@ fn verify_telegram_payload(
         return Err(ServiceError::Unauthorized("Telegram 登录时间异常".to_string()));
     }
 
-    if now - payload.auth_date > config.telegram_auth_max_age_secs {
+    if now - payload.auth_date > max_age_secs {
         return Err(ServiceError::Unauthorized("Telegram 登录已过期，请重新登录".to_string()));
     }
 
-    let expected_hash = telegram_hash(&config.bot_token, payload)?;
+    let expected_hash = telegram_hash(bot_token, payload)?;
     if expected_hash != payload.hash {
         return Err(ServiceError::Unauthorized("Telegram 登录校验失败".to_string()));
     }
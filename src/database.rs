@@ -125,7 +125,7 @@ pub fn run_migrations_with_backup() -> Result<(), DatabaseError> {
     log::info!("开始运行数据库迁移...");
     
     connection.run_pending_migrations(MIGRATIONS)
-        .map_err(|e| DatabaseError::MigrationError(e))?;
+        .map_err(DatabaseError::MigrationError)?;
     
     log::info!("数据库迁移完成");
     
@@ -139,7 +139,7 @@ pub fn run_migrations() -> Result<(), DatabaseError> {
     log::info!("开始运行数据库迁移...");
     
     connection.run_pending_migrations(MIGRATIONS)
-        .map_err(|e| DatabaseError::MigrationError(e))?;
+        .map_err(DatabaseError::MigrationError)?;
     
     log::info!("数据库迁移完成");
     
@@ -152,7 +152,7 @@ pub fn get_migration_status() -> Result<Vec<String>, DatabaseError> {
     
     // 获取已应用的迁移
     let applied_migrations = connection.applied_migrations()
-        .map_err(|e| DatabaseError::MigrationError(e))?;
+        .map_err(DatabaseError::MigrationError)?;
     
     Ok(applied_migrations.into_iter().map(|v| v.to_string()).collect())
 }
\ No newline at end of file
@@ -0,0 +1,211 @@
+use std::env;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use diesel::prelude::*;
+use diesel::OptionalExtension;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use teloxide::dispatching::dialogue::Storage;
+use teloxide::types::ChatId;
+
+use crate::database;
+use crate::models::{BotDialogue, NewBotDialogue};
+use crate::schema::bot_dialogues;
+
+/// 两次自动清理之间的最短间隔
+const PURGE_INTERVAL_SECS: i64 = 3600;
+
+type StorageFuture<T> = Pin<Box<dyn Future<Output = Result<T, DialogueStorageError>> + Send>>;
+
+/// 对话状态存储错误类型
+#[derive(Debug)]
+pub enum DialogueStorageError {
+    Config(String),
+    Database(String),
+    Serialization(String),
+    DialogueNotFound,
+}
+
+impl std::fmt::Display for DialogueStorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DialogueStorageError::Config(e) => write!(f, "配置错误: {}", e),
+            DialogueStorageError::Database(e) => write!(f, "数据库错误: {}", e),
+            DialogueStorageError::Serialization(e) => write!(f, "序列化错误: {}", e),
+            DialogueStorageError::DialogueNotFound => write!(f, "对话不存在"),
+        }
+    }
+}
+
+impl std::error::Error for DialogueStorageError {}
+
+/// 基于 SQLite (Diesel) 的对话状态存储，bot 重启后对话不会丢失。
+///
+/// 超过 `BOT_DIALOGUE_TTL_SECONDS` 未更新的对话视为已过期，读取时返回空，
+/// 写入时顺带清理，两次清理至少间隔 `PURGE_INTERVAL_SECS`。
+pub struct DieselStorage<D> {
+    ttl_secs: i64,
+    /// 上次自动清理的 Unix 时间戳
+    last_purge: AtomicI64,
+    _state: PhantomData<fn() -> D>,
+}
+
+impl<D> DieselStorage<D> {
+    pub fn new(ttl_secs: i64) -> Arc<Self> {
+        Arc::new(Self {
+            ttl_secs,
+            last_purge: AtomicI64::new(0),
+            _state: PhantomData,
+        })
+    }
+
+    pub fn from_env() -> Result<Arc<Self>, DialogueStorageError> {
+        let ttl_secs = match env::var("BOT_DIALOGUE_TTL_SECONDS") {
+            Ok(value) => value.parse::<i64>().map_err(|_| {
+                DialogueStorageError::Config("BOT_DIALOGUE_TTL_SECONDS 必须是整数".to_string())
+            })?,
+            Err(_) => 86_400,
+        };
+        Ok(Self::new(ttl_secs))
+    }
+
+    /// 删除所有已过期的对话，返回删除的条数
+    pub fn purge_expired(&self) -> Result<usize, DialogueStorageError> {
+        let mut conn = establish_connection()?;
+        let cutoff = timestamp_string(Utc::now() - Duration::seconds(self.ttl_secs));
+        diesel::delete(bot_dialogues::table.filter(bot_dialogues::updated_at.lt(cutoff)))
+            .execute(&mut conn)
+            .map_err(map_db_err)
+    }
+
+    /// 距上次清理超过间隔时才执行清理
+    fn purge_expired_if_due(&self) -> Result<(), DialogueStorageError> {
+        let now = Utc::now().timestamp();
+        let last_purge = self.last_purge.load(Ordering::Relaxed);
+        if now - last_purge < PURGE_INTERVAL_SECS.min(self.ttl_secs) {
+            return Ok(());
+        }
+        // 并发写入时只让一个调用执行清理
+        if self.last_purge.compare_exchange(last_purge, now, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+            return Ok(());
+        }
+        self.purge_expired().map(|_| ())
+    }
+
+    fn is_expired(&self, updated_at: &str) -> bool {
+        match DateTime::parse_from_rfc3339(updated_at) {
+            Ok(updated_at) => {
+                updated_at.with_timezone(&Utc) + Duration::seconds(self.ttl_secs) <= Utc::now()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+impl<D> Storage<D> for DieselStorage<D>
+where
+    D: Serialize + DeserializeOwned + Send + 'static,
+{
+    type Error = DialogueStorageError;
+
+    fn remove_dialogue(self: Arc<Self>, chat_id: ChatId) -> StorageFuture<()>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let mut conn = establish_connection()?;
+            let deleted = diesel::delete(bot_dialogues::table.filter(bot_dialogues::chat_id.eq(chat_id.0)))
+                .execute(&mut conn)
+                .map_err(map_db_err)?;
+
+            if deleted > 0 {
+                Ok(())
+            } else {
+                Err(DialogueStorageError::DialogueNotFound)
+            }
+        })
+    }
+
+    fn update_dialogue(self: Arc<Self>, chat_id: ChatId, dialogue: D) -> StorageFuture<()>
+    where
+        D: Send + 'static,
+    {
+        Box::pin(async move {
+            let state = serde_json::to_string(&dialogue)
+                .map_err(|err| DialogueStorageError::Serialization(err.to_string()))?;
+            let record = NewBotDialogue {
+                chat_id: chat_id.0,
+                state,
+                updated_at: timestamp_string(Utc::now()),
+            };
+
+            let mut conn = establish_connection()?;
+            diesel::insert_into(bot_dialogues::table)
+                .values(&record)
+                .on_conflict(bot_dialogues::chat_id)
+                .do_update()
+                .set(&record)
+                .execute(&mut conn)
+                .map_err(map_db_err)?;
+
+            if let Err(err) = self.purge_expired_if_due() {
+                log::warn!("清理过期对话失败: {}", err);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn get_dialogue(self: Arc<Self>, chat_id: ChatId) -> StorageFuture<Option<D>> {
+        Box::pin(async move {
+            let mut conn = establish_connection()?;
+            let record = bot_dialogues::table
+                .filter(bot_dialogues::chat_id.eq(chat_id.0))
+                .first::<BotDialogue>(&mut conn)
+                .optional()
+                .map_err(map_db_err)?;
+
+            let record = match record {
+                Some(record) => record,
+                None => return Ok(None),
+            };
+
+            if self.is_expired(&record.updated_at) {
+                diesel::delete(bot_dialogues::table.filter(bot_dialogues::chat_id.eq(chat_id.0)))
+                    .execute(&mut conn)
+                    .map_err(map_db_err)?;
+                return Ok(None);
+            }
+
+            match serde_json::from_str::<D>(&record.state) {
+                Ok(state) => Ok(Some(state)),
+                Err(err) => {
+                    // State 结构变更后旧数据无法解析，直接丢弃让用户重新开始
+                    log::warn!("无法解析 chat {} 的对话状态，已丢弃: {}", chat_id.0, err);
+                    diesel::delete(bot_dialogues::table.filter(bot_dialogues::chat_id.eq(chat_id.0)))
+                        .execute(&mut conn)
+                        .map_err(map_db_err)?;
+                    Ok(None)
+                }
+            }
+        })
+    }
+}
+
+fn establish_connection() -> Result<diesel::SqliteConnection, DialogueStorageError> {
+    database::establish_connection()
+        .map_err(|err| DialogueStorageError::Database(format!("数据库连接失败: {}", err)))
+}
+
+fn map_db_err(err: diesel::result::Error) -> DialogueStorageError {
+    DialogueStorageError::Database(format!("数据库操作失败: {}", err))
+}
+
+fn timestamp_string(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
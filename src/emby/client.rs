@@ -0,0 +1,286 @@
+use std::env;
+use std::fmt;
+use std::future::Future;
+
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use super::types::{
+    EmbyAuthenticationResult, EmbyItem, EmbyItemsResponse, EmbyLibrary, EmbySession, EmbyUser, EmbyUserPolicy, NewEmbyUser,
+};
+
+const CLIENT_AUTHORIZATION: &str =
+    r#"Emby Client="nyamedia-bot", Device="nyamedia-bot", DeviceId="nyamedia-bot", Version="1.0.0""#;
+
+#[derive(Debug, Clone)]
+pub struct EmbyConfig {
+    pub base_url: String,
+    pub api_key: String,
+    /// 新用户从该用户复制策略，读取 `EMBY_COPY_FROM_USER_ID`
+    pub copy_from_user_id: Option<String>,
+}
+
+impl EmbyConfig {
+    pub fn from_env() -> Result<Self, EmbyError> {
+        let base_url = env::var("EMBY_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| EmbyError::Config("EMBY_URL 未配置".to_string()))?;
+        let api_key = env::var("EMBY_TOKEN")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| EmbyError::Config("EMBY_TOKEN 未配置".to_string()))?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            copy_from_user_id: env::var("EMBY_COPY_FROM_USER_ID")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum EmbyError {
+    /// 缺少 `EMBY_URL` 等配置
+    Config(String),
+    /// 无法连接 Emby
+    Network(String),
+    /// API Key 无效或权限不足（401/403）
+    Unauthorized(String),
+    NotFound(String),
+    /// 请求被 Emby 拒绝（400），例如用户名已存在
+    BadRequest(String),
+    /// 其它非成功状态码
+    Upstream { status: u16, message: String },
+    /// 返回内容无法解析
+    Decode(String),
+}
+
+impl fmt::Display for EmbyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbyError::Config(message) => write!(f, "Emby 配置错误: {}", message),
+            EmbyError::Network(message) => write!(f, "无法连接 Emby: {}", message),
+            EmbyError::Unauthorized(_) => write!(f, "Emby API Key 无效或权限不足"),
+            EmbyError::NotFound(_) => write!(f, "Emby 中不存在该对象"),
+            EmbyError::BadRequest(message) => write!(f, "Emby 拒绝了请求: {}", message),
+            EmbyError::Upstream { status, message } => write!(f, "Emby 返回错误 (HTTP {}): {}", status, message),
+            EmbyError::Decode(message) => write!(f, "Emby 返回内容解析失败: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EmbyError {}
+
+/// Emby 服务端接口。bot 和 webhook 只通过该 trait 访问 Emby，测试时可以指向本地的假服务
+pub trait EmbyApi: Send + Sync {
+    fn create_user(&self, name: &str) -> impl Future<Output = Result<EmbyUser, EmbyError>> + Send;
+
+    fn get_user(&self, user_id: &str) -> impl Future<Output = Result<EmbyUser, EmbyError>> + Send;
+
+    fn list_users(&self) -> impl Future<Output = Result<Vec<EmbyUser>, EmbyError>> + Send;
+
+    /// 用用户名和密码登录，用于验证账户归属。密码错误时返回 `Unauthorized`
+    fn authenticate_by_name(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> impl Future<Output = Result<EmbyUser, EmbyError>> + Send;
+
+    fn delete_user(&self, user_id: &str) -> impl Future<Output = Result<(), EmbyError>> + Send;
+
+    /// 将密码重置为空
+    fn reset_password(&self, user_id: &str) -> impl Future<Output = Result<(), EmbyError>> + Send;
+
+    /// 以管理员身份为用户设置新密码
+    fn set_password(&self, user_id: &str, new_password: &str) -> impl Future<Output = Result<(), EmbyError>> + Send;
+
+    fn update_policy(
+        &self,
+        user_id: &str,
+        policy: &EmbyUserPolicy,
+    ) -> impl Future<Output = Result<(), EmbyError>> + Send;
+
+    fn list_libraries(&self) -> impl Future<Output = Result<Vec<EmbyLibrary>, EmbyError>> + Send;
+
+    /// 按 ID 查询条目，附带 ProviderIds
+    fn get_items(&self, ids: &[&str]) -> impl Future<Output = Result<Vec<EmbyItem>, EmbyError>> + Send;
+
+    /// 条目的上级条目，从近到远排列，包含所在媒体库（CollectionFolder）
+    fn get_ancestors(&self, item_id: &str) -> impl Future<Output = Result<Vec<EmbyItem>, EmbyError>> + Send;
+
+    fn list_sessions(&self) -> impl Future<Output = Result<Vec<EmbySession>, EmbyError>> + Send;
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbyClient {
+    client: Client,
+    config: EmbyConfig,
+}
+
+impl EmbyClient {
+    pub fn new(config: EmbyConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+
+    pub fn from_env() -> Result<Self, EmbyError> {
+        Ok(Self::new(EmbyConfig::from_env()?))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.config.base_url, path))
+            .header("X-Emby-Token", &self.config.api_key)
+    }
+
+    async fn send(&self, builder: RequestBuilder) -> Result<Response, EmbyError> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| EmbyError::Network(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let message = response.text().await.unwrap_or_default().trim().to_string();
+        Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => EmbyError::Unauthorized(message),
+            StatusCode::NOT_FOUND => EmbyError::NotFound(message),
+            StatusCode::BAD_REQUEST => EmbyError::BadRequest(message),
+            _ => EmbyError::Upstream {
+                status: status.as_u16(),
+                message,
+            },
+        })
+    }
+
+    async fn send_json<T: DeserializeOwned>(&self, builder: RequestBuilder) -> Result<T, EmbyError> {
+        self.send(builder)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(|e| EmbyError::Decode(e.to_string()))
+    }
+}
+
+impl EmbyApi for EmbyClient {
+    async fn create_user(&self, name: &str) -> Result<EmbyUser, EmbyError> {
+        let payload = NewEmbyUser {
+            name: name.to_string(),
+            copy_from_user_id: self.config.copy_from_user_id.clone(),
+            user_copy_options: vec!["UserPolicy".to_string()],
+        };
+        self.send_json(self.request(Method::POST, "/Users/New").json(&payload)).await
+    }
+
+    async fn get_user(&self, user_id: &str) -> Result<EmbyUser, EmbyError> {
+        self.send_json(self.request(Method::GET, &format!("/Users/{}", user_id))).await
+    }
+
+    async fn list_users(&self) -> Result<Vec<EmbyUser>, EmbyError> {
+        self.send_json(self.request(Method::GET, "/Users")).await
+    }
+
+    async fn authenticate_by_name(&self, username: &str, password: &str) -> Result<EmbyUser, EmbyError> {
+        let payload = serde_json::json!({ "Username": username, "Pw": password });
+        let result: EmbyAuthenticationResult = self
+            .send_json(
+                self.request(Method::POST, "/Users/AuthenticateByName")
+                    .header("X-Emby-Authorization", CLIENT_AUTHORIZATION)
+                    .json(&payload),
+            )
+            .await?;
+
+        // 只用于验证身份，登录产生的会话立即注销
+        if let Some(access_token) = result.access_token.as_deref() {
+            let logout = self.client
+                .post(format!("{}/Sessions/Logout", self.config.base_url))
+                .header("X-Emby-Token", access_token)
+                .send()
+                .await;
+            if let Err(e) = logout {
+                log::warn!("注销 Emby 验证会话失败: {}", e);
+            }
+        }
+
+        Ok(result.user)
+    }
+
+    async fn delete_user(&self, user_id: &str) -> Result<(), EmbyError> {
+        self.send(self.request(Method::DELETE, &format!("/Users/{}", user_id))).await?;
+        Ok(())
+    }
+
+    async fn reset_password(&self, user_id: &str) -> Result<(), EmbyError> {
+        let payload = serde_json::json!({ "ResetPassword": true });
+        self.send(self.request(Method::POST, &format!("/Users/{}/Password", user_id)).json(&payload)).await?;
+        Ok(())
+    }
+
+    async fn set_password(&self, user_id: &str, new_password: &str) -> Result<(), EmbyError> {
+        // 管理员 API Key 可以直接设置新密码，不需要当前密码，也不会在中途留下空密码
+        let payload = serde_json::json!({ "Id": user_id, "NewPw": new_password });
+        self.send(self.request(Method::POST, &format!("/Users/{}/Password", user_id)).json(&payload)).await?;
+        Ok(())
+    }
+
+    async fn update_policy(&self, user_id: &str, policy: &EmbyUserPolicy) -> Result<(), EmbyError> {
+        self.send(self.request(Method::POST, &format!("/Users/{}/Policy", user_id)).json(policy)).await?;
+        Ok(())
+    }
+
+    async fn list_libraries(&self) -> Result<Vec<EmbyLibrary>, EmbyError> {
+        self.send_json(self.request(Method::GET, "/Library/VirtualFolders")).await
+    }
+
+    async fn get_items(&self, ids: &[&str]) -> Result<Vec<EmbyItem>, EmbyError> {
+        let ids = ids.join(",");
+        let response: EmbyItemsResponse = self
+            .send_json(
+                self.request(Method::GET, "/Items")
+                    .query(&[("Ids", ids.as_str()), ("Fields", "ProviderIds,ProductionYear")]),
+            )
+            .await?;
+        Ok(response.items)
+    }
+
+    async fn get_ancestors(&self, item_id: &str) -> Result<Vec<EmbyItem>, EmbyError> {
+        self.send_json(self.request(Method::GET, &format!("/Items/{}/Ancestors", item_id))).await
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<EmbySession>, EmbyError> {
+        self.send_json(self.request(Method::GET, "/Sessions")).await
+    }
+}
+
+/// 启用或禁用用户。策略接口需要完整策略，因此先读取再修改 `IsDisabled`
+pub async fn set_user_disabled<E: EmbyApi>(api: &E, user_id: &str, disabled: bool) -> Result<(), EmbyError> {
+    let user = api.get_user(user_id).await?;
+    let mut policy = user
+        .policy
+        .ok_or_else(|| EmbyError::Decode("用户信息中缺少 Policy".to_string()))?;
+    policy.is_disabled = disabled;
+    api.update_policy(user_id, &policy).await
+}
+
+/// 按用户名查找用户，忽略大小写
+pub async fn find_user_by_name<E: EmbyApi>(api: &E, name: &str) -> Result<Option<EmbyUser>, EmbyError> {
+    let users = api.list_users().await?;
+    Ok(users.into_iter().find(|user| user.name.eq_ignore_ascii_case(name.trim())))
+}
+
+/// 条目所在的媒体库名称
+pub async fn library_name<E: EmbyApi>(api: &E, item_id: &str) -> Result<Option<String>, EmbyError> {
+    let ancestors = api.get_ancestors(item_id).await?;
+    Ok(ancestors
+        .into_iter()
+        .find(|item| item.item_type.as_deref() == Some("CollectionFolder"))
+        .and_then(|item| item.name))
+}
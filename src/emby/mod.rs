@@ -0,0 +1,4 @@
+pub mod client;
+pub mod types;
+
+pub use client::{EmbyApi, EmbyClient, EmbyConfig, EmbyError};
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbyUser {
+    #[serde(rename = "Id")]
+    pub id: String,
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Policy", default)]
+    pub policy: Option<EmbyUserPolicy>,
+}
+
+/// 用户策略。Emby 的策略接口要求提交完整策略，未列出的字段保存在 `extra` 中原样回传
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbyUserPolicy {
+    #[serde(rename = "IsAdministrator", default)]
+    pub is_administrator: bool,
+    #[serde(rename = "IsDisabled", default)]
+    pub is_disabled: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NewEmbyUser {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "CopyFromUserId", skip_serializing_if = "Option::is_none")]
+    pub copy_from_user_id: Option<String>,
+    #[serde(rename = "UserCopyOptions")]
+    pub user_copy_options: Vec<String>,
+}
+
+/// `/Users/AuthenticateByName` 的返回
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbyAuthenticationResult {
+    #[serde(rename = "User")]
+    pub user: EmbyUser,
+    #[serde(rename = "AccessToken", default)]
+    pub access_token: Option<String>,
+}
+
+/// 媒体库（`/Library/VirtualFolders`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbyLibrary {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "ItemId", default)]
+    pub item_id: Option<String>,
+    #[serde(rename = "CollectionType", default)]
+    pub collection_type: Option<String>,
+    #[serde(rename = "Locations", default)]
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbyItem {
+    #[serde(rename = "Id", default)]
+    pub id: String,
+    #[serde(rename = "Name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "Type", default)]
+    pub item_type: Option<String>,
+    #[serde(rename = "SeriesId", default)]
+    pub series_id: Option<String>,
+    #[serde(rename = "ProductionYear", default)]
+    pub production_year: Option<i32>,
+    #[serde(rename = "ProviderIds", default)]
+    pub provider_ids: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbyItemsResponse {
+    #[serde(rename = "Items", default)]
+    pub items: Vec<EmbyItem>,
+    #[serde(rename = "TotalRecordCount", default)]
+    pub total_record_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbySession {
+    #[serde(rename = "Id", default)]
+    pub id: String,
+    #[serde(rename = "UserId", default)]
+    pub user_id: Option<String>,
+    #[serde(rename = "UserName", default)]
+    pub user_name: Option<String>,
+    #[serde(rename = "Client", default)]
+    pub client: Option<String>,
+    #[serde(rename = "DeviceName", default)]
+    pub device_name: Option<String>,
+    #[serde(rename = "NowPlayingItem", default)]
+    pub now_playing_item: Option<EmbyItem>,
+}
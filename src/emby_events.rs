@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+/// Emby Webhooks 插件推送的原始数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    #[serde(rename = "Title", default)]
+    pub title: Option<String>,
+    #[serde(rename = "Description", default)]
+    pub description: Option<String>,
+    #[serde(rename = "Date", default)]
+    pub date: Option<String>,
+    #[serde(rename = "Event")]
+    pub event: String,
+    #[serde(rename = "Severity", default)]
+    pub severity: Option<String>,
+    #[serde(rename = "Item", default)]
+    pub item: Option<Item>,
+    #[serde(rename = "User", default)]
+    pub user: Option<User>,
+    #[serde(rename = "Session", default)]
+    pub session: Option<Session>,
+    #[serde(rename = "Server", default)]
+    pub server: Option<Server>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    #[serde(rename = "Name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "Id", default)]
+    pub id: Option<String>,
+    #[serde(rename = "Type", default)]
+    pub item_type: Option<String>,
+    #[serde(rename = "IndexNumber", default)]
+    pub index_number: Option<u32>,
+    #[serde(rename = "ParentIndexNumber", default)]
+    pub parent_index_number: Option<u32>,
+    #[serde(rename = "ProductionYear", default)]
+    pub production_year: Option<u16>,
+    #[serde(rename = "SeriesName", default)]
+    pub series_name: Option<String>,
+    #[serde(rename = "SeriesId", default)]
+    pub series_id: Option<String>,
+    #[serde(rename = "SeasonName", default)]
+    pub season_name: Option<String>,
+    #[serde(rename = "ProviderIds", default)]
+    pub provider_ids: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    #[serde(rename = "Name", default)]
+    pub name: Option<String>,
+    #[serde(rename = "Id", default)]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(rename = "RemoteEndPoint", default)]
+    pub remote_end_point: Option<String>,
+    #[serde(rename = "Client", default)]
+    pub client: Option<String>,
+    #[serde(rename = "DeviceName", default)]
+    pub device_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Server {
+    #[serde(rename = "Name", default)]
+    pub name: Option<String>,
+}
+
+/// 入库条目的类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemKind {
+    Movie,
+    Series,
+    Season,
+    Episode,
+    Other(String),
+}
+
+impl ItemKind {
+    pub fn from_item(item: Option<&Item>) -> Self {
+        match item.and_then(|item| item.item_type.as_deref()) {
+            Some("Movie") => ItemKind::Movie,
+            Some("Series") => ItemKind::Series,
+            Some("Season") => ItemKind::Season,
+            Some("Episode") => ItemKind::Episode,
+            Some(other) => ItemKind::Other(other.to_lowercase()),
+            None => ItemKind::Other("unknown".to_string()),
+        }
+    }
+
+    fn key(&self) -> &str {
+        match self {
+            ItemKind::Movie => "movie",
+            ItemKind::Series => "series",
+            ItemKind::Season => "season",
+            ItemKind::Episode => "episode",
+            ItemKind::Other(other) => other,
+        }
+    }
+}
+
+/// 解析后的 Emby 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmbyEvent {
+    LibraryNew(ItemKind),
+    LibraryDeleted(ItemKind),
+    PlaybackStart,
+    PlaybackStop,
+    UserAuthenticated,
+    UserAuthenticationFailed,
+    System(String),
+    Other(String),
+}
+
+impl EmbyEvent {
+    pub fn from_payload(payload: &WebhookPayload) -> Self {
+        let kind = ItemKind::from_item(payload.item.as_ref());
+        match payload.event.as_str() {
+            "library.new" => EmbyEvent::LibraryNew(kind),
+            "library.deleted" => EmbyEvent::LibraryDeleted(kind),
+            "playback.start" => EmbyEvent::PlaybackStart,
+            "playback.stop" => EmbyEvent::PlaybackStop,
+            "user.authenticated" => EmbyEvent::UserAuthenticated,
+            "user.authenticationfailed" => EmbyEvent::UserAuthenticationFailed,
+            event if event.starts_with("system.") => EmbyEvent::System(event.to_string()),
+            event => EmbyEvent::Other(event.to_string()),
+        }
+    }
+
+    /// 查找通知规则时使用的 key，越具体的越靠前
+    pub fn config_keys(&self) -> Vec<String> {
+        match self {
+            EmbyEvent::LibraryNew(kind) => vec![format!("library.new.{}", kind.key()), "library.new".to_string()],
+            EmbyEvent::LibraryDeleted(kind) => vec![format!("library.deleted.{}", kind.key()), "library.deleted".to_string()],
+            EmbyEvent::PlaybackStart => vec!["playback.start".to_string()],
+            EmbyEvent::PlaybackStop => vec!["playback.stop".to_string()],
+            EmbyEvent::UserAuthenticated => vec!["user.authenticated".to_string()],
+            EmbyEvent::UserAuthenticationFailed => vec!["user.authenticationfailed".to_string()],
+            EmbyEvent::System(event) => vec![event.clone(), "system".to_string()],
+            EmbyEvent::Other(event) => vec![event.clone()],
+        }
+    }
+}
+
+/// 单个事件的通知规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRule {
+    pub template: String,
+    #[serde(default)]
+    pub chats: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct EventRuleOverride {
+    template: Option<String>,
+    chats: Option<Vec<i64>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EventConfigFile {
+    #[serde(default)]
+    events: HashMap<String, EventRuleOverride>,
+}
+
+/// 各事件的通知模板和目标会话
+#[derive(Debug, Clone)]
+pub struct EventNotificationConfig {
+    defaults: HashMap<String, EventRule>,
+    /// 配置文件中的规则，任意层级的 key 都优先于内置默认规则
+    overrides: HashMap<String, EventRuleOverride>,
+}
+
+impl EventNotificationConfig {
+    /// 内置默认规则：入库类事件推送给订阅了对应类别的会话，其余事件默认不推送
+    pub fn defaults() -> Self {
+        let mut rules = HashMap::new();
+        let mut insert = |key: &str, template: &str| {
+            rules.insert(key.to_string(), EventRule { template: template.to_string(), chats: Vec::new() });
+        };
+
+        insert("library.new.movie", "新电影入库: {item_name} ({year})");
+        insert("library.new.series", "新剧集入库: {item_name} ({year})");
+        insert("library.new.season", "新剧集入库: {series_name} ({year})\n{season_name}");
+        insert("library.new.episode", "新剧集入库: {series_name} ({year})\n{episodes} 入库");
+        insert("library.new", "新内容入库: {item_name}");
+        insert("library.deleted", "媒体已删除: {item_name}");
+        insert("playback.start", "{user_name} 开始播放: {item_name}");
+        insert("playback.stop", "{user_name} 停止播放: {item_name}");
+        insert("user.authenticated", "{user_name} 登录成功 ({device_name} / {client})");
+        insert("user.authenticationfailed", "登录失败: {title}\n{description}");
+        insert("system", "系统事件: {title}\n{description}");
+
+        Self { defaults: rules, overrides: HashMap::new() }
+    }
+
+    /// 读取 `WEBHOOK_EVENTS_CONFIG` 指向的 TOML 文件并覆盖默认规则
+    pub fn from_env() -> Result<Self, String> {
+        let path = match env::var("WEBHOOK_EVENTS_CONFIG") {
+            Ok(path) if !path.trim().is_empty() => path,
+            _ => return Ok(Self::defaults()),
+        };
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("读取 {} 失败: {}", path, e))?;
+        Self::from_toml(&content).map_err(|e| format!("解析 {} 失败: {}", path, e))
+    }
+
+    /// 解析 TOML 格式的事件规则，未配置的事件使用默认规则
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let file: EventConfigFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        Ok(Self { overrides: file.events, ..Self::defaults() })
+    }
+
+    /// 模板和目标会话分别按 key 从具体到通用查找，先查配置文件，再查内置默认规则。
+    /// 例如配置了 `library.new` 时，它会覆盖内置的 `library.new.episode`
+    pub fn rule_for(&self, event: &EmbyEvent) -> Option<EventRule> {
+        let keys = event.config_keys();
+        let configured = keys.iter().any(|key| self.overrides.contains_key(key) || self.defaults.contains_key(key));
+        if !configured {
+            return None;
+        }
+
+        let template = keys.iter()
+            .find_map(|key| self.overrides.get(key).and_then(|rule| rule.template.clone()))
+            .or_else(|| keys.iter().find_map(|key| self.defaults.get(key).map(|rule| rule.template.clone())))
+            .unwrap_or_else(|| "{title}\n{description}".to_string());
+        let chats = keys.iter()
+            .find_map(|key| self.overrides.get(key).and_then(|rule| rule.chats.clone()))
+            .or_else(|| keys.iter().find_map(|key| self.defaults.get(key).map(|rule| rule.chats.clone())))
+            .unwrap_or_default();
+
+        Some(EventRule { template, chats })
+    }
+}
+
+/// 使用 payload 中的字段渲染通知模板
+pub fn render_template(template: &str, payload: &WebhookPayload) -> String {
+    render_values(template, &payload_values(payload))
+}
+
+/// 用给定的占位符取值渲染模板。只扫描一遍模板，取值中的占位符不会被再次替换
+pub fn render_values(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        match values.iter().find(|(placeholder, _)| candidate.starts_with(placeholder)) {
+            Some((placeholder, value)) => {
+                rendered.push_str(value);
+                rest = &candidate[placeholder.len()..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn payload_values(payload: &WebhookPayload) -> Vec<(&'static str, String)> {
+    let item = payload.item.as_ref();
+    let text = |value: Option<&String>| value.cloned().unwrap_or_default();
+    let number = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+
+    vec![
+        ("{event}", payload.event.clone()),
+        ("{title}", text(payload.title.as_ref())),
+        ("{description}", text(payload.description.as_ref())),
+        ("{date}", text(payload.date.as_ref())),
+        ("{item_name}", text(item.and_then(|i| i.name.as_ref()))),
+        ("{item_type}", text(item.and_then(|i| i.item_type.as_ref()))),
+        ("{series_name}", text(item.and_then(|i| i.series_name.as_ref()))),
+        ("{season_name}", text(item.and_then(|i| i.season_name.as_ref()))),
+        ("{season}", number(item.and_then(|i| i.parent_index_number))),
+        ("{episode}", number(item.and_then(|i| i.index_number))),
+        ("{year}", item.and_then(|i| i.production_year).map(|y| y.to_string()).unwrap_or_default()),
+        ("{user_name}", text(payload.user.as_ref().and_then(|u| u.name.as_ref()))),
+        ("{device_name}", text(payload.session.as_ref().and_then(|s| s.device_name.as_ref()))),
+        ("{client}", text(payload.session.as_ref().and_then(|s| s.client.as_ref()))),
+        ("{remote_address}", text(payload.session.as_ref().and_then(|s| s.remote_end_point.as_ref()))),
+        ("{server_name}", text(payload.server.as_ref().and_then(|s| s.name.as_ref()))),
+    ]
+}
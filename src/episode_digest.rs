@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use teloxide::prelude::*;
+
+use crate::database;
+use crate::emby_events::{self, EmbyEvent, EventNotificationConfig, Item, ItemKind};
+use crate::models::{EpisodeNotification, NewEpisodeNotification};
+use crate::notification;
+use crate::schema::episode_notifications;
+
+const DEFAULT_WINDOW_SECS: i64 = 600;
+const FLUSH_INTERVAL_SECS: u64 = 30;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// 已推送的记录保留多久后删除。保留期内同一集重复的入库事件仍会被忽略
+const NOTIFIED_RETENTION_SECS: i64 = 24 * 3600;
+
+/// 剧集入库后等待多久再汇总推送，读取 `WEBHOOK_DIGEST_WINDOW_SECONDS`
+pub fn window_secs_from_env() -> i64 {
+    env::var("WEBHOOK_DIGEST_WINDOW_SECONDS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(DEFAULT_WINDOW_SECS)
+}
+
+/// 记录一集入库，已经记录过的同一集会被忽略。返回是否为新记录
+pub fn record_episode(conn: &mut SqliteConnection, item: &Item, library: Option<&str>) -> Result<bool, String> {
+    let series_id = match item.series_id.as_ref() {
+        Some(series_id) => series_id.clone(),
+        None => return Ok(false),
+    };
+
+    let record = NewEpisodeNotification {
+        series_id,
+        series_name: item.series_name.clone()
+            .or_else(|| item.name.clone())
+            .unwrap_or_default(),
+        production_year: item.production_year.map(i32::from),
+        season_number: item.parent_index_number.map(|n| n as i32),
+        episode_number: item.index_number.map(|n| n as i32),
+        created_at: Utc::now().format(TIME_FORMAT).to_string(),
+        notified_at: None,
+        library: library.map(ToOwned::to_owned),
+    };
+
+    let inserted = diesel::insert_or_ignore_into(episode_notifications::table)
+        .values(&record)
+        .execute(conn)
+        .map_err(|e| format!("记录剧集入库失败: {}", e))?;
+
+    Ok(inserted > 0)
+}
+
+/// 推送等待时间超过 `window_secs` 的剧集汇总，每部剧一条消息。返回推送的剧集数量
+pub async fn flush_due(bot: &Bot, config: &EventNotificationConfig, window_secs: i64) -> Result<usize, String> {
+    let mut conn = database::establish_connection()
+        .map_err(|e| format!("数据库连接失败: {}", e))?;
+
+    let pending = episode_notifications::table
+        .filter(episode_notifications::notified_at.is_null())
+        .order((
+            episode_notifications::series_id.asc(),
+            episode_notifications::season_number.asc(),
+            episode_notifications::episode_number.asc(),
+        ))
+        .load::<EpisodeNotification>(&mut conn)
+        .map_err(|e| format!("数据库查询失败: {}", e))?;
+
+    let mut by_series: BTreeMap<String, Vec<EpisodeNotification>> = BTreeMap::new();
+    for row in pending {
+        by_series.entry(row.series_id.clone()).or_default().push(row);
+    }
+
+    // 窗口从该剧第一集入库开始计算
+    let cutoff = (Utc::now() - Duration::seconds(window_secs)).format(TIME_FORMAT).to_string();
+    let rule = config.rule_for(&EmbyEvent::LibraryNew(ItemKind::Episode));
+
+    let mut flushed = 0;
+    for rows in by_series.values() {
+        let due = rows.iter().map(|row| row.created_at.as_str()).min().is_some_and(|first| first <= cutoff.as_str());
+        if !due {
+            continue;
+        }
+
+        if let Some(rule) = &rule {
+            let message = emby_events::render_values(&rule.template, &digest_values(rows));
+            let library = rows.iter().find_map(|row| row.library.as_deref());
+            let chats = notification::recipients(Some(notification::category::NEW_EPISODES), library, &rule.chats);
+            for chat_id in &chats {
+                bot.send_message(ChatId(*chat_id), message.clone()).await.ok();
+            }
+        }
+
+        let ids: Vec<i32> = rows.iter().map(|row| row.id).collect();
+        diesel::update(episode_notifications::table.filter(episode_notifications::id.eq_any(ids)))
+            .set(episode_notifications::notified_at.eq(Utc::now().format(TIME_FORMAT).to_string()))
+            .execute(&mut conn)
+            .map_err(|e| format!("更新推送状态失败: {}", e))?;
+
+        flushed += 1;
+    }
+
+    let retention_cutoff = (Utc::now() - Duration::seconds(NOTIFIED_RETENTION_SECS)).format(TIME_FORMAT).to_string();
+    diesel::delete(episode_notifications::table.filter(episode_notifications::notified_at.lt(retention_cutoff)))
+        .execute(&mut conn)
+        .map_err(|e| format!("清理已推送的剧集记录失败: {}", e))?;
+
+    Ok(flushed)
+}
+
+/// 定时推送剧集汇总，随 webhook 服务一起启动
+pub async fn run_digest_loop(bot: Bot, config: EventNotificationConfig, window_secs: i64) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(FLUSH_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+        match flush_due(&bot, &config, window_secs).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("已推送 {} 部剧集的入库汇总", count),
+            Err(e) => log::warn!("推送剧集入库汇总失败: {}", e),
+        }
+    }
+}
+
+fn digest_values(rows: &[EpisodeNotification]) -> Vec<(&'static str, String)> {
+    let first = &rows[0];
+    let season_numbers: Vec<String> = dedup_sorted(rows.iter().filter_map(|row| row.season_number))
+        .iter()
+        .map(|season| format!("第 {} 季", season))
+        .collect();
+
+    vec![
+        ("{event}", "library.new".to_string()),
+        ("{item_type}", "Episode".to_string()),
+        ("{item_name}", first.series_name.clone()),
+        ("{series_name}", first.series_name.clone()),
+        ("{season_name}", season_numbers.join("、")),
+        ("{year}", first.production_year.map(|y| y.to_string()).unwrap_or_default()),
+        ("{episodes}", format_episodes(rows)),
+        ("{count}", rows.len().to_string()),
+    ]
+}
+
+/// 将集数整理为 `S01E01–E12, E15、S02E01` 的形式，没有季号时省略 `Sxx`
+fn format_episodes(rows: &[EpisodeNotification]) -> String {
+    let mut seasons: BTreeMap<Option<i32>, Vec<i32>> = BTreeMap::new();
+    let mut unnumbered = 0;
+    for row in rows {
+        match row.episode_number {
+            Some(episode) => seasons.entry(row.season_number).or_default().push(episode),
+            None => unnumbered += 1,
+        }
+    }
+
+    let mut parts: Vec<String> = seasons
+        .into_iter()
+        .map(|(season, episodes)| {
+            let ranges: Vec<String> = episode_ranges(&dedup_sorted(episodes.into_iter()))
+                .into_iter()
+                .map(|(start, end)| if start == end {
+                    format!("E{:02}", start)
+                } else {
+                    format!("E{:02}–E{:02}", start, end)
+                })
+                .collect();
+            match season {
+                Some(season) => format!("S{:02}{}", season, ranges.join(", ")),
+                None => ranges.join(", "),
+            }
+        })
+        .collect();
+
+    if unnumbered > 0 {
+        parts.push(format!("{} 集", unnumbered));
+    }
+
+    parts.join("、")
+}
+
+fn episode_ranges(episodes: &[i32]) -> Vec<(i32, i32)> {
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    for &episode in episodes {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == episode => *end = episode,
+            _ => ranges.push((episode, episode)),
+        }
+    }
+    ranges
+}
+
+fn dedup_sorted(values: impl Iterator<Item = i32>) -> Vec<i32> {
+    let mut values: Vec<i32> = values.collect();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
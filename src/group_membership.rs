@@ -0,0 +1,95 @@
+use std::env;
+
+use teloxide::prelude::*;
+use teloxide::types::{ChatMemberKind, ChatMemberUpdated};
+
+use crate::auth;
+use crate::emby::{self, EmbyApi};
+
+/// 绑定的群组配置。配置 `MEMBER_GROUP_CHAT_ID` 后，用户离开或被踢出该群组时会禁用其 Emby 账户，重新加入时恢复
+#[derive(Debug, Clone)]
+pub struct GroupMembershipConfig {
+    pub group_chat_id: Option<ChatId>,
+    /// 读取 `REGISTER_REQUIRE_GROUP_MEMBERSHIP`，为 true 时 `/register` 要求用户当前在群组中
+    pub require_for_register: bool,
+}
+
+impl GroupMembershipConfig {
+    pub fn from_env() -> Self {
+        Self {
+            group_chat_id: env::var("MEMBER_GROUP_CHAT_ID")
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .map(ChatId),
+            require_for_register: env::var("REGISTER_REQUIRE_GROUP_MEMBERSHIP")
+                .map(|value| value == "true" || value == "1")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// 注册前检查群组成员身份。未开启要求或未配置群组时总是通过
+pub async fn check_register_allowed(bot: &Bot, config: &GroupMembershipConfig, user_id: UserId) -> Result<(), String> {
+    let group_chat_id = match config.group_chat_id {
+        Some(group_chat_id) if config.require_for_register => group_chat_id,
+        _ => return Ok(()),
+    };
+
+    let member = bot
+        .get_chat_member(group_chat_id, user_id)
+        .await
+        .map_err(|e| format!("无法确认群组成员身份，请联系管理员。[Error: {}]", e))?;
+
+    if member.kind.is_present() {
+        Ok(())
+    } else {
+        Err("注册前请先加入群组。".to_string())
+    }
+}
+
+/// 处理绑定群组的 `chat_member` 更新：离开/被踢出时禁用 Emby 账户，重新加入时恢复
+pub async fn handle_member_update<E: EmbyApi>(
+    api: &E,
+    update: &ChatMemberUpdated,
+    config: &GroupMembershipConfig,
+) -> Result<(), String> {
+    if config.group_chat_id != Some(update.chat.id) {
+        return Ok(());
+    }
+
+    let was_present = update.old_chat_member.kind.is_present();
+    let is_present = update.new_chat_member.kind.is_present();
+    if was_present == is_present {
+        return Ok(());
+    }
+
+    let user = &update.new_chat_member.user;
+    let telegram_id = user.id.0 as i64;
+    if !auth::check_registered(telegram_id) {
+        return Ok(());
+    }
+    let emby_user_id = auth::get_emby_id(telegram_id);
+    if emby_user_id.is_empty() {
+        return Ok(());
+    }
+
+    emby::client::set_user_disabled(api, &emby_user_id, !is_present)
+        .await
+        .map_err(|e| e.to_string())?;
+    log::info!(
+        "用户 {} {}群组，已{} Emby 账户 {}",
+        telegram_id,
+        if is_present { "重新加入" } else { describe_leave(&update.new_chat_member.kind) },
+        if is_present { "启用" } else { "禁用" },
+        emby_user_id,
+    );
+    Ok(())
+}
+
+fn describe_leave(kind: &ChatMemberKind) -> &'static str {
+    if kind.is_banned() {
+        "被移出"
+    } else {
+        "离开"
+    }
+}
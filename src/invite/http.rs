@@ -0,0 +1,106 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use diesel::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::database;
+use crate::web_auth;
+
+use super::service::{self, InviteError, InviteSummary};
+
+#[derive(Debug, Deserialize)]
+struct CreateInviteRequest {
+    max_uses: Option<i32>,
+    expires_in_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/invites")
+            .route("", web::get().to(list_invites))
+            .route("", web::post().to(create_invite))
+            .route("/{invite_id}/revoke", web::post().to(revoke_invite)),
+    );
+}
+
+async fn list_invites(req: HttpRequest) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
+    let mut conn = match connection() {
+        Ok(conn) => conn,
+        Err(err) => return map_error(err),
+    };
+
+    match service::list_invites(&mut conn) {
+        Ok(invites) => HttpResponse::Ok().json(
+            invites.into_iter().map(InviteSummary::from).collect::<Vec<_>>(),
+        ),
+        Err(err) => map_error(err),
+    }
+}
+
+async fn create_invite(req: HttpRequest, payload: web::Json<CreateInviteRequest>) -> impl Responder {
+    let (_, user) = match web_auth::middleware::verify_admin(&req) {
+        Ok(session) => session,
+        Err(err) => return web_auth::http::map_error(err),
+    };
+
+    let mut conn = match connection() {
+        Ok(conn) => conn,
+        Err(err) => return map_error(err),
+    };
+
+    match service::create_invite(
+        &mut conn,
+        user.telegram_id,
+        payload.max_uses.unwrap_or(1),
+        payload.expires_in_secs,
+    ) {
+        Ok(invite) => HttpResponse::Ok().json(InviteSummary::from(invite)),
+        Err(err) => map_error(err),
+    }
+}
+
+async fn revoke_invite(req: HttpRequest, path: web::Path<i32>) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
+    let mut conn = match connection() {
+        Ok(conn) => conn,
+        Err(err) => return map_error(err),
+    };
+
+    match service::revoke_invite(&mut conn, path.into_inner()) {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
+        Err(err) => map_error(err),
+    }
+}
+
+fn connection() -> Result<SqliteConnection, InviteError> {
+    database::establish_connection()
+        .map_err(|e| InviteError::Internal(format!("数据库连接失败: {}", e)))
+}
+
+fn map_error(err: InviteError) -> HttpResponse {
+    match err {
+        InviteError::BadRequest(message) => {
+            HttpResponse::BadRequest().json(ErrorResponse { error: message })
+        }
+        InviteError::NotFound(message) => {
+            HttpResponse::NotFound().json(ErrorResponse { error: message })
+        }
+        InviteError::Unavailable(message) => {
+            HttpResponse::Conflict().json(ErrorResponse { error: message })
+        }
+        InviteError::Internal(message) => {
+            HttpResponse::InternalServerError().json(ErrorResponse { error: message })
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use std::env;
+use std::fmt;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::auth;
+use crate::emby::EmbyApi;
+use crate::models::{InviteCode, NewInviteCode, NewInviteRedemption};
+use crate::schema::{invite_codes, invite_redemptions};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const CODE_LENGTH: usize = 10;
+// 去掉容易混淆的 0/O、1/I/L
+const CODE_CHARSET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+#[derive(Debug)]
+pub enum InviteError {
+    BadRequest(String),
+    NotFound(String),
+    /// 邀请码已过期、已撤销或次数已用完
+    Unavailable(String),
+    Internal(String),
+}
+
+impl fmt::Display for InviteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InviteError::BadRequest(message)
+            | InviteError::NotFound(message)
+            | InviteError::Unavailable(message)
+            | InviteError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for InviteError {
+    fn from(err: diesel::result::Error) -> Self {
+        InviteError::Internal(format!("数据库操作失败: {}", err))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct InviteSummary {
+    pub id: i32,
+    pub code: String,
+    pub created_by: i64,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<String>,
+    pub revoked: bool,
+    pub created_at: String,
+    pub usable: bool,
+    pub deep_link: Option<String>,
+}
+
+impl From<InviteCode> for InviteSummary {
+    fn from(invite: InviteCode) -> Self {
+        let usable = check_usable(&invite).is_ok();
+        let deep_link = deep_link(&invite.code);
+        InviteSummary {
+            id: invite.id,
+            code: invite.code,
+            created_by: invite.created_by,
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+            expires_at: invite.expires_at,
+            revoked: invite.revoked,
+            created_at: invite.created_at,
+            usable,
+            deep_link,
+        }
+    }
+}
+
+/// 统一邀请码格式，用户输入时大小写和首尾空白不敏感
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// 通过 `BOT_USERNAME` 生成 `/start <code>` 深链接，未配置时返回 None
+pub fn deep_link(code: &str) -> Option<String> {
+    env::var("BOT_USERNAME")
+        .ok()
+        .map(|username| username.trim().trim_start_matches('@').to_string())
+        .filter(|username| !username.is_empty())
+        .map(|username| format!("https://t.me/{}?start={}", username, code))
+}
+
+/// 创建邀请码，`expires_in_secs` 为 None 表示永不过期
+pub fn create_invite(
+    conn: &mut SqliteConnection,
+    created_by: i64,
+    max_uses: i32,
+    expires_in_secs: Option<i64>,
+) -> Result<InviteCode, InviteError> {
+    if max_uses < 1 {
+        return Err(InviteError::BadRequest("可用次数至少为 1".to_string()));
+    }
+    if expires_in_secs.is_some_and(|secs| secs <= 0) {
+        return Err(InviteError::BadRequest("有效期必须大于 0".to_string()));
+    }
+
+    let expires_at = expires_in_secs
+        .map(|secs| (Utc::now() + Duration::seconds(secs)).format(TIME_FORMAT).to_string());
+
+    let record = NewInviteCode {
+        code: generate_code(),
+        created_by,
+        max_uses,
+        expires_at,
+    };
+
+    diesel::insert_into(invite_codes::table)
+        .values(&record)
+        .execute(conn)?;
+
+    find_by_code(conn, &record.code)
+}
+
+pub fn list_invites(conn: &mut SqliteConnection) -> Result<Vec<InviteCode>, InviteError> {
+    invite_codes::table
+        .order(invite_codes::created_at.desc())
+        .select(InviteCode::as_select())
+        .load(conn)
+        .map_err(InviteError::from)
+}
+
+/// 撤销邀请码，已兑换的记录保留
+pub fn revoke_invite(conn: &mut SqliteConnection, id: i32) -> Result<(), InviteError> {
+    let updated = diesel::update(invite_codes::table.find(id))
+        .set(invite_codes::revoked.eq(true))
+        .execute(conn)?;
+
+    if updated == 0 {
+        return Err(InviteError::NotFound("邀请码不存在".to_string()));
+    }
+    Ok(())
+}
+
+/// 检查邀请码当前是否可用，不消耗次数
+pub fn validate(conn: &mut SqliteConnection, code: &str) -> Result<InviteCode, InviteError> {
+    let invite = find_by_code(conn, code)?;
+    check_usable(&invite)?;
+    Ok(invite)
+}
+
+/// 兑换邀请码：消耗一次使用次数并记录兑换人，返回兑换记录 ID。
+/// 注册失败时应调用 [`release`] 归还次数
+pub fn redeem(conn: &mut SqliteConnection, code: &str, telegram_id: i64) -> Result<i32, InviteError> {
+    conn.transaction(|conn| {
+        let invite = find_by_code(conn, code)?;
+        check_usable(&invite)?;
+
+        // 条件更新，防止并发兑换超出次数
+        let updated = diesel::update(
+            invite_codes::table
+                .find(invite.id)
+                .filter(invite_codes::uses.lt(invite_codes::max_uses)),
+        )
+        .set(invite_codes::uses.eq(invite_codes::uses + 1))
+        .execute(conn)?;
+        if updated == 0 {
+            return Err(InviteError::Unavailable("邀请码使用次数已用完".to_string()));
+        }
+
+        diesel::insert_into(invite_redemptions::table)
+            .values(&NewInviteRedemption {
+                invite_code_id: invite.id,
+                telegram_id,
+            })
+            .execute(conn)?;
+
+        invite_redemptions::table
+            .filter(invite_redemptions::invite_code_id.eq(invite.id))
+            .filter(invite_redemptions::telegram_id.eq(telegram_id))
+            .order(invite_redemptions::id.desc())
+            .select(invite_redemptions::id)
+            .first::<i32>(conn)
+            .map_err(InviteError::from)
+    })
+}
+
+/// 注册成功后记录对应的 Emby 用户
+pub fn complete(conn: &mut SqliteConnection, redemption_id: i32, emby_user_id: &str) -> Result<(), InviteError> {
+    diesel::update(invite_redemptions::table.find(redemption_id))
+        .set(invite_redemptions::emby_user_id.eq(emby_user_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// 撤回一次兑换并归还使用次数
+pub fn release(conn: &mut SqliteConnection, redemption_id: i32) -> Result<(), InviteError> {
+    conn.transaction(|conn| {
+        let invite_code_id = invite_redemptions::table
+            .find(redemption_id)
+            .select(invite_redemptions::invite_code_id)
+            .first::<i32>(conn)
+            .optional()?;
+        let invite_code_id = match invite_code_id {
+            Some(invite_code_id) => invite_code_id,
+            None => return Ok(()),
+        };
+
+        diesel::delete(invite_redemptions::table.find(redemption_id)).execute(conn)?;
+        diesel::update(invite_codes::table.find(invite_code_id).filter(invite_codes::uses.gt(0)))
+            .set(invite_codes::uses.eq(invite_codes::uses - 1))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// 使用邀请码注册 Emby 账户并绑定 Telegram 用户，返回 Emby 用户 ID。
+/// 先占用邀请码次数再创建 Emby 用户，避免并发注册超出次数；创建失败时归还次数
+pub async fn register_with_invite<E: EmbyApi>(
+    api: &E,
+    conn: &mut SqliteConnection,
+    code: &str,
+    telegram_id: i64,
+    username: &str,
+) -> Result<String, String> {
+    let redemption_id = redeem(conn, code, telegram_id).map_err(|e| e.to_string())?;
+
+    match api.create_user(username).await {
+        Ok(user) => {
+            if let Err(e) = complete(conn, redemption_id, &user.id) {
+                log::warn!("记录邀请码兑换结果失败: {}", e);
+            }
+            auth::register(telegram_id, username.to_string(), user.id.clone());
+            Ok(user.id)
+        }
+        Err(e) => {
+            if let Err(e) = release(conn, redemption_id) {
+                log::warn!("归还邀请码使用次数失败: {}", e);
+            }
+            Err(e.to_string())
+        }
+    }
+}
+
+fn find_by_code(conn: &mut SqliteConnection, code: &str) -> Result<InviteCode, InviteError> {
+    invite_codes::table
+        .filter(invite_codes::code.eq(normalize_code(code)))
+        .select(InviteCode::as_select())
+        .first(conn)
+        .optional()?
+        .ok_or_else(|| InviteError::NotFound("邀请码不存在".to_string()))
+}
+
+fn check_usable(invite: &InviteCode) -> Result<(), InviteError> {
+    if invite.revoked {
+        return Err(InviteError::Unavailable("邀请码已被撤销".to_string()));
+    }
+    if let Some(expires_at) = invite.expires_at.as_deref() {
+        if expires_at <= Utc::now().format(TIME_FORMAT).to_string().as_str() {
+            return Err(InviteError::Unavailable("邀请码已过期".to_string()));
+        }
+    }
+    if invite.uses >= invite.max_uses {
+        return Err(InviteError::Unavailable("邀请码使用次数已用完".to_string()));
+    }
+    Ok(())
+}
+
+fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_CHARSET[rng.gen_range(0..CODE_CHARSET.len())] as char)
+        .collect()
+}
@@ -4,36 +4,28 @@ pub mod database;
 
 pub mod auth;
 pub mod bot;
+pub mod dialogue_storage;
 pub mod webhook;
+pub mod webhook_auth;
+pub mod emby_events;
+pub mod episode_digest;
+pub mod notification;
 pub mod static_files;
 pub mod scraper;
 pub mod cli_auth;
+pub mod web_auth;
 pub mod onedrive;
 pub mod media_upload;
-
-use std::env;
-use reqwest::Client;
+pub mod media_request;
+pub mod request_quota;
+pub mod rate_limit;
+pub mod invite;
+pub mod group_membership;
+pub mod emby;
+pub mod account_link;
+pub mod password_reset;
 
 pub fn establish_connection() -> diesel::SqliteConnection {
     // 使用新的数据库模块
     database::establish_connection().expect("Failed to establish database connection")
 }
-
-pub async fn delete_emby_user(user_id: &str) -> Result<(), String> {
-    let client = Client::new();
-    let emby_url = env::var("EMBY_URL").expect("EMBY_URL must be set");
-    let emby_token = env::var("EMBY_TOKEN").expect("EMBY_TOKEN must be set");
-
-    let res = client.delete(format!("{}/Users/{}", emby_url, user_id))
-        .header("X-Emby-Token", emby_token)
-        .send()
-        .await
-        .map_err(|_| "请联系管理员。[Error: Emby API]".to_string())?;
-
-    if res.status().is_success() {
-        Ok(())
-    } else {
-        let error_message = res.text().await.map_err(|_| "请联系管理员。[Error: res.text]".to_string())?;
-        Err(error_message)
-    }
-}
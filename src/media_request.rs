@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::OptionalExtension;
+use teloxide::prelude::*;
+
+use crate::models::{media_request_status, MediaRequest, MediaRequestEvent, MediaRequestNote, NewMediaRequest, NewMediaRequestEvent, NewMediaRequestFollower, NewMediaRequestNote};
+use crate::notification;
+use crate::request_quota::{self, RequestQuotaConfig};
+use crate::schema::{media, media_request_events, media_request_followers, media_request_notes, media_requests};
+
+/// 请求记录及其媒体标题（尚未刮削时为 None）
+pub type RequestWithTitle = (MediaRequest, Option<String>);
+
+#[derive(Debug)]
+pub enum MediaRequestError {
+    NotFound(String),
+    /// 请求参数不正确
+    BadRequest(String),
+    Conflict(String),
+    /// 超出请求配额，消息中包含可以再次提交的时间
+    QuotaExceeded(String),
+    Internal(String),
+}
+
+impl std::fmt::Display for MediaRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaRequestError::NotFound(message)
+            | MediaRequestError::BadRequest(message)
+            | MediaRequestError::Conflict(message)
+            | MediaRequestError::QuotaExceeded(message)
+            | MediaRequestError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for MediaRequestError {
+    fn from(err: diesel::result::Error) -> Self {
+        map_db_err(err)
+    }
+}
+
+pub fn status_text(status: i32) -> &'static str {
+    match status {
+        media_request_status::SUBMITTED => "已提交",
+        media_request_status::ARCHIVED => "已入库",
+        media_request_status::CANCELLED => "已取消",
+        media_request_status::INVALID => "不符合规范",
+        media_request_status::REMOVED => "已从媒体库移除",
+        _ => "未知状态",
+    }
+}
+
+/// 发送给请求者的状态变更通知
+pub fn status_notification_message(request: &MediaRequest, new_status: i32, reason: Option<&str>) -> String {
+    let mut message = format!(
+        "您的媒体请求状态已更新：\n\n📁 来源：{}\n🎬 媒体ID：{}\n📊 状态：{}",
+        request.source,
+        request.media_id,
+        status_text(new_status),
+    );
+    if let Some(reason) = reason {
+        message.push_str(&format!("\n💬 原因：{}", reason));
+    }
+    message.push_str("\n\n");
+    message.push_str(match new_status {
+        media_request_status::ARCHIVED => "恭喜！您的请求已成功入库，现在可以在媒体库中找到相关内容。",
+        media_request_status::INVALID => "抱歉，您的请求不符合我们的规范要求，请检查后重新提交。",
+        media_request_status::CANCELLED => "您的请求已被取消。如有疑问，请联系管理员。",
+        media_request_status::SUBMITTED => "您的请求已重新打开，我们会尽快处理。",
+        media_request_status::REMOVED => "该媒体已从媒体库中移除，如仍需要可以重新提交请求。",
+        _ => "",
+    });
+    message
+}
+
+/// 去掉首尾空白，空字符串视为未填写
+pub fn normalize_reason(reason: Option<&str>) -> Option<&str> {
+    reason.map(str::trim).filter(|reason| !reason.is_empty())
+}
+
+/// 按 `media_request_status::TRANSITIONS` 更新请求状态，同时记录原因和一条状态变更事件，返回更新前的请求记录。
+/// `actor` 为操作者 Telegram ID，系统自动操作时为 None
+pub fn update_status(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    new_status: i32,
+    actor: Option<i64>,
+    reason: Option<&str>,
+) -> Result<MediaRequest, MediaRequestError> {
+    if !media_request_status::is_valid(new_status) {
+        return Err(MediaRequestError::BadRequest(format!("未知的请求状态：{}", new_status)));
+    }
+
+    conn.transaction(|conn| {
+        let request = media_requests::table
+            .filter(media_requests::id.eq(request_id))
+            .first::<MediaRequest>(conn)
+            .optional()
+            .map_err(map_db_err)?
+            .ok_or_else(|| MediaRequestError::NotFound("请求不存在".to_string()))?;
+
+        if !media_request_status::can_transition(request.status, new_status) {
+            return Err(MediaRequestError::Conflict(format!(
+                "请求 #{} 当前状态为「{}」，不能变更为「{}」",
+                request.id,
+                status_text(request.status),
+                status_text(new_status),
+            )));
+        }
+
+        diesel::update(media_requests::table.filter(media_requests::id.eq(request_id)))
+            .set((
+                media_requests::status.eq(new_status),
+                media_requests::status_reason.eq(normalize_reason(reason)),
+                media_requests::updated_at.eq(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+            ))
+            .execute(conn)
+            .map_err(|_| MediaRequestError::Internal("状态更新失败".to_string()))?;
+
+        record_event(conn, request_id, actor, Some(request.status), new_status, reason)?;
+
+        Ok(request)
+    })
+}
+
+/// 记录一条请求事件，创建请求时 `old_status` 为 None
+pub fn record_event(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    actor: Option<i64>,
+    old_status: Option<i32>,
+    new_status: i32,
+    reason: Option<&str>,
+) -> Result<(), MediaRequestError> {
+    diesel::insert_into(media_request_events::table)
+        .values(&NewMediaRequestEvent {
+            media_request_id: request_id,
+            actor,
+            old_status,
+            new_status,
+            reason: normalize_reason(reason).map(ToOwned::to_owned),
+        })
+        .execute(conn)
+        .map(|_| ())
+        .map_err(map_db_err)
+}
+
+/// 请求的完整事件时间线，按发生顺序排列
+pub fn list_events(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+) -> Result<Vec<MediaRequestEvent>, MediaRequestError> {
+    media_request_events::table
+        .filter(media_request_events::media_request_id.eq(request_id))
+        .order(media_request_events::id.asc())
+        .load::<MediaRequestEvent>(conn)
+        .map_err(map_db_err)
+}
+
+/// 单条事件的文字描述，用于 bot 时间线展示
+pub fn describe_event(event: &MediaRequestEvent) -> String {
+    let actor = match event.actor {
+        Some(actor) => actor.to_string(),
+        None => "系统".to_string(),
+    };
+    let mut line = match event.old_status {
+        None => format!("[{}] {} 提交了请求", event.created_at, actor),
+        Some(old_status) => format!(
+            "[{}] {}：{} → {}",
+            event.created_at,
+            actor,
+            status_text(old_status),
+            status_text(event.new_status),
+        ),
+    };
+    if let Some(reason) = &event.reason {
+        line.push_str(&format!("（原因：{}）", reason));
+    }
+    line
+}
+
+/// 分页查询某个用户的请求及其媒体标题，按提交时间倒序。返回当前页和总数
+pub fn list_for_user(
+    conn: &mut diesel::SqliteConnection,
+    user_id: i64,
+    page: i64,
+    page_size: i64,
+) -> Result<(Vec<RequestWithTitle>, i64), MediaRequestError> {
+    let total = media_requests::table
+        .filter(media_requests::request_user.eq(user_id))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(map_db_err)?;
+
+    let requests = media_requests::table
+        .left_join(media::table.on(media::media_request_id.eq(media_requests::id)))
+        .filter(media_requests::request_user.eq(user_id))
+        .order((media_requests::created_at.desc(), media_requests::id.desc()))
+        .limit(page_size)
+        .offset(page * page_size)
+        .select((MediaRequest::as_select(), media::title.nullable()))
+        .load::<RequestWithTitle>(conn)
+        .map_err(map_db_err)?;
+
+    Ok((requests, total))
+}
+
+/// 用户提交媒体请求的处理结果
+#[derive(Debug)]
+pub enum SubmitOutcome {
+    /// 创建了新请求
+    Created(MediaRequest),
+    /// 重新打开了已取消、不符合规范或已移除的请求，`previous` 为更新前的记录
+    Reopened { previous: MediaRequest, request: MediaRequest },
+    /// 请求处理中，用户被记为关注者，`newly` 表示是否为新关注
+    Followed { request: MediaRequest, newly: bool },
+    /// 用户自己提交的请求仍在处理中
+    AlreadyRequested(MediaRequest),
+    /// 请求当前状态不允许重新提交，例如已入库
+    Unavailable(MediaRequest),
+}
+
+/// 提交媒体请求：没有相同请求时新建，已结束的请求重新打开，处理中的请求记为关注。
+/// 新建和重新打开会占用配额，`quota` 为 None 时不检查（管理员）
+pub fn submit(
+    conn: &mut diesel::SqliteConnection,
+    source: &str,
+    media_id: &str,
+    requester: i64,
+    quota: Option<&RequestQuotaConfig>,
+) -> Result<SubmitOutcome, MediaRequestError> {
+    let existing = media_requests::table
+        .filter(media_requests::source.eq(source))
+        .filter(media_requests::media_id.eq(media_id))
+        .first::<MediaRequest>(conn)
+        .optional()
+        .map_err(map_db_err)?;
+
+    match existing {
+        Some(existing) if media_request_status::can_transition(existing.status, media_request_status::SUBMITTED) => {
+            if let Some(quota) = quota {
+                request_quota::check(conn, quota, requester)?;
+            }
+            let request = rerequest(conn, existing.id, requester)?;
+            Ok(SubmitOutcome::Reopened { previous: existing, request })
+        }
+        Some(existing) if existing.status == media_request_status::SUBMITTED => {
+            if existing.request_user == requester {
+                return Ok(SubmitOutcome::AlreadyRequested(existing));
+            }
+            let newly = follow(conn, existing.id, requester)?;
+            Ok(SubmitOutcome::Followed { request: existing, newly })
+        }
+        Some(existing) => Ok(SubmitOutcome::Unavailable(existing)),
+        None => {
+            if let Some(quota) = quota {
+                request_quota::check(conn, quota, requester)?;
+            }
+            let request = conn.transaction(|conn| {
+                diesel::insert_into(media_requests::table)
+                    .values(&NewMediaRequest {
+                        source: source.to_string(),
+                        media_id: media_id.to_string(),
+                        request_user: requester,
+                        status: media_request_status::SUBMITTED,
+                    })
+                    .execute(conn)
+                    .map_err(map_db_err)?;
+
+                let request = media_requests::table
+                    .filter(media_requests::source.eq(source))
+                    .filter(media_requests::media_id.eq(media_id))
+                    .first::<MediaRequest>(conn)
+                    .map_err(map_db_err)?;
+
+                record_event(conn, request.id, Some(requester), None, media_request_status::SUBMITTED, None)?;
+                Ok::<_, MediaRequestError>(request)
+            })?;
+            Ok(SubmitOutcome::Created(request))
+        }
+    }
+}
+
+/// 重新请求已取消、不符合规范或已移除的媒体，请求者变更为 `requester`，返回更新后的请求记录。
+/// 原请求者转为关注者，但自己撤回了请求的不再关注；新请求者不再重复记录为关注者
+pub fn rerequest(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    requester: i64,
+) -> Result<MediaRequest, MediaRequestError> {
+    conn.transaction(|conn| {
+        let withdrawn = withdrawn_by_requester(conn, request_id)?;
+        let previous = update_status(conn, request_id, media_request_status::SUBMITTED, Some(requester), None)?;
+
+        if previous.request_user != requester && !withdrawn {
+            follow(conn, request_id, previous.request_user)?;
+        }
+        diesel::delete(
+            media_request_followers::table
+                .filter(media_request_followers::media_request_id.eq(request_id))
+                .filter(media_request_followers::telegram_id.eq(requester)),
+        )
+        .execute(conn)
+        .map_err(map_db_err)?;
+
+        diesel::update(media_requests::table.filter(media_requests::id.eq(request_id)))
+            .set(media_requests::request_user.eq(requester))
+            .execute(conn)
+            .map_err(map_db_err)?;
+
+        media_requests::table
+            .filter(media_requests::id.eq(request_id))
+            .first::<MediaRequest>(conn)
+            .map_err(map_db_err)
+    })
+}
+
+/// 请求是否处于由请求者本人撤回的已取消状态
+fn withdrawn_by_requester(conn: &mut diesel::SqliteConnection, request_id: i32) -> Result<bool, MediaRequestError> {
+    let request = media_requests::table
+        .filter(media_requests::id.eq(request_id))
+        .first::<MediaRequest>(conn)
+        .optional()
+        .map_err(map_db_err)?;
+    let request = match request {
+        Some(request) if request.status == media_request_status::CANCELLED => request,
+        _ => return Ok(false),
+    };
+
+    let last_actor = media_request_events::table
+        .filter(media_request_events::media_request_id.eq(request_id))
+        .order(media_request_events::id.desc())
+        .select(media_request_events::actor)
+        .first::<Option<i64>>(conn)
+        .optional()
+        .map_err(map_db_err)?
+        .flatten();
+    Ok(last_actor == Some(request.request_user))
+}
+
+/// 请求者撤回自己仍处于已提交状态的请求，并通知关注者和订阅会话，返回更新前的请求记录
+pub async fn cancel_by_requester(
+    bot: &Bot,
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    user_id: i64,
+) -> Result<MediaRequest, MediaRequestError> {
+    ensure_owner(conn, request_id, user_id)?;
+    let request = update_status(conn, request_id, media_request_status::CANCELLED, Some(user_id), None)?;
+    notify_followers(conn, bot, &request, media_request_status::CANCELLED, None).await;
+    notify_subscribers(bot, &request, media_request_status::CANCELLED, None).await;
+    Ok(request)
+}
+
+/// 请求者重新提交自己已取消或不符合规范的请求，并通知关注者和订阅会话，返回更新前的请求记录
+pub async fn reopen_by_requester(
+    bot: &Bot,
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    user_id: i64,
+) -> Result<MediaRequest, MediaRequestError> {
+    ensure_owner(conn, request_id, user_id)?;
+    let request = update_status(conn, request_id, media_request_status::SUBMITTED, Some(user_id), None)?;
+    notify_followers(conn, bot, &request, media_request_status::SUBMITTED, None).await;
+    notify_subscribers(bot, &request, media_request_status::SUBMITTED, None).await;
+    Ok(request)
+}
+
+fn ensure_owner(conn: &mut diesel::SqliteConnection, request_id: i32, user_id: i64) -> Result<(), MediaRequestError> {
+    let owned = media_requests::table
+        .filter(media_requests::id.eq(request_id))
+        .filter(media_requests::request_user.eq(user_id))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(map_db_err)?;
+    if owned == 0 {
+        return Err(MediaRequestError::NotFound("请求不存在".to_string()));
+    }
+    Ok(())
+}
+
+/// 通知请求者状态变更，发送失败只记录日志
+pub async fn notify_requester(bot: &Bot, request: &MediaRequest, new_status: i32, reason: Option<&str>) {
+    let notification_message = status_notification_message(request, new_status, normalize_reason(reason));
+    if bot.send_message(ChatId(request.request_user), notification_message).await.is_err() {
+        log::warn!("Failed to send notification to user {}", request.request_user);
+    }
+}
+
+/// 更新请求状态并通知请求者和订阅了请求状态变更的会话，返回更新前的请求记录
+pub async fn transition(
+    bot: &Bot,
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    new_status: i32,
+    actor: Option<i64>,
+    reason: Option<&str>,
+) -> Result<MediaRequest, MediaRequestError> {
+    let request = update_status(conn, request_id, new_status, actor, reason)?;
+    notify_requester(bot, &request, new_status, reason).await;
+    notify_followers(conn, bot, &request, new_status, reason).await;
+    notify_subscribers(bot, &request, new_status, reason).await;
+    Ok(request)
+}
+
+/// 将用户记录为已有请求的关注者（投票）。返回是否为新关注
+pub fn follow(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    telegram_id: i64,
+) -> Result<bool, MediaRequestError> {
+    let inserted = diesel::insert_or_ignore_into(media_request_followers::table)
+        .values(&NewMediaRequestFollower {
+            media_request_id: request_id,
+            telegram_id,
+        })
+        .execute(conn)
+        .map_err(map_db_err)?;
+
+    Ok(inserted > 0)
+}
+
+pub fn list_followers(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+) -> Result<Vec<i64>, MediaRequestError> {
+    media_request_followers::table
+        .filter(media_request_followers::media_request_id.eq(request_id))
+        .select(media_request_followers::telegram_id)
+        .load::<i64>(conn)
+        .map_err(map_db_err)
+}
+
+/// 请求的票数：请求者本人加上关注者
+pub fn vote_count(conn: &mut diesel::SqliteConnection, request_id: i32) -> Result<i64, MediaRequestError> {
+    let followers = media_request_followers::table
+        .filter(media_request_followers::media_request_id.eq(request_id))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(map_db_err)?;
+
+    Ok(followers + 1)
+}
+
+/// 所有请求的关注者数量，用于列表排序
+pub fn follower_counts(conn: &mut diesel::SqliteConnection) -> Result<HashMap<i32, i64>, MediaRequestError> {
+    let counts = media_request_followers::table
+        .group_by(media_request_followers::media_request_id)
+        .select((media_request_followers::media_request_id, diesel::dsl::count_star()))
+        .load::<(i32, i64)>(conn)
+        .map_err(map_db_err)?;
+
+    Ok(counts.into_iter().collect())
+}
+
+/// 关注者与请求者收到同样的状态变更通知
+pub async fn notify_followers(
+    conn: &mut diesel::SqliteConnection,
+    bot: &Bot,
+    request: &MediaRequest,
+    new_status: i32,
+    reason: Option<&str>,
+) {
+    let followers = match list_followers(conn, request.id) {
+        Ok(followers) => followers,
+        Err(e) => {
+            log::warn!("Failed to load followers of request {}: {}", request.id, e);
+            return;
+        }
+    };
+
+    let notification_message = status_notification_message(request, new_status, normalize_reason(reason));
+    for follower in followers.into_iter().filter(|follower| *follower != request.request_user) {
+        if bot.send_message(ChatId(follower), notification_message.clone()).await.is_err() {
+            log::warn!("Failed to send notification to follower {}", follower);
+        }
+    }
+}
+
+/// 推送给订阅了请求状态变更的会话
+pub async fn notify_subscribers(bot: &Bot, request: &MediaRequest, new_status: i32, reason: Option<&str>) {
+    let mut message = format!(
+        "媒体请求 #{} 状态变更：\n\n📁 来源：{}\n🎬 媒体ID：{}\n📊 状态：{} → {}",
+        request.id,
+        request.source,
+        request.media_id,
+        status_text(request.status),
+        status_text(new_status),
+    );
+    if let Some(reason) = normalize_reason(reason) {
+        message.push_str(&format!("\n💬 原因：{}", reason));
+    }
+    notification::broadcast(bot, notification::category::REQUEST_STATUS, None, &message).await;
+}
+
+/// 为请求添加仅管理员可见的内部备注
+pub fn add_note(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+    author: i64,
+    content: &str,
+) -> Result<MediaRequestNote, MediaRequestError> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(MediaRequestError::BadRequest("备注内容不能为空".to_string()));
+    }
+
+    let exists = media_requests::table
+        .filter(media_requests::id.eq(request_id))
+        .count()
+        .get_result::<i64>(conn)
+        .map_err(map_db_err)?;
+    if exists == 0 {
+        return Err(MediaRequestError::NotFound("请求不存在".to_string()));
+    }
+
+    diesel::insert_into(media_request_notes::table)
+        .values(&NewMediaRequestNote {
+            media_request_id: request_id,
+            author,
+            content: content.to_string(),
+        })
+        .execute(conn)
+        .map_err(map_db_err)?;
+
+    media_request_notes::table
+        .filter(media_request_notes::media_request_id.eq(request_id))
+        .order(media_request_notes::id.desc())
+        .first::<MediaRequestNote>(conn)
+        .map_err(map_db_err)
+}
+
+pub fn list_notes(
+    conn: &mut diesel::SqliteConnection,
+    request_id: i32,
+) -> Result<Vec<MediaRequestNote>, MediaRequestError> {
+    media_request_notes::table
+        .filter(media_request_notes::media_request_id.eq(request_id))
+        .order(media_request_notes::id.asc())
+        .load::<MediaRequestNote>(conn)
+        .map_err(map_db_err)
+}
+
+fn map_db_err(err: diesel::result::Error) -> MediaRequestError {
+    MediaRequestError::Internal(format!("数据库操作失败: {}", err))
+}
@@ -10,6 +10,7 @@ use crate::models::{
     UpdateMediaUploadRequest,
 };
 use crate::schema::{media, media_requests, media_upload_requests};
+use crate::scraper::{self, EpisodeNumbering};
 
 #[derive(Debug)]
 pub enum MediaUploadError {
@@ -92,7 +93,7 @@ pub fn create_upload_request(
         input.season,
         input.episode,
     )?;
-    let target_path = build_target_path(&media_record.title, season);
+    let target_path = build_target_path(&media_record.title, media_record.year, season);
 
     let existing = if let (Some(season), Some(episode)) = (season, episode) {
         media_upload_requests::table
@@ -263,19 +264,23 @@ fn validate_source_and_episode_fields(
     season: Option<i32>,
     episode: Option<i32>,
 ) -> Result<(Option<i32>, Option<i32>), MediaUploadError> {
-    match source {
-        "TMDB/TV" => {
+    let numbering = scraper::provider::provider(source)
+        .map(|provider| provider.episode_numbering())
+        .unwrap_or(EpisodeNumbering::Unsupported);
+
+    match numbering {
+        EpisodeNumbering::Required => {
             let season = season.ok_or_else(|| {
-                MediaUploadError::BadRequest("TMDB/TV 请求必须提供 season".to_string())
+                MediaUploadError::BadRequest(format!("{} 请求必须提供 season", source))
             })?;
             let episode = episode.ok_or_else(|| {
-                MediaUploadError::BadRequest("TMDB/TV 请求必须提供 episode".to_string())
+                MediaUploadError::BadRequest(format!("{} 请求必须提供 episode", source))
             })?;
             validate_positive_number("season", season)?;
             validate_positive_number("episode", episode)?;
             Ok((Some(season), Some(episode)))
         }
-        "BGM.TV" => {
+        EpisodeNumbering::Optional => {
             if let Some(season) = season {
                 validate_positive_number("season", season)?;
             }
@@ -284,18 +289,20 @@ fn validate_source_and_episode_fields(
             }
 
             if season.is_some() ^ episode.is_some() {
-                return Err(MediaUploadError::BadRequest(
-                    "BGM.TV 如果提供 season/episode，必须同时提供".to_string(),
-                ));
+                return Err(MediaUploadError::BadRequest(format!(
+                    "{} 如果提供 season/episode，必须同时提供",
+                    source
+                )));
             }
 
             Ok((season, episode))
         }
-        _ => {
+        EpisodeNumbering::Unsupported => {
             if season.is_some() || episode.is_some() {
-                return Err(MediaUploadError::BadRequest(
-                    "只有 TMDB/TV 或 BGM.TV 可以使用 season/episode".to_string(),
-                ));
+                return Err(MediaUploadError::BadRequest(format!(
+                    "{} 请求不能使用 season/episode",
+                    source
+                )));
             }
             Ok((None, None))
         }
@@ -312,8 +319,12 @@ fn validate_positive_number(name: &str, value: i32) -> Result<(), MediaUploadErr
     Ok(())
 }
 
-fn build_target_path(media_title: &str, season: Option<i32>) -> String {
-    let safe_title = media_title.trim();
+/// Emby 推荐的 `标题 (年份)` 目录结构，没有年份时只使用标题
+fn build_target_path(media_title: &str, year: Option<i32>, season: Option<i32>) -> String {
+    let safe_title = match year {
+        Some(year) => format!("{} ({})", media_title.trim(), year),
+        None => media_title.trim().to_string(),
+    };
     match season {
         Some(season) => format!("/media/series/{}/Season {:02}/", safe_title, season),
         None => format!("/media/movie/{}/", safe_title),
@@ -1,5 +1,23 @@
 use diesel::prelude::*;
 
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::bot_dialogues)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct BotDialogue {
+    pub chat_id: i64,
+    pub state: String,
+    pub updated_at: String,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::bot_dialogues)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewBotDialogue {
+    pub chat_id: i64,
+    pub state: String,
+    pub updated_at: String,
+}
+
 #[derive(Queryable, Selectable, Debug)]
 #[diesel(table_name = crate::schema::cli_login_challenges)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -41,6 +59,167 @@ pub struct NewCliLoginChallenge {
     pub consumed_at: Option<String>,
 }
 
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::episode_notifications)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EpisodeNotification {
+    pub id: i32,
+    pub series_id: String,
+    pub series_name: String,
+    pub production_year: Option<i32>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub created_at: String,
+    pub notified_at: Option<String>,
+    pub library: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::episode_notifications)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewEpisodeNotification {
+    pub series_id: String,
+    pub series_name: String,
+    pub production_year: Option<i32>,
+    pub season_number: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub created_at: String,
+    pub notified_at: Option<String>,
+    pub library: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::notification_subscriptions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NotificationSubscription {
+    pub id: i32,
+    pub chat_id: i64,
+    pub category: String,
+    pub library: String,
+    pub created_by: i64,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::notification_subscriptions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewNotificationSubscription {
+    pub chat_id: i64,
+    pub category: String,
+    pub library: String,
+    pub created_by: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::emby_link_attempts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewEmbyLinkAttempt {
+    pub telegram_id: i64,
+    pub created_at: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::emby_link_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EmbyLinkRequest {
+    pub id: i32,
+    pub telegram_id: i64,
+    pub emby_user_id: String,
+    pub emby_username: String,
+    pub status: i32,
+    pub reviewed_by: Option<i64>,
+    pub created_at: String,
+    pub reviewed_at: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::emby_link_requests)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewEmbyLinkRequest {
+    pub telegram_id: i64,
+    pub emby_user_id: String,
+    pub emby_username: String,
+}
+
+/// 绑定已有 Emby 账户的审核状态
+pub mod link_request_status {
+    pub const PENDING: i32 = 0;
+    pub const APPROVED: i32 = 1;
+    pub const REJECTED: i32 = 2;
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::invite_codes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct InviteCode {
+    pub id: i32,
+    pub code: String,
+    pub created_by: i64,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<String>,
+    pub revoked: bool,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::invite_codes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewInviteCode {
+    pub code: String,
+    pub created_by: i64,
+    pub max_uses: i32,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::invite_redemptions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct InviteRedemption {
+    pub id: i32,
+    pub invite_code_id: i32,
+    pub telegram_id: i64,
+    pub emby_user_id: Option<String>,
+    pub redeemed_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::invite_redemptions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewInviteRedemption {
+    pub invite_code_id: i32,
+    pub telegram_id: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::password_reset_attempts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPasswordResetAttempt {
+    pub telegram_id: i64,
+    pub created_at: String,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::scrape_cache)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ScrapeCacheEntry {
+    pub id: i32,
+    pub source: String,
+    pub media_id: String,
+    pub payload: String,
+    pub fetched_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::scrape_cache)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewScrapeCacheEntry {
+    pub source: String,
+    pub media_id: String,
+    pub payload: String,
+    pub fetched_at: String,
+}
+
 #[derive(Queryable, Selectable)]
 #[diesel(table_name = crate::schema::telegram_users)] // 可选的，但是极大地改善了生成的编译器错误信息。
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -72,6 +251,7 @@ pub struct MediaRequest {
     pub status: i32,
     pub created_at: String,
     pub updated_at: String,
+    pub status_reason: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -84,6 +264,58 @@ pub struct NewMediaRequest {
     pub status: i32,
 }
 
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::media_request_events)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct MediaRequestEvent {
+    pub id: i32,
+    pub media_request_id: i32,
+    pub actor: Option<i64>,
+    pub old_status: Option<i32>,
+    pub new_status: i32,
+    pub reason: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::media_request_events)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewMediaRequestEvent {
+    pub media_request_id: i32,
+    pub actor: Option<i64>,
+    pub old_status: Option<i32>,
+    pub new_status: i32,
+    pub reason: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::media_request_followers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewMediaRequestFollower {
+    pub media_request_id: i32,
+    pub telegram_id: i64,
+}
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::schema::media_request_notes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct MediaRequestNote {
+    pub id: i32,
+    pub media_request_id: i32,
+    pub author: i64,
+    pub content: String,
+    pub created_at: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::media_request_notes)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewMediaRequestNote {
+    pub media_request_id: i32,
+    pub author: i64,
+    pub content: String,
+}
+
 #[derive(Queryable, Selectable, Debug)]
 #[diesel(table_name = crate::schema::media)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -95,6 +327,20 @@ pub struct Media {
     pub poster: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    pub year: Option<i32>,
+    pub original_title: Option<String>,
+    /// 逗号分隔的类型
+    pub genres: Option<String>,
+    /// 单位分钟
+    pub runtime: Option<i32>,
+    pub season_count: Option<i32>,
+    pub episode_count: Option<i32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<String>,
+    pub tmdb_id: Option<String>,
+    pub bgm_id: Option<String>,
+    /// 最近一次定时刷新的时间，无论成功与否
+    pub refresh_attempted_at: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -105,6 +351,18 @@ pub struct NewMedia {
     pub title: String,
     pub summary: Option<String>,
     pub poster: Option<String>,
+    pub year: Option<i32>,
+    pub original_title: Option<String>,
+    /// 逗号分隔的类型
+    pub genres: Option<String>,
+    /// 单位分钟
+    pub runtime: Option<i32>,
+    pub season_count: Option<i32>,
+    pub episode_count: Option<i32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<String>,
+    pub tmdb_id: Option<String>,
+    pub bgm_id: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Debug)]
@@ -161,6 +419,29 @@ pub mod media_request_status {
     pub const ARCHIVED: i32 = 1;    // 已入库
     pub const CANCELLED: i32 = 2;   // 被取消
     pub const INVALID: i32 = 3;     // 不符合规范
+    pub const REMOVED: i32 = 4;     // 已从媒体库移除
+
+    pub const ALL: [i32; 5] = [SUBMITTED, ARCHIVED, CANCELLED, INVALID, REMOVED];
+
+    /// 允许的状态变更 (from, to)
+    pub const TRANSITIONS: &[(i32, i32)] = &[
+        (SUBMITTED, ARCHIVED),
+        (SUBMITTED, INVALID),
+        (SUBMITTED, CANCELLED),
+        (CANCELLED, SUBMITTED), // 取消后重新请求
+        (INVALID, SUBMITTED),   // 修正后重新打开
+        (ARCHIVED, REMOVED),    // 从媒体库移除
+        (REMOVED, SUBMITTED),   // 移除后重新请求
+        (REMOVED, ARCHIVED),    // 重新入库
+    ];
+
+    pub fn is_valid(status: i32) -> bool {
+        ALL.contains(&status)
+    }
+
+    pub fn can_transition(from: i32, to: i32) -> bool {
+        TRANSITIONS.contains(&(from, to))
+    }
 }
 
 pub mod cli_login_challenge_status {
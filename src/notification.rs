@@ -0,0 +1,198 @@
+use std::env;
+
+use diesel::prelude::*;
+use teloxide::prelude::*;
+
+use crate::database;
+use crate::emby_events::{EmbyEvent, ItemKind};
+use crate::models::{NewNotificationSubscription, NotificationSubscription};
+use crate::schema::{app_markers, notification_subscriptions};
+
+/// 可订阅的通知类别
+pub mod category {
+    pub const NEW_MOVIES: &str = "movies";
+    pub const NEW_EPISODES: &str = "episodes";
+    pub const REQUEST_STATUS: &str = "requests";
+
+    pub const ALL: [&str; 3] = [NEW_MOVIES, NEW_EPISODES, REQUEST_STATUS];
+}
+
+pub fn category_label(category: &str) -> &'static str {
+    match category {
+        category::NEW_MOVIES => "新电影入库",
+        category::NEW_EPISODES => "新剧集入库",
+        category::REQUEST_STATUS => "媒体请求状态变更",
+        _ => "未知类别",
+    }
+}
+
+/// 将用户输入的类别名称转换为内部类别，支持英文和中文别名
+pub fn parse_category(input: &str) -> Option<&'static str> {
+    match input.trim().to_lowercase().as_str() {
+        "movies" | "movie" | "电影" => Some(category::NEW_MOVIES),
+        "episodes" | "episode" | "series" | "tv" | "剧集" => Some(category::NEW_EPISODES),
+        "requests" | "request" | "请求" => Some(category::REQUEST_STATUS),
+        _ => None,
+    }
+}
+
+/// Emby 事件对应的订阅类别，没有对应类别的事件只按事件规则中的 chats 推送
+pub fn category_for_event(event: &EmbyEvent) -> Option<&'static str> {
+    match event {
+        EmbyEvent::LibraryNew(ItemKind::Movie) => Some(category::NEW_MOVIES),
+        EmbyEvent::LibraryNew(ItemKind::Series | ItemKind::Season | ItemKind::Episode) => Some(category::NEW_EPISODES),
+        _ => None,
+    }
+}
+
+/// 添加订阅，`library` 为 None 表示全部媒体库。返回是否为新订阅
+pub fn subscribe(
+    conn: &mut SqliteConnection,
+    chat_id: i64,
+    category: &str,
+    library: Option<&str>,
+    created_by: i64,
+) -> Result<bool, String> {
+    let inserted = diesel::insert_or_ignore_into(notification_subscriptions::table)
+        .values(&NewNotificationSubscription {
+            chat_id,
+            category: category.to_string(),
+            library: library.unwrap_or_default().to_string(),
+            created_by,
+        })
+        .execute(conn)
+        .map_err(|e| format!("添加订阅失败: {}", e))?;
+
+    Ok(inserted > 0)
+}
+
+/// 取消订阅，`category` 为 None 时取消该会话的全部订阅。返回删除的订阅数量
+pub fn unsubscribe(
+    conn: &mut SqliteConnection,
+    chat_id: i64,
+    category: Option<&str>,
+    library: Option<&str>,
+) -> Result<usize, String> {
+    let mut query = diesel::delete(notification_subscriptions::table)
+        .filter(notification_subscriptions::chat_id.eq(chat_id))
+        .into_boxed();
+    if let Some(category) = category {
+        query = query
+            .filter(notification_subscriptions::category.eq(category))
+            .filter(notification_subscriptions::library.eq(library.unwrap_or_default()));
+    }
+
+    query.execute(conn).map_err(|e| format!("取消订阅失败: {}", e))
+}
+
+pub fn list_for_chat(conn: &mut SqliteConnection, chat_id: i64) -> Result<Vec<NotificationSubscription>, String> {
+    notification_subscriptions::table
+        .filter(notification_subscriptions::chat_id.eq(chat_id))
+        .order((notification_subscriptions::category.asc(), notification_subscriptions::library.asc()))
+        .load::<NotificationSubscription>(conn)
+        .map_err(|e| format!("数据库查询失败: {}", e))
+}
+
+/// 订阅了该类别的会话。未限定媒体库的订阅总是匹配，限定媒体库的订阅只匹配同名媒体库
+pub fn subscribed_chats(
+    conn: &mut SqliteConnection,
+    category: &str,
+    library: Option<&str>,
+) -> Result<Vec<i64>, String> {
+    let subscriptions = notification_subscriptions::table
+        .filter(notification_subscriptions::category.eq(category))
+        .load::<NotificationSubscription>(conn)
+        .map_err(|e| format!("数据库查询失败: {}", e))?;
+
+    let mut chats: Vec<i64> = subscriptions
+        .into_iter()
+        .filter(|subscription| {
+            subscription.library.is_empty()
+                || library.is_some_and(|library| subscription.library.eq_ignore_ascii_case(library))
+        })
+        .map(|subscription| subscription.chat_id)
+        .collect();
+    chats.sort_unstable();
+    chats.dedup();
+
+    Ok(chats)
+}
+
+/// 合并固定的目标会话和订阅会话，去重后返回
+pub fn recipients(category: Option<&str>, library: Option<&str>, static_chats: &[i64]) -> Vec<i64> {
+    let mut chats = static_chats.to_vec();
+
+    if let Some(category) = category {
+        match database::establish_connection() {
+            Ok(mut conn) => match subscribed_chats(&mut conn, category, library) {
+                Ok(subscribed) => chats.extend(subscribed),
+                Err(e) => log::warn!("{}", e),
+            },
+            Err(e) => log::warn!("数据库连接失败: {}", e),
+        }
+    }
+
+    chats.sort_unstable();
+    chats.dedup();
+    chats
+}
+
+/// 推送给订阅了该类别的所有会话，发送失败只记录日志
+pub async fn broadcast(bot: &Bot, category: &str, library: Option<&str>, message: &str) {
+    for chat_id in recipients(Some(category), library, &[]) {
+        if bot.send_message(ChatId(chat_id), message).await.is_err() {
+            log::warn!("Failed to send notification to chat {}", chat_id);
+        }
+    }
+}
+
+/// 导入过旧配置后写入的标记
+const LEGACY_IMPORT_MARKER: &str = "legacy_notify_chats_imported";
+
+/// 将旧的 `WEBHOOK_NOTIFY_CHAT` 配置导入为新电影/新剧集订阅
+pub fn import_legacy_chats_from_env() -> Result<usize, String> {
+    let chat_list = match env::var("WEBHOOK_NOTIFY_CHAT") {
+        Ok(chat_list) if !chat_list.trim().is_empty() => chat_list,
+        _ => return Ok(0),
+    };
+
+    let mut conn = database::establish_connection()
+        .map_err(|e| format!("数据库连接失败: {}", e))?;
+    import_legacy_chats(&mut conn, &chat_list)
+}
+
+/// 只导入一次并写入标记，之后以数据库为准，
+/// 避免管理员取消全部订阅后重启又被恢复
+pub fn import_legacy_chats(conn: &mut SqliteConnection, chat_list: &str) -> Result<usize, String> {
+    let imported_before: i64 = app_markers::table
+        .filter(app_markers::name.eq(LEGACY_IMPORT_MARKER))
+        .count()
+        .get_result(conn)
+        .map_err(|e| format!("查询导入标记失败: {}", e))?;
+    if imported_before > 0 {
+        return Ok(0);
+    }
+
+    let mut imported = 0;
+    for chat in chat_list.split(',').map(str::trim).filter(|chat| !chat.is_empty()) {
+        let chat_id = match chat.parse::<i64>() {
+            Ok(chat_id) => chat_id,
+            Err(_) => {
+                log::warn!("WEBHOOK_NOTIFY_CHAT 中的会话 ID 无效，已忽略: {}", chat);
+                continue;
+            }
+        };
+        for category in [category::NEW_MOVIES, category::NEW_EPISODES] {
+            if subscribe(conn, chat_id, category, None, 0)? {
+                imported += 1;
+            }
+        }
+    }
+
+    diesel::insert_into(app_markers::table)
+        .values(app_markers::name.eq(LEGACY_IMPORT_MARKER))
+        .execute(conn)
+        .map_err(|e| format!("写入导入标记失败: {}", e))?;
+
+    Ok(imported)
+}
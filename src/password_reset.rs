@@ -0,0 +1,128 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::emby::EmbyApi;
+use crate::models::NewPasswordResetAttempt;
+use crate::rate_limit::{self, RollingWindow, TIME_FORMAT};
+use crate::schema::password_reset_attempts;
+
+/// 自助修改/重置密码的限制
+#[derive(Debug, Clone)]
+pub struct PasswordResetConfig {
+    /// 新密码的最小长度
+    pub min_length: usize,
+    /// 新密码至少包含几类字符（小写字母、大写字母、数字、符号）
+    pub min_char_classes: usize,
+    /// 滚动窗口内允许的重置次数，0 表示不限制
+    pub max_per_window: i64,
+    pub window_secs: i64,
+}
+
+impl PasswordResetConfig {
+    pub fn from_env() -> Self {
+        Self {
+            min_length: rate_limit::env_i64("PASSWORD_MIN_LENGTH", 8) as usize,
+            min_char_classes: rate_limit::env_i64("PASSWORD_MIN_CHAR_CLASSES", 2).min(4) as usize,
+            max_per_window: rate_limit::env_i64("PASSWORD_RESET_MAX_PER_WINDOW", 3),
+            window_secs: rate_limit::env_i64("PASSWORD_RESET_WINDOW_SECONDS", 3600),
+        }
+    }
+
+    /// 展示给用户的密码要求
+    pub fn requirements(&self) -> String {
+        format!(
+            "密码至少 {} 位，且至少包含小写字母、大写字母、数字、符号中的 {} 类。",
+            self.min_length, self.min_char_classes
+        )
+    }
+}
+
+/// 检查新密码强度，不满足时返回原因
+pub fn validate_strength(config: &PasswordResetConfig, password: &str) -> Result<(), String> {
+    if password.chars().any(char::is_whitespace) {
+        return Err("密码不能包含空白字符。".to_string());
+    }
+    if password.chars().count() < config.min_length {
+        return Err(format!("密码太短。{}", config.requirements()));
+    }
+
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count();
+    if classes < config.min_char_classes {
+        return Err(format!("密码太简单。{}", config.requirements()));
+    }
+
+    Ok(())
+}
+
+/// 检查用户在窗口内的重置次数，超出时说明何时可以再试
+pub fn check_rate_limit(
+    conn: &mut SqliteConnection,
+    config: &PasswordResetConfig,
+    telegram_id: i64,
+) -> Result<(), String> {
+    let window = RollingWindow::new(config.max_per_window, config.window_secs);
+    let retry_at = window
+        .check(|window_start| {
+            password_reset_attempts::table
+                .filter(password_reset_attempts::telegram_id.eq(telegram_id))
+                .filter(password_reset_attempts::created_at.gt(window_start))
+                .order(password_reset_attempts::created_at.asc())
+                .select(password_reset_attempts::created_at)
+                .load::<String>(conn)
+        })
+        .map_err(|e| format!("数据库查询失败: {}", e))?;
+    if let Some(retry_at) = retry_at {
+        return Err(format!("密码操作过于频繁，请于 {} 之后再试。", retry_at));
+    }
+
+    Ok(())
+}
+
+pub fn record_attempt(conn: &mut SqliteConnection, telegram_id: i64) -> Result<(), String> {
+    diesel::insert_into(password_reset_attempts::table)
+        .values(&NewPasswordResetAttempt {
+            telegram_id,
+            created_at: Utc::now().format(TIME_FORMAT).to_string(),
+        })
+        .execute(conn)
+        .map_err(|e| format!("记录密码操作失败: {}", e))?;
+    Ok(())
+}
+
+/// 为用户设置新密码：检查频率和强度后调用 Emby。强度不满足不计入次数
+pub async fn set_password<E: EmbyApi>(
+    api: &E,
+    conn: &mut SqliteConnection,
+    config: &PasswordResetConfig,
+    telegram_id: i64,
+    emby_user_id: &str,
+    new_password: &str,
+) -> Result<(), String> {
+    check_rate_limit(conn, config, telegram_id)?;
+    validate_strength(config, new_password)?;
+
+    record_attempt(conn, telegram_id)?;
+    api.set_password(emby_user_id, new_password).await.map_err(|e| e.to_string())
+}
+
+/// 将密码重置为空，同样受频率限制
+pub async fn reset_password<E: EmbyApi>(
+    api: &E,
+    conn: &mut SqliteConnection,
+    config: &PasswordResetConfig,
+    telegram_id: i64,
+    emby_user_id: &str,
+) -> Result<(), String> {
+    check_rate_limit(conn, config, telegram_id)?;
+
+    record_attempt(conn, telegram_id)?;
+    api.reset_password(emby_user_id).await.map_err(|e| e.to_string())
+}
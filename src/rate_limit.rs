@@ -0,0 +1,55 @@
+//! 基于数据库记录时间的滚动窗口限制，供请求配额、绑定验证和密码重置共用
+
+use std::env;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+
+/// 数据库中时间字段的格式
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 读取非负整数环境变量，未设置或无法解析时使用默认值
+pub fn env_i64(key: &str, default: i64) -> i64 {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(default)
+}
+
+/// `window_secs` 秒内最多 `max` 次，任一项为 0 表示不限制
+#[derive(Debug, Clone, Copy)]
+pub struct RollingWindow {
+    pub max: i64,
+    pub window_secs: i64,
+}
+
+impl RollingWindow {
+    pub fn new(max: i64, window_secs: i64) -> Self {
+        Self { max, window_secs }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.max > 0 && self.window_secs > 0
+    }
+
+    /// 检查窗口内的记录数。`load_since` 接收窗口起点，返回之后的记录时间（升序）。
+    /// 未达上限时返回 None，否则返回可以再次操作的时间
+    pub fn check<E>(&self, load_since: impl FnOnce(String) -> Result<Vec<String>, E>) -> Result<Option<String>, E> {
+        if !self.enabled() {
+            return Ok(None);
+        }
+
+        let window_start = (Utc::now() - Duration::seconds(self.window_secs)).format(TIME_FORMAT).to_string();
+        let recent = load_since(window_start)?;
+        if (recent.len() as i64) < self.max {
+            return Ok(None);
+        }
+
+        // 窗口内最早的几条记录过期后即可再次操作
+        let index = recent.len() - self.max as usize;
+        let retry_at = NaiveDateTime::parse_from_str(&recent[index], TIME_FORMAT)
+            .map(|at| (at + Duration::seconds(self.window_secs)).format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|_| "稍后".to_string());
+        Ok(Some(retry_at))
+    }
+}
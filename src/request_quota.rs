@@ -0,0 +1,78 @@
+use diesel::prelude::*;
+
+use crate::media_request::MediaRequestError;
+use crate::models::media_request_status;
+use crate::rate_limit::{self, RollingWindow};
+use crate::schema::{media_request_events, media_requests};
+
+/// 普通用户的媒体请求配额，管理员不受限制。各项为 0 表示不限制
+#[derive(Debug, Clone)]
+pub struct RequestQuotaConfig {
+    /// 同时处于已提交状态的请求数上限
+    pub max_open: i64,
+    /// 滚动窗口内新提交（含重新提交）的请求数上限
+    pub max_per_window: i64,
+    pub window_secs: i64,
+}
+
+impl RequestQuotaConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_open: rate_limit::env_i64("REQUEST_MAX_OPEN_PER_USER", 5),
+            max_per_window: rate_limit::env_i64("REQUEST_MAX_PER_WINDOW", 10),
+            window_secs: rate_limit::env_i64("REQUEST_WINDOW_SECONDS", 7 * 24 * 3600),
+        }
+    }
+}
+
+/// 检查用户是否还能提交新的请求，超出配额时返回 `QuotaExceeded` 并说明何时可以再次提交
+pub fn check(
+    conn: &mut SqliteConnection,
+    config: &RequestQuotaConfig,
+    user_id: i64,
+) -> Result<(), MediaRequestError> {
+    if config.max_open > 0 {
+        let open = media_requests::table
+            .filter(media_requests::request_user.eq(user_id))
+            .filter(media_requests::status.eq(media_request_status::SUBMITTED))
+            .count()
+            .get_result::<i64>(conn)?;
+
+        if open >= config.max_open {
+            return Err(MediaRequestError::QuotaExceeded(format!(
+                "您已有 {} 个待处理的请求（上限 {} 个），请等待其中的请求处理完成或取消后再提交。",
+                open, config.max_open
+            )));
+        }
+    }
+
+    let window = RollingWindow::new(config.max_per_window, config.window_secs);
+    let retry_at = window.check(|window_start| {
+        media_request_events::table
+            .filter(media_request_events::actor.eq(user_id))
+            .filter(media_request_events::new_status.eq(media_request_status::SUBMITTED))
+            .filter(media_request_events::created_at.gt(window_start))
+            .order(media_request_events::created_at.asc())
+            .select(media_request_events::created_at)
+            .load::<String>(conn)
+    })?;
+    if let Some(retry_at) = retry_at {
+        return Err(MediaRequestError::QuotaExceeded(format!(
+            "您最近提交的请求过多（每 {} 最多 {} 个），请于 {} 之后再提交。",
+            describe_window(config.window_secs),
+            config.max_per_window,
+            retry_at
+        )));
+    }
+
+    Ok(())
+}
+
+fn describe_window(secs: i64) -> String {
+    match secs {
+        secs if secs % 86400 == 0 => format!("{} 天", secs / 86400),
+        secs if secs % 3600 == 0 => format!("{} 小时", secs / 3600),
+        secs if secs % 60 == 0 => format!("{} 分钟", secs / 60),
+        secs => format!("{} 秒", secs),
+    }
+}
@@ -1,5 +1,20 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    app_markers (name) {
+        name -> Text,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    bot_dialogues (chat_id) {
+        chat_id -> BigInt,
+        state -> Text,
+        updated_at -> Text,
+    }
+}
+
 diesel::table! {
     cli_login_challenges (id) {
         id -> Integer,
@@ -20,6 +35,41 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    emby_link_attempts (id) {
+        id -> Integer,
+        telegram_id -> BigInt,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    emby_link_requests (id) {
+        id -> Integer,
+        telegram_id -> BigInt,
+        emby_user_id -> Text,
+        emby_username -> Text,
+        status -> Integer,
+        reviewed_by -> Nullable<BigInt>,
+        created_at -> Text,
+        reviewed_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    episode_notifications (id) {
+        id -> Integer,
+        series_id -> Text,
+        series_name -> Text,
+        production_year -> Nullable<Integer>,
+        season_number -> Nullable<Integer>,
+        episode_number -> Nullable<Integer>,
+        created_at -> Text,
+        notified_at -> Nullable<Text>,
+        library -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     media_upload_requests (id) {
         id -> Integer,
@@ -37,6 +87,29 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    invite_codes (id) {
+        id -> Integer,
+        code -> Text,
+        created_by -> BigInt,
+        max_uses -> Integer,
+        uses -> Integer,
+        expires_at -> Nullable<Text>,
+        revoked -> Bool,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    invite_redemptions (id) {
+        id -> Integer,
+        invite_code_id -> Integer,
+        telegram_id -> BigInt,
+        emby_user_id -> Nullable<Text>,
+        redeemed_at -> Text,
+    }
+}
+
 diesel::table! {
     media (id) {
         id -> Integer,
@@ -46,6 +119,47 @@ diesel::table! {
         poster -> Nullable<Text>,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        year -> Nullable<Integer>,
+        original_title -> Nullable<Text>,
+        genres -> Nullable<Text>,
+        runtime -> Nullable<Integer>,
+        season_count -> Nullable<Integer>,
+        episode_count -> Nullable<Integer>,
+        imdb_id -> Nullable<Text>,
+        tvdb_id -> Nullable<Text>,
+        tmdb_id -> Nullable<Text>,
+        bgm_id -> Nullable<Text>,
+        refresh_attempted_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    media_request_events (id) {
+        id -> Integer,
+        media_request_id -> Integer,
+        actor -> Nullable<BigInt>,
+        old_status -> Nullable<Integer>,
+        new_status -> Integer,
+        reason -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    media_request_followers (media_request_id, telegram_id) {
+        media_request_id -> Integer,
+        telegram_id -> BigInt,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    media_request_notes (id) {
+        id -> Integer,
+        media_request_id -> Integer,
+        author -> BigInt,
+        content -> Text,
+        created_at -> Text,
     }
 }
 
@@ -58,6 +172,36 @@ diesel::table! {
         status -> Integer,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        status_reason -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    notification_subscriptions (id) {
+        id -> Integer,
+        chat_id -> BigInt,
+        category -> Text,
+        library -> Text,
+        created_by -> BigInt,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    password_reset_attempts (id) {
+        id -> Integer,
+        telegram_id -> BigInt,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    scrape_cache (id) {
+        id -> Integer,
+        source -> Text,
+        media_id -> Text,
+        payload -> Text,
+        fetched_at -> Text,
     }
 }
 
@@ -71,13 +215,30 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(invite_redemptions -> invite_codes (invite_code_id));
 diesel::joinable!(media -> media_requests (media_request_id));
+diesel::joinable!(media_request_events -> media_requests (media_request_id));
+diesel::joinable!(media_request_followers -> media_requests (media_request_id));
+diesel::joinable!(media_request_notes -> media_requests (media_request_id));
 diesel::joinable!(media_upload_requests -> media_requests (media_request_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    app_markers,
+    bot_dialogues,
     cli_login_challenges,
+    emby_link_attempts,
+    emby_link_requests,
+    episode_notifications,
+    invite_codes,
+    invite_redemptions,
     media,
-    media_upload_requests,
+    media_request_events,
+    media_request_followers,
+    media_request_notes,
     media_requests,
+    media_upload_requests,
+    notification_subscriptions,
+    password_reset_attempts,
+    scrape_cache,
     telegram_users,
 );
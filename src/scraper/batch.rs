@@ -0,0 +1,190 @@
+use std::time::Duration;
+
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use serde::Serialize;
+
+use super::{cache, provider, save_media_to_db, ScraperConfig};
+use crate::database;
+use crate::schema::{media, media_requests};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// 定时刷新的检查间隔
+const REFRESH_INTERVAL_SECS: u64 = 6 * 3600;
+/// 每次定时刷新最多处理的条数，剩余的留到下一次
+const REFRESH_BATCH_LIMIT: i64 = 50;
+/// 刷新失败后再次尝试前的等待时间
+const REFRESH_RETRY_AFTER_SECS: i64 = 24 * 3600;
+
+#[derive(Debug, Serialize)]
+pub struct BatchScrapeResult {
+    pub total_processed: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub errors: Vec<String>,
+}
+
+/// 刮削所有还没有媒体信息的请求。`delay` 为两次请求之间的间隔，用于避免触发数据源的频率限制
+pub async fn scrape_missing(
+    conn: &mut SqliteConnection,
+    config: &ScraperConfig,
+    delay: Duration,
+) -> Result<BatchScrapeResult, diesel::result::Error> {
+    // 查询所有没有对应媒体信息的请求
+    let unscraped_requests = media_requests::table
+        .left_join(media::table.on(media::media_request_id.eq(media_requests::id)))
+        .filter(media::id.is_null()) // 没有对应的媒体信息
+        .select((
+            media_requests::id,
+            media_requests::source,
+            media_requests::media_id,
+        ))
+        .load::<(i32, String, String)>(conn)?;
+
+    Ok(scrape_requests(conn, config, unscraped_requests, false, delay).await)
+}
+
+/// 重新刮削超过 `config.refresh_after_days` 天未更新的媒体信息，最多处理 `limit` 条，最久未刷新的优先。
+/// 刷新失败的媒体在 `REFRESH_RETRY_AFTER_SECS` 内不会再次尝试
+pub async fn refresh_stale(
+    conn: &mut SqliteConnection,
+    config: &ScraperConfig,
+    limit: i64,
+    delay: Duration,
+) -> Result<BatchScrapeResult, diesel::result::Error> {
+    let now = chrono::Utc::now();
+    let cutoff = (now - chrono::Duration::days(config.refresh_after_days))
+        .format(TIME_FORMAT)
+        .to_string();
+    let retry_cutoff = (now - chrono::Duration::seconds(REFRESH_RETRY_AFTER_SECS))
+        .format(TIME_FORMAT)
+        .to_string();
+
+    let stale_requests = media::table
+        .inner_join(media_requests::table.on(media_requests::id.eq(media::media_request_id)))
+        .filter(media::updated_at.lt(&cutoff))
+        .filter(
+            media::refresh_attempted_at
+                .is_null()
+                .or(media::refresh_attempted_at.lt(&retry_cutoff)),
+        )
+        .order(sql::<Text>("COALESCE(media.refresh_attempted_at, media.updated_at)").asc())
+        .limit(limit.max(0))
+        .select((
+            media_requests::id,
+            media_requests::source,
+            media_requests::media_id,
+        ))
+        .load::<(i32, String, String)>(conn)?;
+
+    // 先记录尝试时间，刷新失败的媒体会推迟到下一次重试
+    let request_ids: Vec<i32> = stale_requests.iter().map(|(request_id, _, _)| *request_id).collect();
+    diesel::update(media::table.filter(media::media_request_id.eq_any(&request_ids)))
+        .set(media::refresh_attempted_at.eq(now.format(TIME_FORMAT).to_string()))
+        .execute(conn)?;
+
+    // 旧数据要从数据源重新获取，不能使用缓存
+    Ok(scrape_requests(conn, config, stale_requests, true, delay).await)
+}
+
+/// 定时刷新过期的媒体信息并清理过期缓存，随 webhook 服务一起启动
+pub async fn run_refresh_loop(config: ScraperConfig) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(REFRESH_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+
+        let mut conn = match database::establish_connection() {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("数据库连接失败: {}", e);
+                continue;
+            }
+        };
+
+        if let Err(e) = cache::purge_expired(&mut conn, &config) {
+            log::warn!("{}", e);
+        }
+
+        if config.refresh_after_days == 0 {
+            continue;
+        }
+        match refresh_stale(&mut conn, &config, REFRESH_BATCH_LIMIT, Duration::from_millis(500)).await {
+            Ok(result) if result.total_processed == 0 => {}
+            Ok(result) => log::info!("已刷新 {} 条媒体信息，失败 {} 条", result.successful, result.failed),
+            Err(e) => log::warn!("刷新媒体信息失败: {}", e),
+        }
+    }
+}
+
+async fn scrape_requests(
+    conn: &mut SqliteConnection,
+    config: &ScraperConfig,
+    requests: Vec<(i32, String, String)>,
+    bypass_cache: bool,
+    delay: Duration,
+) -> BatchScrapeResult {
+    let total_processed = requests.len();
+    let mut successful = 0;
+    let mut failed = 0;
+    let mut errors = Vec::new();
+
+    // 批量处理每个请求
+    for (request_id, source, media_id) in requests {
+        let provider = match provider::provider(&source) {
+            Some(provider) => provider,
+            None => {
+                let error_msg = format!("请求ID {}: 不支持的媒体源: {}", request_id, source);
+                errors.push(error_msg);
+                failed += 1;
+                continue;
+            }
+        };
+
+        // 调用刮削API
+        let lookup = if bypass_cache {
+            cache::refresh(conn, config, provider, &media_id).await
+        } else {
+            cache::lookup(conn, config, provider, &media_id).await
+        };
+        match lookup {
+            Ok(media_info) => {
+                // 保存媒体信息到数据库
+                match save_media_to_db(conn, request_id, &media_info) {
+                    Ok(_) => {
+                        successful += 1;
+                        log::info!("成功刮削请求ID {}: {} {}", request_id, source, media_id);
+                    },
+                    Err(e) => {
+                        let error_msg = format!("请求ID {}: 保存失败: {:?}", request_id, e);
+                        errors.push(error_msg);
+                        failed += 1;
+                        log::warn!("请求ID {} 保存失败: {:?}", request_id, e);
+                    }
+                }
+            },
+            Err(e) => {
+                let error_msg = format!("请求ID {}: 刮削失败: {}", request_id, e);
+                errors.push(error_msg);
+                failed += 1;
+                log::warn!("请求ID {} 刮削失败: {}", request_id, e);
+            }
+        }
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    log::info!(
+        "批量刮削完成: 总计={}, 成功={}, 失败={}", 
+        total_processed, successful, failed
+    );
+
+    BatchScrapeResult {
+        total_processed,
+        successful,
+        failed,
+        errors,
+    }
+}
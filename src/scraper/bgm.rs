@@ -0,0 +1,282 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::provider::{EpisodeNumbering, MediaProvider, ProviderFuture};
+use super::{extract_year, non_empty, ExternalIds, MediaInfo, ScraperConfig, SearchResult, SEARCH_RESULT_LIMIT};
+
+/// Bangumi 条目类型，对应接口中的 `type` 字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BgmSubjectType {
+    Book,
+    Anime,
+    Music,
+    Game,
+    Real,
+}
+
+impl BgmSubjectType {
+    /// 可以请求的视频类条目
+    pub const VIDEO: [BgmSubjectType; 2] = [BgmSubjectType::Anime, BgmSubjectType::Real];
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            1 => Some(BgmSubjectType::Book),
+            2 => Some(BgmSubjectType::Anime),
+            3 => Some(BgmSubjectType::Music),
+            4 => Some(BgmSubjectType::Game),
+            6 => Some(BgmSubjectType::Real),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            BgmSubjectType::Book => 1,
+            BgmSubjectType::Anime => 2,
+            BgmSubjectType::Music => 3,
+            BgmSubjectType::Game => 4,
+            BgmSubjectType::Real => 6,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BgmSubjectType::Book => "书籍",
+            BgmSubjectType::Anime => "动画",
+            BgmSubjectType::Music => "音乐",
+            BgmSubjectType::Game => "游戏",
+            BgmSubjectType::Real => "三次元",
+        }
+    }
+
+    pub fn is_video(self) -> bool {
+        Self::VIDEO.contains(&self)
+    }
+}
+
+// Bangumi 的条目数据不完整时很多字段会缺失或为空字符串，这里全部按可选处理
+#[derive(Debug, Serialize, Deserialize)]
+struct BgmResponse {
+    name: Option<String>,
+    name_cn: Option<String>,
+    #[serde(default)]
+    summary: Option<String>,
+    images: Option<BgmImages>,
+    date: Option<String>,
+    eps: Option<i32>,
+    total_episodes: Option<i32>,
+    #[serde(default)]
+    meta_tags: Vec<String>,
+    #[serde(default)]
+    infobox: Vec<BgmInfoboxItem>,
+    #[serde(rename = "type")]
+    subject_type: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BgmImages {
+    common: Option<String>,
+}
+
+/// infobox 的值可能是字符串，也可能是 `[{"k": "...", "v": "..."}]` 形式的列表
+#[derive(Debug, Serialize, Deserialize)]
+struct BgmInfoboxItem {
+    key: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+impl BgmInfoboxItem {
+    fn values(&self) -> Vec<String> {
+        let values = match &self.value {
+            serde_json::Value::String(value) => vec![value.clone()],
+            serde_json::Value::Array(items) => items
+                .iter()
+                .filter_map(|item| match item {
+                    serde_json::Value::String(value) => Some(value.clone()),
+                    other => other.get("v").and_then(|v| v.as_str()).map(ToOwned::to_owned),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        values.into_iter().filter_map(|value| non_empty(Some(value))).collect()
+    }
+}
+
+impl BgmResponse {
+    fn infobox_values(&self, key: &str) -> Vec<String> {
+        self.infobox
+            .iter()
+            .filter(|item| item.key == key)
+            .flat_map(BgmInfoboxItem::values)
+            .collect()
+    }
+
+    /// 标题依次使用中文名、infobox 中的中文名、原名和别名
+    fn title(&self) -> Option<String> {
+        non_empty(self.name_cn.clone())
+            .or_else(|| self.infobox_values("中文名").into_iter().next())
+            .or_else(|| non_empty(self.name.clone()))
+            .or_else(|| self.infobox_values("别名").into_iter().next())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BgmSearchResponse {
+    data: Vec<BgmSearchItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BgmSearchItem {
+    id: i64,
+    name: Option<String>,
+    name_cn: Option<String>,
+    date: Option<String>,
+    images: Option<BgmImages>,
+}
+
+/// Bangumi 条目不区分电影和剧集
+pub struct BgmProvider;
+
+impl MediaProvider for BgmProvider {
+    fn source(&self) -> &'static str {
+        "BGM.TV"
+    }
+
+    fn data_source(&self) -> &'static str {
+        "BGM.TV"
+    }
+
+    fn media_type(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn lookup<'a>(&'a self, config: &'a ScraperConfig, media_id: &'a str) -> ProviderFuture<'a, MediaInfo> {
+        Box::pin(scrape_bgm(config, media_id))
+    }
+
+    fn search<'a>(&'a self, config: &'a ScraperConfig, query: &'a str) -> ProviderFuture<'a, Vec<SearchResult>> {
+        Box::pin(search_bgm(config, query))
+    }
+
+    fn link(&self, media_id: &str) -> String {
+        format!("https://bgm.tv/subject/{}", media_id)
+    }
+
+    fn check_requestable(&self, media_info: &MediaInfo) -> Result<(), String> {
+        match media_info.bgm_subject_type {
+            Some(subject_type) if !subject_type.is_video() => Err(format!(
+                "该条目是{}，只能请求动画或三次元条目。",
+                subject_type.label()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Bangumi 条目通常对应单季，剧场版等条目没有集号
+    fn episode_numbering(&self) -> EpisodeNumbering {
+        EpisodeNumbering::Optional
+    }
+
+    fn matches_emby_provider(&self, provider: &str, _is_series: bool) -> bool {
+        provider.eq_ignore_ascii_case("bangumi")
+    }
+}
+
+async fn scrape_bgm(config: &ScraperConfig, media_id: &str) -> Result<MediaInfo, String> {
+    let access_token = config.bgm_access_token.as_deref()
+        .ok_or_else(|| "BGM_ACCESS_TOKEN not found in environment".to_string())?;
+    
+    let client = Client::new();
+    let url = config.bgm_url(&format!("/v0/subjects/{}", media_id));
+    
+    let response = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("User-Agent", "sun00108/nyamedia-bot")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch from BGM: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("BGM API returned status: {}", response.status()));
+    }
+
+    let bgm_data: BgmResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse BGM response: {}", e))?;
+
+    let title = bgm_data.title().unwrap_or_else(|| "Unknown Title".to_string());
+    let original_title = non_empty(bgm_data.name.clone()).filter(|name| *name != title);
+
+    Ok(MediaInfo {
+        title,
+        summary: bgm_data.summary.unwrap_or_default(),
+        poster: bgm_data.images
+            .and_then(|images| images.common)
+            .unwrap_or_default(),
+        year: bgm_data.date
+            .and_then(|date| extract_year(&date))
+            .and_then(|year| year.parse().ok()),
+        original_title,
+        genres: bgm_data.meta_tags,
+        runtime: None,
+        season_count: None,
+        episode_count: bgm_data.total_episodes
+            .or(bgm_data.eps)
+            .filter(|count| *count > 0),
+        external_ids: ExternalIds {
+            bgm: Some(media_id.to_string()),
+            ..Default::default()
+        },
+        bgm_subject_type: bgm_data.subject_type.and_then(BgmSubjectType::from_code),
+    })
+}
+
+async fn search_bgm(config: &ScraperConfig, query: &str) -> Result<Vec<SearchResult>, String> {
+    let access_token = config.bgm_access_token.as_deref()
+        .ok_or_else(|| "BGM_ACCESS_TOKEN not found in environment".to_string())?;
+
+    let client = Client::new();
+    let url = config.bgm_url("/v0/search/subjects");
+    let video_types: Vec<i32> = BgmSubjectType::VIDEO.iter().map(|subject_type| subject_type.code()).collect();
+    let payload = serde_json::json!({
+        "keyword": query,
+        "filter": { "type": video_types }
+    });
+
+    let response = client
+        .post(&url)
+        .query(&[("limit", SEARCH_RESULT_LIMIT.to_string())])
+        .json(&payload)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("User-Agent", "sun00108/nyamedia-bot")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to search BGM: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("BGM API returned status: {}", response.status()));
+    }
+
+    let search_data: BgmSearchResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse BGM search response: {}", e))?;
+
+    Ok(search_data.data
+        .into_iter()
+        .take(SEARCH_RESULT_LIMIT)
+        .map(|item| SearchResult {
+            id: item.id.to_string(),
+            title: non_empty(item.name_cn)
+                .or_else(|| non_empty(item.name))
+                .unwrap_or_else(|| "Unknown Title".to_string()),
+            year: item.date.and_then(|date| extract_year(&date)),
+            poster: item.images
+                .and_then(|images| images.common)
+                .unwrap_or_default(),
+        })
+        .collect())
+}
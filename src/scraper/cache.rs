@@ -0,0 +1,102 @@
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+
+use super::{MediaInfo, MediaProvider, ScraperConfig};
+use crate::models::{NewScrapeCacheEntry, ScrapeCacheEntry};
+use crate::schema::scrape_cache;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 按 (source, media_id) 读取缓存，缓存不存在或已过期时请求数据源并写入缓存
+pub async fn lookup(
+    conn: &mut SqliteConnection,
+    config: &ScraperConfig,
+    provider: &dyn MediaProvider,
+    media_id: &str,
+) -> Result<MediaInfo, String> {
+    if let Some(media_info) = cached(conn, config, provider.source(), media_id) {
+        return Ok(media_info);
+    }
+
+    refresh(conn, config, provider, media_id).await
+}
+
+/// 跳过缓存直接请求数据源，成功后更新缓存
+pub async fn refresh(
+    conn: &mut SqliteConnection,
+    config: &ScraperConfig,
+    provider: &dyn MediaProvider,
+    media_id: &str,
+) -> Result<MediaInfo, String> {
+    let media_info = provider.lookup(config, media_id).await?;
+
+    if config.cache_ttl_secs > 0 {
+        if let Err(e) = store(conn, provider.source(), media_id, &media_info) {
+            log::warn!("写入刮削缓存失败 {} {}: {}", provider.source(), media_id, e);
+        }
+    }
+
+    Ok(media_info)
+}
+
+/// 删除已过期的缓存。返回删除的条数
+pub fn purge_expired(conn: &mut SqliteConnection, config: &ScraperConfig) -> Result<usize, String> {
+    diesel::delete(scrape_cache::table.filter(scrape_cache::fetched_at.lt(cutoff(config.cache_ttl_secs))))
+        .execute(conn)
+        .map_err(|e| format!("清理刮削缓存失败: {}", e))
+}
+
+fn cached(conn: &mut SqliteConnection, config: &ScraperConfig, source: &str, media_id: &str) -> Option<MediaInfo> {
+    if config.cache_ttl_secs <= 0 {
+        return None;
+    }
+
+    let entry = scrape_cache::table
+        .filter(scrape_cache::source.eq(source))
+        .filter(scrape_cache::media_id.eq(media_id))
+        .filter(scrape_cache::fetched_at.ge(cutoff(config.cache_ttl_secs)))
+        .first::<ScrapeCacheEntry>(conn)
+        .optional();
+
+    match entry {
+        Ok(entry) => entry.and_then(|entry| match serde_json::from_str(&entry.payload) {
+            Ok(media_info) => Some(media_info),
+            // 结构变化后旧缓存无法解析，当作未命中
+            Err(e) => {
+                log::debug!("刮削缓存无法解析 {} {}: {}", source, media_id, e);
+                None
+            }
+        }),
+        Err(e) => {
+            log::warn!("读取刮削缓存失败 {} {}: {}", source, media_id, e);
+            None
+        }
+    }
+}
+
+fn store(conn: &mut SqliteConnection, source: &str, media_id: &str, media_info: &MediaInfo) -> Result<(), String> {
+    let payload = serde_json::to_string(media_info).map_err(|e| e.to_string())?;
+    let entry = NewScrapeCacheEntry {
+        source: source.to_string(),
+        media_id: media_id.to_string(),
+        payload,
+        fetched_at: Utc::now().format(TIME_FORMAT).to_string(),
+    };
+
+    diesel::insert_into(scrape_cache::table)
+        .values(&entry)
+        .on_conflict((scrape_cache::source, scrape_cache::media_id))
+        .do_update()
+        .set((
+            scrape_cache::payload.eq(&entry.payload),
+            scrape_cache::fetched_at.eq(&entry.fetched_at),
+        ))
+        .execute(conn)
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn cutoff(ttl_secs: i64) -> String {
+    (Utc::now() - Duration::seconds(ttl_secs)).format(TIME_FORMAT).to_string()
+}
@@ -0,0 +1,180 @@
+pub mod batch;
+pub mod bgm;
+pub mod cache;
+pub mod provider;
+pub mod tmdb;
+
+use std::env;
+
+use serde::{Deserialize, Serialize};
+
+pub use batch::{refresh_stale, run_refresh_loop, scrape_missing, BatchScrapeResult};
+pub use provider::{EpisodeNumbering, MediaProvider, ProviderFuture};
+
+/// 刮削数据源的接口地址和凭据
+#[derive(Debug, Clone)]
+pub struct ScraperConfig {
+    pub tmdb_base_url: String,
+    pub tmdb_access_token: Option<String>,
+    pub bgm_base_url: String,
+    pub bgm_access_token: Option<String>,
+    /// 刮削结果的缓存时间，0 表示不缓存
+    pub cache_ttl_secs: i64,
+    /// 媒体信息超过多少天未更新时由定时任务重新刮削，0 表示不刷新
+    pub refresh_after_days: i64,
+}
+
+impl ScraperConfig {
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: i64| {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+                .filter(|value| *value >= 0)
+                .unwrap_or(default)
+        };
+
+        Self {
+            tmdb_base_url: env::var("TMDB_BASE_URL")
+                .unwrap_or_else(|_| "https://api.themoviedb.org/3".to_string()),
+            tmdb_access_token: env::var("TMDB_ACCESS_TOKEN").ok(),
+            bgm_base_url: env::var("BGM_BASE_URL")
+                .unwrap_or_else(|_| "https://api.bgm.tv".to_string()),
+            bgm_access_token: env::var("BGM_ACCESS_TOKEN").ok(),
+            cache_ttl_secs: read("SCRAPE_CACHE_TTL_SECONDS", 86400),
+            refresh_after_days: read("MEDIA_REFRESH_AFTER_DAYS", 30),
+        }
+    }
+
+    fn tmdb_url(&self, path: &str) -> String {
+        format!("{}{}", self.tmdb_base_url.trim_end_matches('/'), path)
+    }
+
+    fn bgm_url(&self, path: &str) -> String {
+        format!("{}{}", self.bgm_base_url.trim_end_matches('/'), path)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MediaInfo {
+    pub title: String,
+    pub summary: String,
+    pub poster: String,
+    pub year: Option<i32>,
+    pub original_title: Option<String>,
+    pub genres: Vec<String>,
+    /// 单集或电影时长，单位分钟
+    pub runtime: Option<i32>,
+    pub season_count: Option<i32>,
+    pub episode_count: Option<i32>,
+    pub external_ids: ExternalIds,
+    /// 只有 Bangumi 条目有类型
+    pub bgm_subject_type: Option<bgm::BgmSubjectType>,
+}
+
+impl MediaInfo {
+    /// `标题 (年份)`，没有年份时只返回标题
+    pub fn display_title(&self) -> String {
+        match self.year {
+            Some(year) => format!("{} ({})", self.title, year),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// 条目在各数据库中的 ID，用于和 Emby 的 ProviderIds 对照
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExternalIds {
+    pub imdb: Option<String>,
+    pub tvdb: Option<String>,
+    pub tmdb: Option<String>,
+    pub bgm: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub id: String,
+    pub title: String,
+    pub year: Option<String>,
+    pub poster: String,
+}
+
+/// 搜索结果最多返回的条数
+const SEARCH_RESULT_LIMIT: usize = 5;
+
+/// 将 `media.genres` 中逗号分隔的类型拆分为列表
+pub fn split_genres(genres: Option<&str>) -> Vec<String> {
+    genres
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|genre| !genre.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+fn extract_year(date: &str) -> Option<String> {
+    let year = date.get(0..4)?;
+    if year.chars().all(|c| c.is_ascii_digit()) {
+        Some(year.to_string())
+    } else {
+        None
+    }
+}
+
+// 保存媒体信息到数据库
+pub fn save_media_to_db(
+    conn: &mut diesel::SqliteConnection,
+    media_request_id: i32,
+    media_info: &MediaInfo,
+) -> Result<(), diesel::result::Error> {
+    use crate::models::NewMedia;
+    use crate::schema::media;
+    use diesel::prelude::*;
+
+    let new_media = NewMedia {
+        media_request_id,
+        title: media_info.title.clone(),
+        summary: if media_info.summary.is_empty() { None } else { Some(media_info.summary.clone()) },
+        poster: if media_info.poster.is_empty() { None } else { Some(media_info.poster.clone()) },
+        year: media_info.year,
+        original_title: media_info.original_title.clone(),
+        genres: if media_info.genres.is_empty() { None } else { Some(media_info.genres.join(",")) },
+        runtime: media_info.runtime,
+        season_count: media_info.season_count,
+        episode_count: media_info.episode_count,
+        imdb_id: media_info.external_ids.imdb.clone(),
+        tvdb_id: media_info.external_ids.tvdb.clone(),
+        tmdb_id: media_info.external_ids.tmdb.clone(),
+        bgm_id: media_info.external_ids.bgm.clone(),
+    };
+
+    // 插入或更新（基于unique的media_request_id）
+    diesel::insert_into(media::table)
+        .values(&new_media)
+        .on_conflict(media::media_request_id)
+        .do_update()
+        .set((
+            media::title.eq(&new_media.title),
+            media::summary.eq(&new_media.summary),
+            media::poster.eq(&new_media.poster),
+            media::year.eq(new_media.year),
+            media::original_title.eq(&new_media.original_title),
+            media::genres.eq(&new_media.genres),
+            media::runtime.eq(new_media.runtime),
+            media::season_count.eq(new_media.season_count),
+            media::episode_count.eq(new_media.episode_count),
+            media::imdb_id.eq(&new_media.imdb_id),
+            media::tvdb_id.eq(&new_media.tvdb_id),
+            media::tmdb_id.eq(&new_media.tmdb_id),
+            media::bgm_id.eq(&new_media.bgm_id),
+            media::updated_at.eq(diesel::dsl::now),
+        ))
+        .execute(conn)?;
+    
+    Ok(())
+}
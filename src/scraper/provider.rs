@@ -0,0 +1,93 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use super::bgm::BgmProvider;
+use super::tmdb::{TmdbKind, TmdbProvider};
+use super::{MediaInfo, ScraperConfig, SearchResult};
+
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// 上传文件时数据源对季号/集号的要求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpisodeNumbering {
+    /// 不区分季和集，例如电影
+    Unsupported,
+    /// 必须同时提供季号和集号
+    Required,
+    /// 可以不提供，提供时必须同时提供季号和集号
+    Optional,
+}
+
+/// 媒体数据源。每个实现对应 `media_requests.source` 中的一个取值
+pub trait MediaProvider: Send + Sync {
+    /// 保存在 `media_requests.source` 中的值，例如 `TMDB/MV`
+    fn source(&self) -> &'static str;
+
+    /// bot 中选择的数据来源，例如 `TMDB`
+    fn data_source(&self) -> &'static str;
+
+    /// bot 中选择的媒体类型（电影/电视剧），None 表示不区分类型
+    fn media_type(&self) -> Option<&'static str>;
+
+    /// 按 ID 获取媒体信息
+    fn lookup<'a>(&'a self, config: &'a ScraperConfig, media_id: &'a str) -> ProviderFuture<'a, MediaInfo>;
+
+    /// 按标题搜索
+    fn search<'a>(&'a self, config: &'a ScraperConfig, query: &'a str) -> ProviderFuture<'a, Vec<SearchResult>>;
+
+    /// 检查条目能否被请求，例如 Bangumi 中的书籍、游戏条目。不能请求时返回给用户的说明
+    fn check_requestable(&self, media_info: &MediaInfo) -> Result<(), String> {
+        let _ = media_info;
+        Ok(())
+    }
+
+    /// 数据源网站上的条目链接
+    fn link(&self, media_id: &str) -> String;
+
+    /// 上传文件时对季号/集号的要求
+    fn episode_numbering(&self) -> EpisodeNumbering {
+        EpisodeNumbering::Unsupported
+    }
+
+    /// Emby 条目的 ProviderIds 中与本数据源对应的键（忽略大小写）是否匹配。
+    /// `is_series` 表示该条目是否属于剧集
+    fn matches_emby_provider(&self, provider: &str, is_series: bool) -> bool {
+        let _ = (provider, is_series);
+        false
+    }
+}
+
+static TMDB_MOVIE: TmdbProvider = TmdbProvider::new(TmdbKind::Movie);
+static TMDB_TV: TmdbProvider = TmdbProvider::new(TmdbKind::Tv);
+static BGM: BgmProvider = BgmProvider;
+
+static PROVIDERS: [&dyn MediaProvider; 3] = [&TMDB_MOVIE, &TMDB_TV, &BGM];
+
+/// 全部已注册的数据源
+pub fn providers() -> &'static [&'static dyn MediaProvider] {
+    &PROVIDERS
+}
+
+/// 按 `media_requests.source` 查找数据源
+pub fn provider(source: &str) -> Option<&'static dyn MediaProvider> {
+    PROVIDERS.iter().copied().find(|provider| provider.source() == source)
+}
+
+/// 按 bot 中选择的数据来源和媒体类型查找数据源
+pub fn provider_for_selection(data_source: &str, media_type: &str) -> Option<&'static dyn MediaProvider> {
+    PROVIDERS.iter().copied().find(|provider| {
+        provider.data_source() == data_source
+            && provider.media_type().is_none_or(|provider_type| provider_type == media_type)
+    })
+}
+
+/// bot 中可选的数据来源，按注册顺序去重
+pub fn data_sources() -> Vec<&'static str> {
+    let mut sources: Vec<&'static str> = Vec::new();
+    for provider in PROVIDERS.iter() {
+        if !sources.contains(&provider.data_source()) {
+            sources.push(provider.data_source());
+        }
+    }
+    sources
+}
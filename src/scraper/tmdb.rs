@@ -0,0 +1,224 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::provider::{EpisodeNumbering, MediaProvider, ProviderFuture};
+use super::{extract_year, non_empty, ExternalIds, MediaInfo, ScraperConfig, SearchResult, SEARCH_RESULT_LIMIT};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TmdbResponse {
+    title: Option<String>,
+    name: Option<String>, // for TV shows
+    original_title: Option<String>,
+    original_name: Option<String>, // for TV shows
+    overview: String,
+    poster_path: Option<String>,
+    release_date: Option<String>,
+    first_air_date: Option<String>, // for TV shows
+    #[serde(default)]
+    genres: Vec<TmdbGenre>,
+    runtime: Option<i32>,
+    #[serde(default)]
+    episode_run_time: Vec<i32>, // for TV shows
+    number_of_seasons: Option<i32>, // for TV shows
+    number_of_episodes: Option<i32>, // for TV shows
+    imdb_id: Option<String>, // for movies
+    external_ids: Option<TmdbExternalIds>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TmdbGenre {
+    name: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TmdbExternalIds {
+    imdb_id: Option<String>,
+    tvdb_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TmdbSearchResponse {
+    results: Vec<TmdbSearchItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TmdbSearchItem {
+    id: i64,
+    title: Option<String>,
+    name: Option<String>, // for TV shows
+    release_date: Option<String>,
+    first_air_date: Option<String>, // for TV shows
+    poster_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmdbKind {
+    Movie,
+    Tv,
+}
+
+impl TmdbKind {
+    /// TMDB API 路径中的类型
+    fn api_type(self) -> &'static str {
+        match self {
+            TmdbKind::Movie => "movie",
+            TmdbKind::Tv => "tv",
+        }
+    }
+}
+
+pub struct TmdbProvider {
+    kind: TmdbKind,
+}
+
+impl TmdbProvider {
+    pub const fn new(kind: TmdbKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl MediaProvider for TmdbProvider {
+    fn source(&self) -> &'static str {
+        match self.kind {
+            TmdbKind::Movie => "TMDB/MV",
+            TmdbKind::Tv => "TMDB/TV",
+        }
+    }
+
+    fn data_source(&self) -> &'static str {
+        "TMDB"
+    }
+
+    fn media_type(&self) -> Option<&'static str> {
+        match self.kind {
+            TmdbKind::Movie => Some("电影"),
+            TmdbKind::Tv => Some("电视剧"),
+        }
+    }
+
+    fn lookup<'a>(&'a self, config: &'a ScraperConfig, media_id: &'a str) -> ProviderFuture<'a, MediaInfo> {
+        Box::pin(scrape_tmdb(config, self.kind.api_type(), media_id))
+    }
+
+    fn search<'a>(&'a self, config: &'a ScraperConfig, query: &'a str) -> ProviderFuture<'a, Vec<SearchResult>> {
+        Box::pin(search_tmdb(config, self.kind.api_type(), query))
+    }
+
+    fn link(&self, media_id: &str) -> String {
+        format!("https://www.themoviedb.org/{}/{}", self.kind.api_type(), media_id)
+    }
+
+    fn episode_numbering(&self) -> EpisodeNumbering {
+        match self.kind {
+            TmdbKind::Movie => EpisodeNumbering::Unsupported,
+            TmdbKind::Tv => EpisodeNumbering::Required,
+        }
+    }
+
+    fn matches_emby_provider(&self, provider: &str, is_series: bool) -> bool {
+        provider.eq_ignore_ascii_case("tmdb") && is_series == (self.kind == TmdbKind::Tv)
+    }
+}
+
+async fn scrape_tmdb(config: &ScraperConfig, media_type: &str, media_id: &str) -> Result<MediaInfo, String> {
+    let access_token = config.tmdb_access_token.as_deref()
+        .ok_or_else(|| "TMDB_ACCESS_TOKEN not found in environment".to_string())?;
+    
+    let client = Client::new();
+    let url = config.tmdb_url(&format!(
+        "/{}/{}?language=zh-CN&append_to_response=external_ids",
+        media_type, media_id
+    ));
+    
+    let response = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch from TMDB: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("TMDB API returned status: {}", response.status()));
+    }
+
+    let tmdb_data: TmdbResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse TMDB response: {}", e))?;
+
+    let title = tmdb_data.title
+        .or(tmdb_data.name)
+        .unwrap_or_else(|| "Unknown Title".to_string());
+    
+    let poster = tmdb_data.poster_path
+        .map(|path| format!("https://image.tmdb.org/t/p/w500{}", path))
+        .unwrap_or_default();
+
+    let external_ids = tmdb_data.external_ids.unwrap_or_default();
+
+    Ok(MediaInfo {
+        title,
+        summary: tmdb_data.overview,
+        poster,
+        year: tmdb_data.release_date
+            .or(tmdb_data.first_air_date)
+            .and_then(|date| extract_year(&date))
+            .and_then(|year| year.parse().ok()),
+        original_title: non_empty(tmdb_data.original_title.or(tmdb_data.original_name)),
+        genres: tmdb_data.genres.into_iter().map(|genre| genre.name).collect(),
+        runtime: tmdb_data.runtime
+            .or_else(|| tmdb_data.episode_run_time.first().copied())
+            .filter(|runtime| *runtime > 0),
+        season_count: tmdb_data.number_of_seasons,
+        episode_count: tmdb_data.number_of_episodes,
+        external_ids: ExternalIds {
+            imdb: non_empty(external_ids.imdb_id.or(tmdb_data.imdb_id)),
+            tvdb: external_ids.tvdb_id.map(|id| id.to_string()),
+            tmdb: Some(media_id.to_string()),
+            bgm: None,
+        },
+        bgm_subject_type: None,
+    })
+}
+
+async fn search_tmdb(config: &ScraperConfig, media_type: &str, query: &str) -> Result<Vec<SearchResult>, String> {
+    let access_token = config.tmdb_access_token.as_deref()
+        .ok_or_else(|| "TMDB_ACCESS_TOKEN not found in environment".to_string())?;
+
+    let client = Client::new();
+    let url = config.tmdb_url(&format!("/search/{}", media_type));
+
+    let response = client
+        .get(&url)
+        .query(&[("query", query), ("language", "zh-CN")])
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to search TMDB: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("TMDB API returned status: {}", response.status()));
+    }
+
+    let search_data: TmdbSearchResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse TMDB search response: {}", e))?;
+
+    Ok(search_data.results
+        .into_iter()
+        .take(SEARCH_RESULT_LIMIT)
+        .map(|item| SearchResult {
+            id: item.id.to_string(),
+            title: item.title
+                .or(item.name)
+                .unwrap_or_else(|| "Unknown Title".to_string()),
+            year: item.release_date
+                .or(item.first_air_date)
+                .and_then(|date| extract_year(&date)),
+            poster: item.poster_path
+                .map(|path| format!("https://image.tmdb.org/t/p/w500{}", path))
+                .unwrap_or_default(),
+        })
+        .collect())
+}
@@ -0,0 +1,79 @@
+use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::cli_auth::service::TelegramLoginPayload;
+
+use super::middleware;
+use super::service::{self, WebAuthConfig, WebAuthError, SESSION_COOKIE_NAME};
+
+#[derive(Debug, Deserialize)]
+struct TelegramLoginRequest {
+    telegram_login: TelegramLoginPayload,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/web")
+            .route("/login/telegram", web::post().to(login_with_telegram))
+            .route("/session", web::get().to(get_session))
+            .route("/logout", web::post().to(logout)),
+    );
+}
+
+async fn login_with_telegram(payload: web::Json<TelegramLoginRequest>) -> impl Responder {
+    let config = match WebAuthConfig::from_env() {
+        Ok(config) => config,
+        Err(err) => return map_error(err),
+    };
+
+    match service::login_with_telegram(&config, payload.telegram_login.clone()) {
+        Ok(result) => {
+            let cookie = Cookie::build(SESSION_COOKIE_NAME, result.access_token.clone())
+                .path("/")
+                .http_only(true)
+                .secure(config.cookie_secure)
+                .same_site(SameSite::Strict)
+                .max_age(CookieDuration::seconds(config.session_ttl_secs))
+                .finish();
+            HttpResponse::Ok().cookie(cookie).json(result)
+        }
+        Err(err) => map_error(err),
+    }
+}
+
+async fn get_session(req: HttpRequest) -> impl Responder {
+    match middleware::verify_session(&req) {
+        Ok((claims, user)) => HttpResponse::Ok().json(service::session_info(&claims, &user)),
+        Err(err) => map_error(err),
+    }
+}
+
+async fn logout() -> impl Responder {
+    let mut cookie = Cookie::build(SESSION_COOKIE_NAME, "")
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish();
+    cookie.make_removal();
+    HttpResponse::Ok().cookie(cookie).json(serde_json::json!({ "success": true }))
+}
+
+pub fn map_error(err: WebAuthError) -> HttpResponse {
+    match err {
+        WebAuthError::Unauthorized(message) => {
+            HttpResponse::Unauthorized().json(ErrorResponse { error: message })
+        }
+        WebAuthError::Forbidden(message) => {
+            HttpResponse::Forbidden().json(ErrorResponse { error: message })
+        }
+        WebAuthError::Config(message) | WebAuthError::Internal(message) => {
+            HttpResponse::InternalServerError().json(ErrorResponse { error: message })
+        }
+    }
+}
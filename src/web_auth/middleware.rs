@@ -0,0 +1,38 @@
+use actix_web::HttpRequest;
+
+use crate::models::TelegramUser;
+
+use super::service::{self, WebAuthConfig, WebAuthError, SESSION_COOKIE_NAME};
+use super::token::WebSessionClaims;
+
+/// 从 Authorization 头或 session cookie 中读取登录凭证
+pub fn session_token(req: &HttpRequest) -> Option<String> {
+    if let Some(header) = req
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+    {
+        return header.strip_prefix("Bearer ").map(ToOwned::to_owned);
+    }
+
+    req.cookie(SESSION_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_string())
+}
+
+pub fn verify_session(req: &HttpRequest) -> Result<(WebSessionClaims, TelegramUser), WebAuthError> {
+    let config = WebAuthConfig::from_env()?;
+    let token = session_token(req)
+        .ok_or_else(|| WebAuthError::Unauthorized("请先登录".to_string()))?;
+
+    service::authenticate(&config, &token)
+}
+
+pub fn verify_admin(req: &HttpRequest) -> Result<(WebSessionClaims, TelegramUser), WebAuthError> {
+    let (claims, user) = verify_session(req)?;
+
+    if !user.admin {
+        return Err(WebAuthError::Forbidden("需要管理员权限".to_string()));
+    }
+
+    Ok((claims, user))
+}
@@ -0,0 +1,4 @@
+pub mod http;
+pub mod middleware;
+pub mod service;
+pub mod token;
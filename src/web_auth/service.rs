@@ -0,0 +1,178 @@
+use std::env;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use diesel::prelude::*;
+use diesel::OptionalExtension;
+use serde::Serialize;
+
+use crate::cli_auth::service::{self as cli_auth_service, TelegramLoginPayload};
+use crate::database;
+use crate::models::TelegramUser;
+use crate::schema::telegram_users;
+
+use super::token::{self, WebSessionClaims};
+
+pub const SESSION_COOKIE_NAME: &str = "nyamedia_session";
+
+#[derive(Debug, Clone)]
+pub struct WebAuthConfig {
+    pub session_secret: String,
+    pub session_ttl_secs: i64,
+    pub telegram_auth_max_age_secs: i64,
+    pub cookie_secure: bool,
+    pub bot_token: String,
+}
+
+impl WebAuthConfig {
+    pub fn from_env() -> Result<Self, WebAuthError> {
+        Ok(Self {
+            session_secret: env::var("WEB_SESSION_SECRET")
+                .map_err(|_| WebAuthError::Config("WEB_SESSION_SECRET 未配置".to_string()))?,
+            session_ttl_secs: parse_env_i64("WEB_SESSION_TTL_SECONDS", 604_800)?,
+            telegram_auth_max_age_secs: parse_env_i64("TELEGRAM_AUTH_MAX_AGE_SECONDS", 300)?,
+            cookie_secure: env::var("WEB_SESSION_COOKIE_SECURE")
+                .map(|value| value != "false" && value != "0")
+                .unwrap_or(true),
+            bot_token: env::var("TELOXIDE_TOKEN")
+                .map_err(|_| WebAuthError::Config("TELOXIDE_TOKEN 未配置".to_string()))?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum WebAuthError {
+    Config(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Internal(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebLoginResult {
+    pub access_token: String,
+    pub token_type: String,
+    pub telegram_id: i64,
+    pub username: String,
+    pub admin: bool,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebSessionInfo {
+    pub telegram_id: i64,
+    pub telegram_username: String,
+    pub database_username: String,
+    pub admin: bool,
+    pub expires_at: String,
+}
+
+pub fn login_with_telegram(
+    config: &WebAuthConfig,
+    telegram_login: TelegramLoginPayload,
+) -> Result<WebLoginResult, WebAuthError> {
+    cli_auth_service::verify_telegram_payload(
+        &config.bot_token,
+        config.telegram_auth_max_age_secs,
+        &telegram_login,
+    )
+    .map_err(map_cli_auth_err)?;
+
+    let mut conn = database::establish_connection()
+        .map_err(|err| WebAuthError::Internal(format!("数据库连接失败: {}", err)))?;
+
+    let user = find_user(&mut conn, telegram_login.id)?
+        .ok_or_else(|| WebAuthError::Unauthorized("该 Telegram 用户未注册，不能登录".to_string()))?;
+
+    let display_username = telegram_login
+        .username
+        .clone()
+        .unwrap_or_else(|| telegram_login.first_name.clone());
+
+    let (access_token, _claims, created_at, expires_at) = token::issue_web_session_token(
+        &config.session_secret,
+        telegram_login.id,
+        &display_username,
+        config.session_ttl_secs,
+    )
+    .map_err(WebAuthError::Internal)?;
+
+    Ok(WebLoginResult {
+        access_token,
+        token_type: "Bearer".to_string(),
+        telegram_id: user.telegram_id,
+        username: user.username,
+        admin: user.admin,
+        expires_at: timestamp_string(expires_at),
+        created_at: timestamp_string(created_at),
+    })
+}
+
+/// 校验 session token，并确认对应用户仍然存在
+pub fn authenticate(config: &WebAuthConfig, session_token: &str) -> Result<(WebSessionClaims, TelegramUser), WebAuthError> {
+    let claims = token::decode_web_session_token(&config.session_secret, session_token)
+        .map_err(|_| WebAuthError::Unauthorized("登录状态无效或已过期".to_string()))?;
+
+    if claims.kind != "web_session" {
+        return Err(WebAuthError::Unauthorized("登录凭证类型错误".to_string()));
+    }
+
+    let mut conn = database::establish_connection()
+        .map_err(|err| WebAuthError::Internal(format!("数据库连接失败: {}", err)))?;
+
+    let user = find_user(&mut conn, claims.telegram_user_id)?
+        .ok_or_else(|| WebAuthError::Unauthorized("该用户已不存在，请重新登录".to_string()))?;
+
+    Ok((claims, user))
+}
+
+pub fn session_info(claims: &WebSessionClaims, user: &TelegramUser) -> WebSessionInfo {
+    let expires_at = DateTime::<Utc>::from_timestamp(claims.exp as i64, 0)
+        .map(timestamp_string)
+        .unwrap_or_default();
+
+    WebSessionInfo {
+        telegram_id: user.telegram_id,
+        telegram_username: claims.telegram_username.clone(),
+        database_username: user.username.clone(),
+        admin: user.admin,
+        expires_at,
+    }
+}
+
+fn find_user(
+    conn: &mut diesel::SqliteConnection,
+    telegram_id: i64,
+) -> Result<Option<TelegramUser>, WebAuthError> {
+    telegram_users::table
+        .filter(telegram_users::telegram_id.eq(telegram_id))
+        .first::<TelegramUser>(conn)
+        .optional()
+        .map_err(|err| WebAuthError::Internal(format!("数据库操作失败: {}", err)))
+}
+
+fn map_cli_auth_err(err: cli_auth_service::ServiceError) -> WebAuthError {
+    match err {
+        cli_auth_service::ServiceError::Config(message) => WebAuthError::Config(message),
+        cli_auth_service::ServiceError::Internal(message) => WebAuthError::Internal(message),
+        cli_auth_service::ServiceError::Unauthorized(message)
+        | cli_auth_service::ServiceError::BadRequest(message)
+        | cli_auth_service::ServiceError::Conflict(message) => WebAuthError::Unauthorized(message),
+        cli_auth_service::ServiceError::InvalidClientId => {
+            WebAuthError::Unauthorized("client_id 不被允许".to_string())
+        }
+    }
+}
+
+fn parse_env_i64(key: &str, default_value: i64) -> Result<i64, WebAuthError> {
+    match env::var(key) {
+        Ok(value) => value
+            .parse::<i64>()
+            .map_err(|_| WebAuthError::Config(format!("{} 必须是整数", key))),
+        Err(_) => Ok(default_value),
+    }
+}
+
+fn timestamp_string(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSessionClaims {
+    pub kind: String,
+    pub sub: String,
+    pub telegram_user_id: i64,
+    pub telegram_username: String,
+    pub iat: usize,
+    pub exp: usize,
+}
+
+pub fn issue_web_session_token(
+    secret: &str,
+    telegram_user_id: i64,
+    telegram_username: &str,
+    ttl_secs: i64,
+) -> Result<(String, WebSessionClaims, DateTime<Utc>, DateTime<Utc>), String> {
+    let created_at = Utc::now();
+    let expires_at = created_at + Duration::seconds(ttl_secs);
+    let claims = WebSessionClaims {
+        kind: "web_session".to_string(),
+        sub: telegram_user_id.to_string(),
+        telegram_user_id,
+        telegram_username: telegram_username.to_string(),
+        iat: created_at.timestamp() as usize,
+        exp: expires_at.timestamp() as usize,
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|err| err.to_string())?;
+
+    Ok((token, claims, created_at, expires_at))
+}
+
+pub fn decode_web_session_token(secret: &str, token: &str) -> Result<WebSessionClaims, String> {
+    let data = decode::<WebSessionClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|err| err.to_string())?;
+
+    Ok(data.claims)
+}
@@ -2,58 +2,64 @@ use std::env;
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use teloxide::{prelude::*};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::Arc;
 use diesel::prelude::*;
-use crate::models::{MediaRequest, TelegramUser, Media, media_request_status};
+use crate::models::{MediaRequest, MediaRequestNote, TelegramUser, Media, media_request_status};
+use crate::media_request;
 use crate::schema::{media_requests, telegram_users, media};
 use crate::database;
 use crate::static_files;
 use crate::scraper;
 use crate::cli_auth;
+use crate::emby::{self, EmbyApi, EmbyClient};
+use crate::invite;
 use crate::onedrive;
 use crate::media_upload;
-use chrono::Utc;
+use crate::web_auth;
+use crate::episode_digest;
+use crate::notification;
+use crate::webhook_auth::{self, WebhookAuthConfig};
+use crate::emby_events::{self, EmbyEvent, EventNotificationConfig, ItemKind, WebhookPayload, Item};
 
 struct WebhookData {
     bot: Bot,
-    chat_list: Vec<i64>,
-    series_ids: Mutex<Vec<String>>,
+    event_config: EventNotificationConfig,
+    auth: WebhookAuthConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct WebhookPayload {
-    #[serde(rename = "Title")]
-    title: String,
-    #[serde(rename = "Description")]
-    description: String,
-    #[serde(rename = "Date")]
-    date: String,
-    #[serde(rename = "Event")]
-    event: String,
-    #[serde(rename = "Item")]
-    item: Option<Item>,
+struct UpdateRequestPayload {
+    request_id: i32,
+    new_status: i32,
+    /// 状态变更原因，会发送给请求者
+    #[serde(default)]
+    reason: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Item {
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "IndexNumber")]
-    index_number: u16,
-    #[serde(rename = "ProductionYear")]
-    production_year: u16,
-    #[serde(rename = "SeriesName")]
-    series_name: String,
-    #[serde(rename = "SeriesId")]
-    series_id: String,
-    #[serde(rename = "SeasonName")]
-    season_name: String,
+#[derive(Debug, Deserialize)]
+struct CreateRequestNotePayload {
+    content: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UpdateRequestPayload {
-    request_id: i32,
+#[derive(Debug, Serialize)]
+struct MediaRequestEventResponse {
+    id: i32,
+    media_request_id: i32,
+    actor: Option<i64>,
+    old_status: Option<i32>,
     new_status: i32,
+    reason: Option<String>,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MediaRequestNoteResponse {
+    id: i32,
+    media_request_id: i32,
+    author: i64,
+    content: String,
+    created_at: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,44 +89,198 @@ struct CompleteMediaUploadPayload {
     file_name: String,
 }
 
-async fn handle_webhook(payload: web::Json<WebhookPayload>, data: web::Data<Arc<WebhookData>>) -> impl Responder {
-    match payload.event.as_str() {
-        "library.new" => {
-            if let Some(item) = &payload.item {
-                let mut series_ids = data.series_ids.lock().unwrap();
-                if !series_ids.contains(&item.series_id) {
-                    series_ids.push(item.series_id.clone());
-                    for chat_id in &data.chat_list { // <- 之后更改为从数据库中读取
-                        let receipt = ChatId(*chat_id);
-                        data.bot.send_message(receipt, format!("新剧集入库: {} ({})\n{} - 第 {} 集 - {}", item.series_name, item.production_year, item.season_name, item.index_number, item.name)).await.ok();
+async fn handle_webhook(
+    req: actix_web::HttpRequest,
+    body: web::Bytes,
+    data: web::Data<Arc<WebhookData>>
+) -> impl Responder {
+    let peer = req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+
+    if let Err(e) = webhook_auth::verify_request(&data.auth, &req, &body) {
+        log::warn!("拒绝来自 {} 的 webhook 请求: {}", peer, e);
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::warn!("来自 {} 的 webhook payload 解析失败: {}", peer, e);
+            return HttpResponse::BadRequest().finish();
+        }
+    };
+
+    if let Err(e) = webhook_auth::verify_date(&data.auth, payload.date.as_deref()) {
+        log::warn!("拒绝来自 {} 的 webhook 请求: {}", peer, e);
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let event = EmbyEvent::from_payload(&payload);
+
+    let category = notification::category_for_event(&event);
+    // 只有入库类通知支持按媒体库过滤订阅
+    let library = match (category, payload.item.as_ref().and_then(|item| item.id.as_ref())) {
+        (Some(_), Some(item_id)) => match fetch_emby_library_name(item_id).await {
+            Ok(library) => library,
+            Err(e) => {
+                log::warn!("获取媒体库名称失败: {}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    // 单集入库先记录下来，由汇总任务按剧集合并推送
+    let notify_now = match (&event, payload.item.as_ref()) {
+        (EmbyEvent::LibraryNew(ItemKind::Episode), Some(item)) if item.series_id.is_some() => {
+            match database::establish_connection() {
+                Ok(mut conn) => {
+                    if let Err(e) = episode_digest::record_episode(&mut conn, item, library.as_deref()) {
+                        log::warn!("{}", e);
                     }
                 }
-            } else {
-                // 之后实现
+                Err(e) => log::warn!("数据库连接失败: {}", e),
             }
-        },
-        _ => {
-            // 之后实现
+            false
+        }
+        _ => true,
+    };
+
+    if notify_now {
+        if let Some(rule) = data.event_config.rule_for(&event) {
+            let message = emby_events::render_template(&rule.template, &payload);
+            for chat_id in notification::recipients(category, library.as_deref(), &rule.chats) {
+                data.bot.send_message(ChatId(chat_id), message.clone()).await.ok();
+            }
+        }
+    }
+
+    if let (EmbyEvent::LibraryNew(_), Some(item)) = (&event, &payload.item) {
+        match fulfil_media_requests(&data.bot, item).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("{} 入库，自动完成 {} 个媒体请求", item.name.as_deref().unwrap_or_default(), count),
+            Err(e) => log::warn!("自动匹配媒体请求失败: {}", e),
         }
     }
-    HttpResponse::Ok()
+
+    if let (EmbyEvent::LibraryDeleted(_), Some(item)) = (&event, &payload.item) {
+        match remove_media_requests(&data.bot, item).await {
+            Ok(0) => {}
+            Ok(count) => log::info!("{} 已删除，{} 个媒体请求标记为已移除", item.name.as_deref().unwrap_or_default(), count),
+            Err(e) => log::warn!("自动匹配媒体请求失败: {}", e),
+        }
+    }
+
+    HttpResponse::Ok().finish()
 }
 
+/// 根据 Emby 条目的 ProviderIds 匹配已提交或已移除的媒体请求，匹配成功的请求自动标记为已入库
+async fn fulfil_media_requests(bot: &Bot, item: &Item) -> Result<usize, String> {
+    let candidates = request_candidates(item).await?;
+    apply_to_matching_requests(
+        bot,
+        &candidates,
+        &[media_request_status::SUBMITTED, media_request_status::REMOVED],
+        media_request_status::ARCHIVED,
+    ).await
+}
 
-fn status_text(status: i32) -> &'static str {
-    match status {
-        media_request_status::SUBMITTED => "已提交",
-        media_request_status::ARCHIVED => "已入库",
-        media_request_status::CANCELLED => "已取消",
-        media_request_status::INVALID => "不符合规范",
-        _ => "未知状态",
+/// 电影或剧集从 Emby 删除后，将对应的已入库请求标记为已从媒体库移除
+async fn remove_media_requests(bot: &Bot, item: &Item) -> Result<usize, String> {
+    // 单集/单季删除不代表整部剧被移除
+    if !matches!(ItemKind::from_item(Some(item)), ItemKind::Movie | ItemKind::Series) {
+        return Ok(0);
     }
+
+    let candidates = request_candidates(item).await?;
+    apply_to_matching_requests(
+        bot,
+        &candidates,
+        &[media_request_status::ARCHIVED],
+        media_request_status::REMOVED,
+    ).await
+}
+
+/// Emby 条目对应的请求 (source, media_id)
+async fn request_candidates(item: &Item) -> Result<Vec<(&'static str, String)>, String> {
+    // 剧集和季的 ProviderIds 属于单集/单季，需要取所属剧集的 ProviderIds
+    let (provider_ids, is_series) = match (ItemKind::from_item(Some(item)), &item.series_id) {
+        (ItemKind::Episode | ItemKind::Season, Some(series_id)) => (fetch_emby_provider_ids(series_id).await?, true),
+        (ItemKind::Series, _) => (item.provider_ids.clone().unwrap_or_default(), true),
+        (ItemKind::Movie, _) => (item.provider_ids.clone().unwrap_or_default(), false),
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut candidates = Vec::new();
+    for (provider, value) in &provider_ids {
+        if value.is_empty() {
+            continue;
+        }
+        for media_provider in scraper::provider::providers() {
+            if media_provider.matches_emby_provider(provider, is_series) {
+                candidates.push((media_provider.source(), value.clone()));
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+async fn apply_to_matching_requests(
+    bot: &Bot,
+    candidates: &[(&'static str, String)],
+    from_statuses: &[i32],
+    new_status: i32,
+) -> Result<usize, String> {
+    if candidates.is_empty() {
+        return Ok(0);
+    }
+
+    let mut conn = database::establish_connection()
+        .map_err(|e| format!("数据库连接失败: {}", e))?;
+
+    let mut updated = 0;
+    for (source, media_id) in candidates {
+        let matching = media_requests::table
+            .filter(media_requests::source.eq(source))
+            .filter(media_requests::media_id.eq(media_id))
+            .filter(media_requests::status.eq_any(from_statuses))
+            .load::<MediaRequest>(&mut conn)
+            .map_err(|e| format!("数据库查询失败: {}", e))?;
+
+        for request in matching {
+            match media_request::transition(bot, &mut conn, request.id, new_status, None, None).await {
+                Ok(_) => updated += 1,
+                Err(e) => log::warn!("请求ID {} 自动变更为{}失败: {}", request.id, media_request::status_text(new_status), e),
+            }
+        }
+    }
+
+    Ok(updated)
+}
+
+async fn fetch_emby_provider_ids(item_id: &str) -> Result<HashMap<String, String>, String> {
+    let client = EmbyClient::from_env().map_err(|e| e.to_string())?;
+    let items = client.get_items(&[item_id]).await.map_err(|e| e.to_string())?;
+
+    Ok(items.into_iter().next().map(|item| item.provider_ids).unwrap_or_default())
+}
+
+/// 条目所在的 Emby 媒体库名称
+async fn fetch_emby_library_name(item_id: &str) -> Result<Option<String>, String> {
+    let client = EmbyClient::from_env().map_err(|e| e.to_string())?;
+    emby::client::library_name(&client, item_id).await.map_err(|e| e.to_string())
 }
 
 async fn update_request(
+    req: actix_web::HttpRequest,
     payload: web::Json<UpdateRequestPayload>,
     data: web::Data<Arc<WebhookData>>
 ) -> impl Responder {
+    let (_, admin) = match web_auth::middleware::verify_admin(&req) {
+        Ok(session) => session,
+        Err(err) => return web_auth::http::map_error(err),
+    };
+
     let mut conn = match database::establish_connection() {
         Ok(conn) => conn,
         Err(_) => {
@@ -131,75 +291,118 @@ async fn update_request(
         }
     };
 
-    // 获取请求详情
-    let request = match media_requests::table
-        .filter(media_requests::id.eq(payload.request_id))
-        .first::<MediaRequest>(&mut conn)
-    {
-        Ok(request) => request,
+    // 更新请求状态并发送Telegram通知，即使通知发送失败，也返回成功，因为状态已经更新
+    if let Err(err) = media_request::transition(&data.bot, &mut conn, payload.request_id, payload.new_status, Some(admin.telegram_id), payload.reason.as_deref()).await {
+        return HttpResponse::build(media_request_error_status(&err)).json(ApiResponse {
+            success: false,
+            message: err.to_string(),
+        });
+    }
+
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: format!("请求状态已更新为：{}", media_request::status_text(payload.new_status)),
+    })
+}
+
+async fn get_request_events(req: actix_web::HttpRequest, path: web::Path<i32>) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
+    let mut conn = match database::establish_connection() {
+        Ok(conn) => conn,
         Err(_) => {
-            return HttpResponse::BadRequest().json(ApiResponse {
-                success: false,
-                message: "请求不存在".to_string(),
-            });
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "数据库连接失败"
+            }));
         }
     };
 
-    // 检查当前状态是否为已提交
-    if request.status != media_request_status::SUBMITTED {
-        return HttpResponse::BadRequest().json(ApiResponse {
-            success: false,
-            message: "只能操作已提交状态的请求".to_string(),
-        });
+    match media_request::list_events(&mut conn, path.into_inner()) {
+        Ok(events) => HttpResponse::Ok().json(events.into_iter().map(|event| MediaRequestEventResponse {
+            id: event.id,
+            media_request_id: event.media_request_id,
+            actor: event.actor,
+            old_status: event.old_status,
+            new_status: event.new_status,
+            reason: event.reason,
+            created_at: event.created_at,
+        }).collect::<Vec<_>>()),
+        Err(err) => map_media_request_error(err),
     }
+}
 
+async fn get_request_notes(req: actix_web::HttpRequest, path: web::Path<i32>) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
 
-    // 更新请求状态
-    let update_result = diesel::update(media_requests::table.filter(media_requests::id.eq(payload.request_id)))
-        .set((
-            media_requests::status.eq(payload.new_status),
-            media_requests::updated_at.eq(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
-        ))
-        .execute(&mut conn);
+    let mut conn = match database::establish_connection() {
+        Ok(conn) => conn,
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "数据库连接失败"
+            }));
+        }
+    };
 
-    if update_result.is_err() {
-        return HttpResponse::InternalServerError().json(ApiResponse {
-            success: false,
-            message: "状态更新失败".to_string(),
-        });
+    match media_request::list_notes(&mut conn, path.into_inner()) {
+        Ok(notes) => HttpResponse::Ok().json(notes.into_iter().map(note_response).collect::<Vec<_>>()),
+        Err(err) => map_media_request_error(err),
     }
+}
 
-    // 发送Telegram通知
-    let status_text = match payload.new_status {
-        media_request_status::ARCHIVED => "已入库",
-        media_request_status::INVALID => "不符合规范",
-        media_request_status::CANCELLED => "已取消",
-        _ => "未知状态",
+async fn create_request_note(
+    req: actix_web::HttpRequest,
+    path: web::Path<i32>,
+    payload: web::Json<CreateRequestNotePayload>,
+) -> impl Responder {
+    let (_, user) = match web_auth::middleware::verify_admin(&req) {
+        Ok(session) => session,
+        Err(err) => return web_auth::http::map_error(err),
     };
 
-    let notification_message = format!(
-        "您的媒体请求状态已更新：\n\n📁 来源：{}\n🎬 媒体ID：{}\n📊 状态：{}\n\n{}",
-        request.source,
-        request.media_id,
-        status_text,
-        match payload.new_status {
-            media_request_status::ARCHIVED => "恭喜！您的请求已成功入库，现在可以在媒体库中找到相关内容。",
-            media_request_status::INVALID => "抱歉，您的请求不符合我们的规范要求，请检查后重新提交。",
-            media_request_status::CANCELLED => "您的请求已被取消。如有疑问，请联系管理员。",
-            _ => "",
+    let mut conn = match database::establish_connection() {
+        Ok(conn) => conn,
+        Err(_) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "数据库连接失败"
+            }));
         }
-    );
+    };
 
-    let chat_id = ChatId(request.request_user);
-    if let Err(_) = data.bot.send_message(chat_id, notification_message).await {
-        // 即使通知发送失败，也返回成功，因为状态已经更新
-        log::warn!("Failed to send notification to user {}", request.request_user);
+    match media_request::add_note(&mut conn, path.into_inner(), user.telegram_id, &payload.content) {
+        Ok(note) => HttpResponse::Ok().json(note_response(note)),
+        Err(err) => map_media_request_error(err),
     }
+}
 
-    HttpResponse::Ok().json(ApiResponse {
-        success: true,
-        message: format!("请求状态已更新为：{}", status_text),
-    })
+fn note_response(note: MediaRequestNote) -> MediaRequestNoteResponse {
+    MediaRequestNoteResponse {
+        id: note.id,
+        media_request_id: note.media_request_id,
+        author: note.author,
+        content: note.content,
+        created_at: note.created_at,
+    }
+}
+
+fn map_media_request_error(err: media_request::MediaRequestError) -> HttpResponse {
+    HttpResponse::build(media_request_error_status(&err)).json(serde_json::json!({ "error": err.to_string() }))
+}
+
+/// 媒体请求错误对应的 HTTP 状态码，各接口保持一致
+fn media_request_error_status(err: &media_request::MediaRequestError) -> actix_web::http::StatusCode {
+    use actix_web::http::StatusCode;
+
+    match err {
+        media_request::MediaRequestError::NotFound(_) => StatusCode::NOT_FOUND,
+        media_request::MediaRequestError::BadRequest(_) => StatusCode::BAD_REQUEST,
+        media_request::MediaRequestError::Conflict(_) => StatusCode::CONFLICT,
+        media_request::MediaRequestError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+        media_request::MediaRequestError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -215,6 +418,40 @@ struct MediaListResponse {
     id: i32,
     title: String,
     poster: Option<String>,
+    #[serde(flatten)]
+    metadata: MediaMetadata,
+}
+
+/// 刮削得到的扩展信息，尚未刮削时各字段为 null
+#[derive(serde::Serialize, Default)]
+struct MediaMetadata {
+    year: Option<i32>,
+    original_title: Option<String>,
+    genres: Vec<String>,
+    runtime: Option<i32>,
+    season_count: Option<i32>,
+    episode_count: Option<i32>,
+    imdb_id: Option<String>,
+    tvdb_id: Option<String>,
+    tmdb_id: Option<String>,
+    bgm_id: Option<String>,
+}
+
+impl From<&Media> for MediaMetadata {
+    fn from(media: &Media) -> Self {
+        Self {
+            year: media.year,
+            original_title: media.original_title.clone(),
+            genres: scraper::split_genres(media.genres.as_deref()),
+            runtime: media.runtime,
+            season_count: media.season_count,
+            episode_count: media.episode_count,
+            imdb_id: media.imdb_id.clone(),
+            tvdb_id: media.tvdb_id.clone(),
+            tmdb_id: media.tmdb_id.clone(),
+            bgm_id: media.bgm_id.clone(),
+        }
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -223,20 +460,21 @@ struct MediaRequestWithMedia {
     source: String,
     media_id: String,
     status: i32,
+    status_reason: Option<String>,
     created_at: String,
     title: Option<String>,
     poster: Option<String>,
+    #[serde(flatten)]
+    metadata: MediaMetadata,
+    /// 请求者加关注者的人数
+    votes: i64,
 }
 
-#[derive(serde::Serialize)]
-struct BatchScrapeResult {
-    total_processed: usize,
-    successful: usize,
-    failed: usize,
-    errors: Vec<String>,
-}
+async fn get_pending_requests(req: actix_web::HttpRequest) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
 
-async fn get_pending_requests() -> impl Responder {
     let mut conn = match database::establish_connection() {
         Ok(conn) => conn,
         Err(_) => {
@@ -255,23 +493,34 @@ async fn get_pending_requests() -> impl Responder {
             media_requests::source,
             media_requests::media_id,
             media_requests::status,
+            media_requests::status_reason,
             media_requests::created_at,
-            media::title.nullable(),
-            media::poster.nullable(),
+            media::all_columns.nullable(),
         ))
-        .load::<(i32, String, String, i32, String, Option<String>, Option<String>)>(&mut conn);
+        .load::<(i32, String, String, i32, Option<String>, String, Option<Media>)>(&mut conn);
+
+    let follower_counts = match media_request::follower_counts(&mut conn) {
+        Ok(counts) => counts,
+        Err(err) => return map_media_request_error(err),
+    };
 
     match pending_requests_result {
         Ok(requests) => {
-            let response: Vec<MediaRequestWithMedia> = requests.into_iter().map(|(id, source, media_id, status, created_at, title, poster)| MediaRequestWithMedia {
+            let mut response: Vec<MediaRequestWithMedia> = requests.into_iter().map(|(id, source, media_id, status, status_reason, created_at, media)| MediaRequestWithMedia {
                 id,
                 source,
                 media_id,
                 status,
+                status_reason,
                 created_at,
-                title,
-                poster,
+                metadata: media.as_ref().map(MediaMetadata::from).unwrap_or_default(),
+                title: media.as_ref().map(|media| media.title.clone()),
+                poster: media.and_then(|media| media.poster),
+                votes: follower_counts.get(&id).copied().unwrap_or(0) + 1,
             }).collect();
+
+            // 票数多的排在前面，同票数按提交时间先后
+            response.sort_by(|a, b| b.votes.cmp(&a.votes).then_with(|| a.created_at.cmp(&b.created_at)));
             
             HttpResponse::Ok().json(response)
         },
@@ -283,7 +532,11 @@ async fn get_pending_requests() -> impl Responder {
     }
 }
 
-async fn get_archived_requests() -> impl Responder {
+async fn get_archived_requests(req: actix_web::HttpRequest) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
     let mut conn = match database::establish_connection() {
         Ok(conn) => conn,
         Err(_) => {
@@ -302,22 +555,30 @@ async fn get_archived_requests() -> impl Responder {
             media_requests::source,
             media_requests::media_id,
             media_requests::status,
+            media_requests::status_reason,
             media_requests::created_at,
-            media::title,
-            media::poster.nullable(),
+            media::all_columns,
         ))
-        .load::<(i32, String, String, i32, String, String, Option<String>)>(&mut conn);
+        .load::<(i32, String, String, i32, Option<String>, String, Media)>(&mut conn);
+
+    let follower_counts = match media_request::follower_counts(&mut conn) {
+        Ok(counts) => counts,
+        Err(err) => return map_media_request_error(err),
+    };
 
     match archived_requests_result {
         Ok(requests) => {
-            let response: Vec<MediaRequestWithMedia> = requests.into_iter().map(|(id, source, media_id, status, created_at, title, poster)| MediaRequestWithMedia {
+            let response: Vec<MediaRequestWithMedia> = requests.into_iter().map(|(id, source, media_id, status, status_reason, created_at, media)| MediaRequestWithMedia {
                 id,
                 source,
                 media_id,
                 status,
+                status_reason,
                 created_at,
-                title: Some(title),
-                poster,
+                metadata: MediaMetadata::from(&media),
+                title: Some(media.title),
+                poster: media.poster,
+                votes: follower_counts.get(&id).copied().unwrap_or(0) + 1,
             }).collect();
             
             HttpResponse::Ok().json(response)
@@ -330,7 +591,11 @@ async fn get_archived_requests() -> impl Responder {
     }
 }
 
-async fn batch_scrape_media() -> impl Responder {
+async fn batch_scrape_media(req: actix_web::HttpRequest) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
     let mut conn = match database::establish_connection() {
         Ok(conn) => conn,
         Err(_) => {
@@ -340,91 +605,23 @@ async fn batch_scrape_media() -> impl Responder {
         }
     };
 
-    // 查询所有没有对应媒体信息的请求
-    let unscraped_requests_result = media_requests::table
-        .left_join(media::table.on(media::media_request_id.eq(media_requests::id)))
-        .filter(media::id.is_null()) // 没有对应的媒体信息
-        .select((
-            media_requests::id,
-            media_requests::source,
-            media_requests::media_id,
-        ))
-        .load::<(i32, String, String)>(&mut conn);
-
-    let unscraped_requests = match unscraped_requests_result {
-        Ok(requests) => requests,
+    // 每个请求之间稍作等待以避免API限制
+    let delay = std::time::Duration::from_millis(500);
+    match scraper::scrape_missing(&mut conn, &scraper::ScraperConfig::from_env(), delay).await {
+        Ok(result) => HttpResponse::Ok().json(result),
         Err(_) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
+            HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "查询未刮削媒体失败"
-            }));
-        }
-    };
-
-    let total_processed = unscraped_requests.len();
-    let mut successful = 0;
-    let mut failed = 0;
-    let mut errors = Vec::new();
-
-    // 批量处理每个请求
-    for (request_id, source, media_id) in unscraped_requests {
-        // 确定API参数
-        let (api_source, api_media_type) = match source.as_str() {
-            "TMDB/MV" => ("tmdb", "movie"),
-            "TMDB/TV" => ("tmdb", "tv"),
-            "BGM.TV" => ("bgm", "subject"),
-            _ => {
-                let error_msg = format!("请求ID {}: 不支持的媒体源: {}", request_id, source);
-                errors.push(error_msg);
-                failed += 1;
-                continue;
-            }
-        };
-
-        // 调用刮削API
-        match scraper::scrape_media_info(api_source, api_media_type, &media_id).await {
-            Ok(media_info) => {
-                // 保存媒体信息到数据库
-                match scraper::save_media_to_db(&mut conn, request_id, &media_info) {
-                    Ok(_) => {
-                        successful += 1;
-                        log::info!("成功刮削请求ID {}: {} {}", request_id, source, media_id);
-                    },
-                    Err(e) => {
-                        let error_msg = format!("请求ID {}: 保存失败: {:?}", request_id, e);
-                        errors.push(error_msg);
-                        failed += 1;
-                        log::warn!("请求ID {} 保存失败: {:?}", request_id, e);
-                    }
-                }
-            },
-            Err(e) => {
-                let error_msg = format!("请求ID {}: 刮削失败: {}", request_id, e);
-                errors.push(error_msg);
-                failed += 1;
-                log::warn!("请求ID {} 刮削失败: {}", request_id, e);
-            }
+            }))
         }
-
-        // 添加短暂延迟以避免API限制
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
     }
-
-    let result = BatchScrapeResult {
-        total_processed,
-        successful,
-        failed,
-        errors,
-    };
-
-    log::info!(
-        "批量刮削完成: 总计={}, 成功={}, 失败={}", 
-        total_processed, successful, failed
-    );
-
-    HttpResponse::Ok().json(result)
 }
 
-async fn get_media_list() -> impl Responder {
+async fn get_media_list(req: actix_web::HttpRequest) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
     let mut conn = match database::establish_connection() {
         Ok(conn) => conn,
         Err(_) => {
@@ -441,6 +638,7 @@ async fn get_media_list() -> impl Responder {
     match media_result {
         Ok(media_list) => {
             let response: Vec<MediaListResponse> = media_list.into_iter().map(|m| MediaListResponse {
+                metadata: MediaMetadata::from(&m),
                 id: m.id,
                 title: m.title,
                 poster: m.poster,
@@ -456,7 +654,11 @@ async fn get_media_list() -> impl Responder {
     }
 }
 
-async fn check_user_registration(path: web::Path<i64>) -> impl Responder {
+async fn check_user_registration(req: actix_web::HttpRequest, path: web::Path<i64>) -> impl Responder {
+    if let Err(err) = web_auth::middleware::verify_admin(&req) {
+        return web_auth::http::map_error(err);
+    }
+
     let telegram_id = path.into_inner();
     
     let mut conn = match database::establish_connection() {
@@ -686,23 +888,35 @@ async fn complete_media_upload(
 pub async fn run_server() -> std::io::Result<()> {
     println!("Starting Emby Webhook Server...");
 
-    let chat_list = env::var("WEBHOOK_NOTIFY_CHAT");
-    let chat_list = match chat_list {
-        Ok(chat_list) => {
-            chat_list.split(",").map(|x| x.parse::<i64>().unwrap()).collect::<Vec<i64>>()
-        },
-        Err(_) => {
-            Vec::new()
-        }
-    };
+    match notification::import_legacy_chats_from_env() {
+        Ok(0) => {}
+        Ok(count) => log::info!("已将 WEBHOOK_NOTIFY_CHAT 导入为 {} 条通知订阅", count),
+        Err(e) => log::warn!("导入 WEBHOOK_NOTIFY_CHAT 失败: {}", e),
+    }
+
+    let event_config = EventNotificationConfig::from_env()
+        .map_err(std::io::Error::other)?;
+
+    let bot = Bot::from_env();
+    actix_rt::spawn(episode_digest::run_digest_loop(
+        bot.clone(),
+        event_config.clone(),
+        episode_digest::window_secs_from_env(),
+    ));
+    actix_rt::spawn(scraper::run_refresh_loop(scraper::ScraperConfig::from_env()));
+
+    let auth = WebhookAuthConfig::from_env();
+    if !auth.enabled() {
+        log::warn!("WEBHOOK_SECRET 未配置，/webhook 将接受任何请求");
+    }
 
     let data = Arc::new(WebhookData {
-        bot: Bot::from_env(),
-        chat_list,
-        series_ids: Mutex::new(Vec::new()),
+        bot,
+        event_config,
+        auth,
     });
     let onedrive_service = onedrive::service::OnedriveService::from_env()
-        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", err)))?;
+        .map_err(|err| std::io::Error::other(format!("{:?}", err)))?;
 
     let bind_address = env::var("WEBHOOK_BIND_ADDRESS").unwrap();
     let bind_port = env::var("WEBHOOK_BIND_PORT").unwrap();
@@ -720,9 +934,17 @@ pub async fn run_server() -> std::io::Result<()> {
             .service(web::resource("/api/pending").route(web::get().to(get_pending_requests)))
             .service(web::resource("/api/archived").route(web::get().to(get_archived_requests)))
             .service(web::resource("/api/update-request").route(web::post().to(update_request)))
+            .service(web::resource("/api/requests/{request_id}/events").route(web::get().to(get_request_events)))
+            .service(
+                web::resource("/api/requests/{request_id}/notes")
+                    .route(web::get().to(get_request_notes))
+                    .route(web::post().to(create_request_note)),
+            )
             .service(web::resource("/api/batch-scrape").route(web::post().to(batch_scrape_media)))
             .service(web::resource("/assets/{filename:.*}").route(web::get().to(static_files::serve_asset_direct)))
             .configure(cli_auth::http::configure)
+            .configure(invite::http::configure)
+            .configure(web_auth::http::configure)
             .configure(onedrive::http::configure)
             .configure(static_files::configure_static_routes)
     })
@@ -0,0 +1,117 @@
+use std::env;
+
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use url::form_urlencoded;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 携带 HMAC 签名的请求头，值为 `sha256=<hex>` 或直接为十六进制签名
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// 携带共享密钥的查询参数，例如 `/webhook?token=<secret>`
+pub const TOKEN_QUERY_PARAM: &str = "token";
+
+const DEFAULT_REPLAY_WINDOW_SECS: i64 = 300;
+
+#[derive(Debug, Clone)]
+pub struct WebhookAuthConfig {
+    /// 未配置时不校验，保持旧行为
+    pub secret: Option<String>,
+    /// payload 中 `Date` 与当前时间允许的最大偏差，0 表示不检查
+    pub replay_window_secs: i64,
+}
+
+#[derive(Debug)]
+pub enum WebhookAuthError {
+    Unauthorized(String),
+}
+
+impl std::fmt::Display for WebhookAuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookAuthError::Unauthorized(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl WebhookAuthConfig {
+    pub fn from_env() -> Self {
+        let secret = env::var("WEBHOOK_SECRET")
+            .ok()
+            .filter(|secret| !secret.trim().is_empty());
+        let replay_window_secs = env::var("WEBHOOK_REPLAY_WINDOW_SECONDS")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|value| *value >= 0)
+            .unwrap_or(DEFAULT_REPLAY_WINDOW_SECS);
+
+        Self { secret, replay_window_secs }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+}
+
+/// 校验查询参数中的密钥或请求体的 HMAC 签名，任一通过即可
+pub fn verify_request(config: &WebhookAuthConfig, req: &HttpRequest, body: &[u8]) -> Result<(), WebhookAuthError> {
+    let secret = match config.secret.as_deref() {
+        Some(secret) => secret,
+        None => return Ok(()),
+    };
+
+    let token = form_urlencoded::parse(req.query_string().as_bytes())
+        .find(|(key, _)| key == TOKEN_QUERY_PARAM)
+        .map(|(_, value)| value.into_owned());
+    if let Some(token) = token {
+        return if constant_time_eq(token.as_bytes(), secret.as_bytes()) {
+            Ok(())
+        } else {
+            Err(WebhookAuthError::Unauthorized("token 不正确".to_string()))
+        };
+    }
+
+    let signature = req
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| WebhookAuthError::Unauthorized("缺少 token 或签名".to_string()))?;
+    let signature = signature.trim();
+    let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let signature = hex::decode(signature)
+        .map_err(|_| WebhookAuthError::Unauthorized("签名格式不正确".to_string()))?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| WebhookAuthError::Unauthorized("签名校验失败".to_string()))?;
+    mac.update(body);
+    mac.verify_slice(&signature)
+        .map_err(|_| WebhookAuthError::Unauthorized("签名不正确".to_string()))
+}
+
+/// 拒绝 `Date` 超出重放窗口的 payload
+pub fn verify_date(config: &WebhookAuthConfig, date: Option<&str>) -> Result<(), WebhookAuthError> {
+    if !config.enabled() || config.replay_window_secs == 0 {
+        return Ok(());
+    }
+
+    let date = date.ok_or_else(|| WebhookAuthError::Unauthorized("payload 缺少 Date".to_string()))?;
+    let sent_at = DateTime::parse_from_rfc3339(date)
+        .map_err(|_| WebhookAuthError::Unauthorized(format!("无法解析 Date: {}", date)))?
+        .with_timezone(&Utc);
+
+    let skew = (Utc::now() - sent_at).num_seconds().abs();
+    if skew > config.replay_window_secs {
+        return Err(WebhookAuthError::Unauthorized(format!("Date 超出允许范围 ({} 秒)", skew)));
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
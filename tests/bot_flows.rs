@@ -0,0 +1,247 @@
+mod support;
+
+use diesel::prelude::*;
+use nyamedia_bot::account_link::{self, LinkAttemptConfig, LinkError};
+use nyamedia_bot::group_membership::{self, GroupMembershipConfig};
+use nyamedia_bot::invite::service as invite;
+use nyamedia_bot::password_reset::{self, PasswordResetConfig};
+use nyamedia_bot::models::{link_request_status, InviteRedemption};
+use nyamedia_bot::schema::invite_redemptions;
+use nyamedia_bot::{auth, establish_connection};
+use serde_json::json;
+use support::fake_emby::FakeEmby;
+use teloxide::types::{ChatId, ChatMemberUpdated};
+
+const GROUP_CHAT_ID: i64 = -100123;
+
+#[actix_web::test]
+async fn register_with_invite_creates_emby_user() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    let mut conn = establish_connection();
+    let code = invite::create_invite(&mut conn, 1, 1, None).unwrap().code;
+
+    let emby_user_id = invite::register_with_invite(&fake.client(), &mut conn, &code, 1001, "alice")
+        .await
+        .unwrap();
+
+    assert!(fake.user(&emby_user_id).is_some());
+    assert_eq!(auth::get_emby_id(1001), emby_user_id);
+
+    let redemption = invite_redemptions::table
+        .filter(invite_redemptions::telegram_id.eq(1001))
+        .select(InviteRedemption::as_select())
+        .first(&mut conn)
+        .unwrap();
+    assert_eq!(redemption.emby_user_id.as_deref(), Some(emby_user_id.as_str()));
+
+    // 单次邀请码用完后不能再注册
+    let err = invite::register_with_invite(&fake.client(), &mut conn, &code, 1002, "bob").await.unwrap_err();
+    assert!(err.contains("次数"));
+}
+
+#[actix_web::test]
+async fn failed_emby_registration_releases_invite() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("existing", "taken");
+    let mut conn = establish_connection();
+    let code = invite::create_invite(&mut conn, 1, 1, None).unwrap().code;
+
+    let err = invite::register_with_invite(&fake.client(), &mut conn, &code, 2001, "taken").await.unwrap_err();
+    assert!(err.contains("already exists"));
+    assert!(!auth::check_registered(2001));
+
+    let invite_code = invite::validate(&mut conn, &code).expect("invite should still be usable");
+    assert_eq!(invite_code.uses, 0);
+
+    invite::register_with_invite(&fake.client(), &mut conn, &code, 2001, "carol").await.unwrap();
+}
+
+#[actix_web::test]
+async fn leaving_group_disables_emby_user() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("emby-3001", "dave");
+    auth::register(3001, "dave".to_string(), "emby-3001".to_string());
+    let config = GroupMembershipConfig {
+        group_chat_id: Some(ChatId(GROUP_CHAT_ID)),
+        require_for_register: false,
+    };
+
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3001, "member", "left"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3001").unwrap()["Policy"]["IsDisabled"], json!(true));
+
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3001, "left", "member"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3001").unwrap()["Policy"]["IsDisabled"], json!(false));
+
+    // 其它群组的变更不影响账户
+    group_membership::handle_member_update(&fake.client(), &member_update(-100999, 3001, "member", "kicked"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3001").unwrap()["Policy"]["IsDisabled"], json!(false));
+}
+
+#[actix_web::test]
+async fn rejoining_group_enables_emby_user() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("emby-3101", "erin");
+    auth::register(3101, "erin".to_string(), "emby-3101".to_string());
+    let config = GroupMembershipConfig {
+        group_chat_id: Some(ChatId(GROUP_CHAT_ID)),
+        require_for_register: false,
+    };
+
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3101, "member", "kicked"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3101").unwrap()["Policy"]["IsDisabled"], json!(true));
+
+    // 解除封禁但尚未回到群组，账户仍保持禁用
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3101, "kicked", "left"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3101").unwrap()["Policy"]["IsDisabled"], json!(true));
+
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3101, "left", "member"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3101").unwrap()["Policy"]["IsDisabled"], json!(false));
+
+    // 在群内的身份变化不影响账户
+    group_membership::handle_member_update(&fake.client(), &member_update(GROUP_CHAT_ID, 3101, "member", "administrator"), &config)
+        .await
+        .unwrap();
+    assert_eq!(fake.user("emby-3101").unwrap()["Policy"]["IsDisabled"], json!(false));
+}
+
+#[actix_web::test]
+async fn link_existing_account_with_password() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("emby-4001", "erin");
+    fake.set_password("emby-4001", "hunter2");
+    let mut conn = establish_connection();
+    let config = LinkAttemptConfig { max_failures: 0, window_secs: 3600 };
+
+    let err = account_link::link_with_password(&fake.client(), &mut conn, &config, 4001, "erin", "wrong").await.unwrap_err();
+    assert!(matches!(err, LinkError::Unauthorized(_)));
+    assert!(!auth::check_registered(4001));
+
+    account_link::link_with_password(&fake.client(), &mut conn, &config, 4001, "erin", "hunter2").await.unwrap();
+    assert_eq!(auth::get_emby_id(4001), "emby-4001");
+    // 没有在 Emby 中创建新用户
+    assert_eq!(fake.state.lock().unwrap().users.len(), 2);
+
+    // 同一个 Emby 账户不能再绑定给其他人
+    let err = account_link::link_with_password(&fake.client(), &mut conn, &config, 4002, "erin", "hunter2").await.unwrap_err();
+    assert!(matches!(err, LinkError::Conflict(_)));
+}
+
+#[actix_web::test]
+async fn link_password_attempts_are_capped() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("emby-4101", "grace");
+    fake.set_password("emby-4101", "hunter2");
+    let mut conn = establish_connection();
+    let config = LinkAttemptConfig { max_failures: 2, window_secs: 3600 };
+
+    // 用户不存在和密码错误返回同样的错误
+    let err = account_link::link_with_password(&fake.client(), &mut conn, &config, 4101, "nobody", "hunter2").await.unwrap_err();
+    assert!(matches!(err, LinkError::Unauthorized(_)));
+
+    let err = account_link::link_with_password(&fake.client(), &mut conn, &config, 4101, "grace", "wrong").await.unwrap_err();
+    assert!(matches!(err, LinkError::TooManyAttempts(_)));
+
+    // 达到上限后即使密码正确也不再验证
+    let err = account_link::link_with_password(&fake.client(), &mut conn, &config, 4101, "grace", "hunter2").await.unwrap_err();
+    assert!(matches!(err, LinkError::TooManyAttempts(_)));
+    assert!(!auth::check_registered(4101));
+}
+
+#[actix_web::test]
+async fn link_request_requires_admin_approval() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    let rejected = account_link::create_request(&mut conn, 5001, "emby-5001", "frank").unwrap();
+    assert!(matches!(
+        account_link::create_request(&mut conn, 5001, "emby-5001", "frank"),
+        Err(LinkError::Conflict(_))
+    ));
+    let rejected = account_link::review_request(&mut conn, rejected.id, false, 1).unwrap();
+    assert_eq!(rejected.status, link_request_status::REJECTED);
+    assert!(!auth::check_registered(5001));
+
+    let request = account_link::create_request(&mut conn, 5001, "emby-5001", "frank").unwrap();
+    let approved = account_link::review_request(&mut conn, request.id, true, 1).unwrap();
+    assert_eq!(approved.status, link_request_status::APPROVED);
+    assert_eq!(auth::get_emby_id(5001), "emby-5001");
+
+    assert!(matches!(account_link::review_request(&mut conn, request.id, true, 1), Err(LinkError::Conflict(_))));
+}
+
+#[actix_web::test]
+async fn set_password_enforces_strength_and_rate_limit() {
+    let _db = support::setup_database().await;
+    let fake = FakeEmby::start();
+    fake.add_user("emby-6001", "grace");
+    let mut conn = establish_connection();
+    let config = PasswordResetConfig {
+        min_length: 8,
+        min_char_classes: 3,
+        max_per_window: 2,
+        window_secs: 3600,
+    };
+
+    for weak in ["Ab1!", "alllowercase1", "has space A1"] {
+        assert!(password_reset::set_password(&fake.client(), &mut conn, &config, 6001, "emby-6001", weak).await.is_err());
+    }
+    assert!(!fake.state.lock().unwrap().passwords.contains_key("emby-6001"));
+
+    // 强度不满足的尝试不计入次数
+    password_reset::set_password(&fake.client(), &mut conn, &config, 6001, "emby-6001", "Str0ngPass").await.unwrap();
+    assert_eq!(fake.state.lock().unwrap().passwords.get("emby-6001").map(String::as_str), Some("Str0ngPass"));
+
+    password_reset::reset_password(&fake.client(), &mut conn, &config, 6001, "emby-6001").await.unwrap();
+    let err = password_reset::set_password(&fake.client(), &mut conn, &config, 6001, "emby-6001", "An0therPass").await.unwrap_err();
+    assert!(err.contains("频繁"));
+    assert!(!fake.state.lock().unwrap().passwords.contains_key("emby-6001"));
+}
+
+fn member_update(chat_id: i64, user_id: i64, old_status: &str, new_status: &str) -> ChatMemberUpdated {
+    let user = json!({ "id": user_id, "is_bot": false, "first_name": "test" });
+    let member = |status: &str| {
+        let mut member = json!({ "user": user, "status": status });
+        if status == "kicked" {
+            member["until_date"] = json!(0);
+        }
+        if status == "administrator" {
+            member["can_be_edited"] = json!(false);
+            member["is_anonymous"] = json!(false);
+            member["can_manage_chat"] = json!(true);
+            member["can_delete_messages"] = json!(false);
+            member["can_manage_video_chats"] = json!(false);
+            member["can_restrict_members"] = json!(false);
+            member["can_promote_members"] = json!(false);
+            member["can_change_info"] = json!(false);
+            member["can_invite_users"] = json!(true);
+        }
+        member
+    };
+
+    serde_json::from_value(json!({
+        "chat": { "id": chat_id, "type": "supergroup", "title": "group" },
+        "from": { "id": 1, "is_bot": false, "first_name": "admin" },
+        "date": 0,
+        "old_chat_member": member(old_status),
+        "new_chat_member": member(new_status),
+    }))
+    .expect("invalid chat member update")
+}
@@ -0,0 +1,79 @@
+mod support;
+
+use diesel::prelude::*;
+use nyamedia_bot::dialogue_storage::{DialogueStorageError, DieselStorage};
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::schema::bot_dialogues;
+use serde::{Deserialize, Serialize};
+use teloxide::dispatching::dialogue::Storage;
+use teloxide::types::ChatId;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum TestState {
+    Start,
+    WaitingName { attempts: u32 },
+}
+
+fn stored_rows() -> i64 {
+    bot_dialogues::table.count().get_result(&mut establish_connection()).unwrap()
+}
+
+#[actix_web::test]
+async fn dialogue_round_trips_through_sqlite() {
+    let _db = support::setup_database().await;
+    let storage = DieselStorage::<TestState>::new(3600);
+    let chat_id = ChatId(1001);
+
+    assert_eq!(storage.clone().get_dialogue(chat_id).await.unwrap(), None);
+
+    storage.clone().update_dialogue(chat_id, TestState::WaitingName { attempts: 2 }).await.unwrap();
+    assert_eq!(
+        storage.clone().get_dialogue(chat_id).await.unwrap(),
+        Some(TestState::WaitingName { attempts: 2 })
+    );
+
+    // 同一会话再次写入时覆盖原状态
+    storage.clone().update_dialogue(chat_id, TestState::Start).await.unwrap();
+    assert_eq!(storage.clone().get_dialogue(chat_id).await.unwrap(), Some(TestState::Start));
+    assert_eq!(stored_rows(), 1);
+
+    storage.clone().remove_dialogue(chat_id).await.unwrap();
+    assert_eq!(storage.clone().get_dialogue(chat_id).await.unwrap(), None);
+    assert!(matches!(
+        storage.clone().remove_dialogue(chat_id).await,
+        Err(DialogueStorageError::DialogueNotFound)
+    ));
+}
+
+#[actix_web::test]
+async fn expired_dialogues_are_dropped_and_purged() {
+    let _db = support::setup_database().await;
+    let fresh = DieselStorage::<TestState>::new(3600);
+    let expired = DieselStorage::<TestState>::new(0);
+
+    fresh.clone().update_dialogue(ChatId(2001), TestState::Start).await.unwrap();
+    fresh.clone().update_dialogue(ChatId(2002), TestState::Start).await.unwrap();
+
+    // TTL 为 0 时所有对话都已过期，读取时返回空并删除该对话
+    assert_eq!(expired.clone().get_dialogue(ChatId(2001)).await.unwrap(), None);
+    assert_eq!(stored_rows(), 1);
+
+    // 写入新对话时清理其他已过期的对话，同一个存储一小时内最多清理一次
+    let writer = DieselStorage::<TestState>::new(3600);
+    diesel::update(bot_dialogues::table)
+        .set(bot_dialogues::updated_at.eq("2000-01-01T00:00:00Z"))
+        .execute(&mut establish_connection())
+        .unwrap();
+    writer.clone().update_dialogue(ChatId(2003), TestState::Start).await.unwrap();
+    assert_eq!(stored_rows(), 1);
+
+    diesel::update(bot_dialogues::table)
+        .set(bot_dialogues::updated_at.eq("2000-01-01T00:00:00Z"))
+        .execute(&mut establish_connection())
+        .unwrap();
+    writer.clone().update_dialogue(ChatId(2004), TestState::Start).await.unwrap();
+    assert_eq!(stored_rows(), 2);
+
+    assert_eq!(writer.purge_expired().unwrap(), 1);
+    assert_eq!(writer.clone().get_dialogue(ChatId(2004)).await.unwrap(), Some(TestState::Start));
+}
@@ -0,0 +1,157 @@
+mod support;
+
+use std::net::TcpListener;
+
+use nyamedia_bot::emby::{self, EmbyApi, EmbyClient, EmbyConfig, EmbyError};
+use serde_json::json;
+use support::fake_emby::{FakeEmby, TEMPLATE_USER_ID};
+
+#[actix_web::test]
+async fn create_user_copies_template_policy() {
+    let fake = FakeEmby::start();
+    let client = fake.client();
+
+    let user = client.create_user("alice").await.unwrap();
+
+    assert_eq!(user.name, "alice");
+    let policy = user.policy.expect("policy should be returned");
+    assert!(!policy.is_disabled);
+    assert_eq!(policy.extra["EnabledFolders"], json!(["lib-movies"]));
+    assert!(fake.user(&user.id).is_some());
+}
+
+#[actix_web::test]
+async fn duplicate_username_is_bad_request() {
+    let fake = FakeEmby::start();
+    let client = fake.client();
+    client.create_user("alice").await.unwrap();
+
+    match client.create_user("alice").await {
+        Err(EmbyError::BadRequest(message)) => assert!(message.contains("already exists")),
+        other => panic!("expected BadRequest, got {:?}", other),
+    }
+}
+
+#[actix_web::test]
+async fn invalid_api_key_is_unauthorized() {
+    let fake = FakeEmby::start();
+    let client = fake.client_with_key("wrong-key");
+
+    assert!(matches!(client.get_user(TEMPLATE_USER_ID).await, Err(EmbyError::Unauthorized(_))));
+}
+
+#[actix_web::test]
+async fn missing_user_is_not_found() {
+    let fake = FakeEmby::start();
+    let client = fake.client();
+
+    assert!(matches!(client.get_user("missing").await, Err(EmbyError::NotFound(_))));
+    assert!(matches!(client.delete_user("missing").await, Err(EmbyError::NotFound(_))));
+}
+
+#[actix_web::test]
+async fn authenticate_by_name_checks_password_and_logs_out() {
+    let fake = FakeEmby::start();
+    fake.add_user("u1", "Bob");
+    fake.set_password("u1", "secret");
+    let client = fake.client();
+
+    assert!(matches!(client.authenticate_by_name("bob", "wrong").await, Err(EmbyError::Unauthorized(_))));
+
+    let user = client.authenticate_by_name("bob", "secret").await.unwrap();
+    assert_eq!(user.id, "u1");
+    assert_eq!(fake.state.lock().unwrap().logged_out_tokens, vec!["token-u1".to_string()]);
+
+    let found = emby::client::find_user_by_name(&client, "BOB").await.unwrap();
+    assert_eq!(found.map(|user| user.id).as_deref(), Some("u1"));
+}
+
+#[actix_web::test]
+async fn unreachable_server_is_network_error() {
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let client = EmbyClient::new(EmbyConfig {
+        base_url: format!("http://{}", addr),
+        api_key: "key".to_string(),
+        copy_from_user_id: None,
+    });
+
+    assert!(matches!(client.list_sessions().await, Err(EmbyError::Network(_))));
+}
+
+#[actix_web::test]
+async fn reset_password_and_delete_user() {
+    let fake = FakeEmby::start();
+    fake.add_user("u1", "bob");
+    let client = fake.client();
+
+    client.reset_password("u1").await.unwrap();
+    assert_eq!(fake.state.lock().unwrap().password_resets, vec!["u1".to_string()]);
+
+    client.delete_user("u1").await.unwrap();
+    assert!(fake.user("u1").is_none());
+}
+
+#[actix_web::test]
+async fn set_password_replaces_existing_password() {
+    let fake = FakeEmby::start();
+    fake.add_user("u1", "bob");
+    fake.set_password("u1", "old-password");
+    let client = fake.client();
+
+    client.set_password("u1", "N3w-password").await.unwrap();
+
+    assert_eq!(fake.state.lock().unwrap().passwords.get("u1").map(String::as_str), Some("N3w-password"));
+    assert!(client.authenticate_by_name("bob", "N3w-password").await.is_ok());
+    // 直接设置新密码，不会先把密码清空
+    assert!(fake.state.lock().unwrap().password_resets.is_empty());
+}
+
+#[actix_web::test]
+async fn set_user_disabled_keeps_other_policy_fields() {
+    let fake = FakeEmby::start();
+    fake.add_user("u1", "bob");
+    let client = fake.client();
+
+    emby::client::set_user_disabled(&client, "u1", true).await.unwrap();
+    let policy = fake.user("u1").unwrap()["Policy"].clone();
+    assert_eq!(policy["IsDisabled"], json!(true));
+    assert_eq!(policy["EnableRemoteAccess"], json!(true));
+
+    emby::client::set_user_disabled(&client, "u1", false).await.unwrap();
+    assert_eq!(fake.user("u1").unwrap()["Policy"]["IsDisabled"], json!(false));
+}
+
+#[actix_web::test]
+async fn library_items_and_sessions() {
+    let fake = FakeEmby::start();
+    {
+        let mut state = fake.state.lock().unwrap();
+        state.libraries.push(json!({ "Name": "电影", "ItemId": "lib-movies", "CollectionType": "movies", "Locations": ["/media/movies"] }));
+        state.items.insert(
+            "m1".to_string(),
+            json!({ "Id": "m1", "Name": "Movie", "Type": "Movie", "ProductionYear": 2024, "ProviderIds": { "Tmdb": "123" } }),
+        );
+        state.ancestors.insert(
+            "m1".to_string(),
+            vec![
+                json!({ "Id": "lib-movies", "Name": "电影", "Type": "CollectionFolder" }),
+                json!({ "Id": "root", "Name": "Media Folders", "Type": "UserRootFolder" }),
+            ],
+        );
+        state.sessions.push(json!({ "Id": "s1", "UserName": "bob", "Client": "Emby Web", "NowPlayingItem": { "Id": "m1", "Name": "Movie" } }));
+    }
+    let client = fake.client();
+
+    let libraries = client.list_libraries().await.unwrap();
+    assert_eq!(libraries[0].name, "电影");
+    assert_eq!(libraries[0].locations, vec!["/media/movies".to_string()]);
+
+    let items = client.get_items(&["m1", "missing"]).await.unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].provider_ids.get("Tmdb").map(String::as_str), Some("123"));
+
+    assert_eq!(emby::client::library_name(&client, "m1").await.unwrap().as_deref(), Some("电影"));
+
+    let sessions = client.list_sessions().await.unwrap();
+    assert_eq!(sessions[0].now_playing_item.as_ref().map(|item| item.id.as_str()), Some("m1"));
+}
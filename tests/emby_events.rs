@@ -0,0 +1,72 @@
+use nyamedia_bot::emby_events::{self, EmbyEvent, EventNotificationConfig, ItemKind, WebhookPayload};
+use serde_json::json;
+
+#[test]
+fn built_in_rules_apply_without_config() {
+    let config = EventNotificationConfig::defaults();
+
+    let episode = config.rule_for(&EmbyEvent::LibraryNew(ItemKind::Episode)).unwrap();
+    assert_eq!(episode.template, "新剧集入库: {series_name} ({year})\n{episodes} 入库");
+    assert!(episode.chats.is_empty());
+    assert!(config.rule_for(&EmbyEvent::Other("custom.event".to_string())).is_none());
+}
+
+#[test]
+fn generic_overrides_take_precedence_over_specific_defaults() {
+    let config = EventNotificationConfig::from_toml(
+        r#"
+        [events."library.new"]
+        template = "入库: {item_name}"
+        chats = [1]
+
+        [events."library.new.movie"]
+        chats = [2]
+        "#,
+    )
+    .unwrap();
+
+    let episode = config.rule_for(&EmbyEvent::LibraryNew(ItemKind::Episode)).unwrap();
+    assert_eq!(episode.template, "入库: {item_name}");
+    assert_eq!(episode.chats, vec![1]);
+
+    // 更具体的配置优先，没有配置的字段继续向通用的 key 查找
+    let movie = config.rule_for(&EmbyEvent::LibraryNew(ItemKind::Movie)).unwrap();
+    assert_eq!(movie.template, "入库: {item_name}");
+    assert_eq!(movie.chats, vec![2]);
+}
+
+#[test]
+fn chats_only_override_keeps_default_template() {
+    let config = EventNotificationConfig::from_toml(
+        r#"
+        [events."playback.start"]
+        chats = [3]
+
+        [events."custom.event"]
+        chats = [4]
+        "#,
+    )
+    .unwrap();
+
+    let playback = config.rule_for(&EmbyEvent::PlaybackStart).unwrap();
+    assert_eq!(playback.template, "{user_name} 开始播放: {item_name}");
+    assert_eq!(playback.chats, vec![3]);
+
+    let custom = config.rule_for(&EmbyEvent::Other("custom.event".to_string())).unwrap();
+    assert_eq!(custom.template, "{title}\n{description}");
+    assert_eq!(custom.chats, vec![4]);
+}
+
+#[test]
+fn template_values_are_not_substituted_again() {
+    let payload: WebhookPayload = serde_json::from_value(json!({
+        "Event": "library.new",
+        "Title": "{item_name}",
+        "Item": { "Name": "{title} {unknown}", "Type": "Movie", "ProductionYear": 1999 },
+    }))
+    .unwrap();
+
+    let rendered = emby_events::render_template("{title} | {item_name} ({year}) {missing}", &payload);
+
+    assert_eq!(rendered, "{item_name} | {title} {unknown} (1999) {missing}");
+}
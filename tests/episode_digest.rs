@@ -0,0 +1,71 @@
+mod support;
+
+use diesel::prelude::*;
+use nyamedia_bot::emby_events::{EventNotificationConfig, Item};
+use nyamedia_bot::episode_digest;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::schema::episode_notifications;
+use serde_json::json;
+use support::fake_telegram::FakeTelegram;
+
+fn episode(series_id: &str, season: Option<u32>, episode: Option<u32>) -> Item {
+    serde_json::from_value(json!({
+        "Name": "第一集",
+        "Type": "Episode",
+        "SeriesId": series_id,
+        "SeriesName": "测试剧集",
+        "ParentIndexNumber": season,
+        "IndexNumber": episode,
+    }))
+    .unwrap()
+}
+
+fn count(conn: &mut SqliteConnection, series_id: &str) -> i64 {
+    episode_notifications::table
+        .filter(episode_notifications::series_id.eq(series_id))
+        .count()
+        .get_result(conn)
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn record_episode_ignores_duplicates_without_numbers() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    assert!(episode_digest::record_episode(&mut conn, &episode("series-1", Some(1), Some(1)), None).unwrap());
+    assert!(!episode_digest::record_episode(&mut conn, &episode("series-1", Some(1), Some(1)), None).unwrap());
+
+    assert!(episode_digest::record_episode(&mut conn, &episode("series-1", None, Some(2)), None).unwrap());
+    assert!(!episode_digest::record_episode(&mut conn, &episode("series-1", None, Some(2)), None).unwrap());
+
+    assert!(episode_digest::record_episode(&mut conn, &episode("series-1", None, None), None).unwrap());
+    assert!(!episode_digest::record_episode(&mut conn, &episode("series-1", None, None), None).unwrap());
+
+    assert_eq!(count(&mut conn, "series-1"), 3);
+}
+
+#[actix_web::test]
+async fn flush_omits_missing_season_and_prunes_notified_rows() {
+    let _db = support::setup_database().await;
+    let telegram = FakeTelegram::start();
+    let config = EventNotificationConfig::from_toml("[events.\"library.new.episode\"]\nchats = [42]").unwrap();
+    let mut conn = establish_connection();
+
+    for (season, number) in [(None, Some(1)), (None, Some(2)), (Some(2), Some(1))] {
+        episode_digest::record_episode(&mut conn, &episode("series-2", season, number), None).unwrap();
+    }
+
+    assert_eq!(episode_digest::flush_due(&telegram.bot(), &config, 0).await.unwrap(), 1);
+    assert_eq!(telegram.messages_to(42), vec!["新剧集入库: 测试剧集 ()\nE01–E02、S02E01 入库".to_string()]);
+    // 刚推送的记录保留一段时间，用于忽略重复的入库事件
+    assert_eq!(count(&mut conn, "series-2"), 3);
+    assert!(!episode_digest::record_episode(&mut conn, &episode("series-2", None, Some(1)), None).unwrap());
+
+    diesel::update(episode_notifications::table)
+        .set(episode_notifications::notified_at.eq("2000-01-01 00:00:00"))
+        .execute(&mut conn)
+        .unwrap();
+    assert_eq!(episode_digest::flush_due(&telegram.bot(), &config, 0).await.unwrap(), 0);
+    assert_eq!(count(&mut conn, "series-2"), 0);
+}
@@ -0,0 +1,88 @@
+mod support;
+
+use diesel::prelude::*;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::invite::service::{self as invite, InviteError};
+use nyamedia_bot::schema::{invite_codes, invite_redemptions};
+
+fn redemptions(conn: &mut SqliteConnection, invite_code_id: i32) -> i64 {
+    invite_redemptions::table
+        .filter(invite_redemptions::invite_code_id.eq(invite_code_id))
+        .count()
+        .get_result(conn)
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn redeem_stops_at_use_cap() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let created = invite::create_invite(&mut conn, 1, 2, None).unwrap();
+
+    // 兑换时忽略大小写和首尾空白
+    invite::redeem(&mut conn, &format!(" {} ", created.code.to_lowercase()), 101).unwrap();
+    invite::redeem(&mut conn, &created.code, 102).unwrap();
+    assert!(matches!(invite::redeem(&mut conn, &created.code, 103), Err(InviteError::Unavailable(_))));
+    assert!(matches!(invite::validate(&mut conn, &created.code), Err(InviteError::Unavailable(_))));
+
+    let uses: i32 = invite_codes::table.find(created.id).select(invite_codes::uses).first(&mut conn).unwrap();
+    assert_eq!(uses, 2);
+    assert_eq!(redemptions(&mut conn, created.id), 2);
+}
+
+#[actix_web::test]
+async fn expired_and_revoked_invites_are_unusable() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    let expiring = invite::create_invite(&mut conn, 1, 1, Some(3600)).unwrap();
+    assert!(invite::validate(&mut conn, &expiring.code).is_ok());
+    diesel::update(invite_codes::table.find(expiring.id))
+        .set(invite_codes::expires_at.eq(Some("2000-01-01 00:00:00")))
+        .execute(&mut conn)
+        .unwrap();
+    match invite::redeem(&mut conn, &expiring.code, 201) {
+        Err(InviteError::Unavailable(message)) => assert!(message.contains("过期")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let revoked = invite::create_invite(&mut conn, 1, 1, None).unwrap();
+    invite::revoke_invite(&mut conn, revoked.id).unwrap();
+    match invite::redeem(&mut conn, &revoked.code, 202) {
+        Err(InviteError::Unavailable(message)) => assert!(message.contains("撤销")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    assert!(matches!(invite::revoke_invite(&mut conn, revoked.id + 100), Err(InviteError::NotFound(_))));
+    assert!(matches!(invite::redeem(&mut conn, "NOSUCHCODE", 203), Err(InviteError::NotFound(_))));
+    assert_eq!(redemptions(&mut conn, expiring.id) + redemptions(&mut conn, revoked.id), 0);
+}
+
+#[actix_web::test]
+async fn release_returns_the_use() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let created = invite::create_invite(&mut conn, 1, 1, None).unwrap();
+
+    let redemption_id = invite::redeem(&mut conn, &created.code, 301).unwrap();
+    assert!(invite::validate(&mut conn, &created.code).is_err());
+
+    invite::release(&mut conn, redemption_id).unwrap();
+    assert_eq!(invite::validate(&mut conn, &created.code).unwrap().uses, 0);
+    assert_eq!(redemptions(&mut conn, created.id), 0);
+
+    // 重复归还不会让次数变成负数
+    invite::release(&mut conn, redemption_id).unwrap();
+    assert_eq!(invite::validate(&mut conn, &created.code).unwrap().uses, 0);
+
+    invite::redeem(&mut conn, &created.code, 302).unwrap();
+}
+
+#[actix_web::test]
+async fn create_invite_rejects_invalid_limits() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    assert!(matches!(invite::create_invite(&mut conn, 1, 0, None), Err(InviteError::BadRequest(_))));
+    assert!(matches!(invite::create_invite(&mut conn, 1, 1, Some(0)), Err(InviteError::BadRequest(_))));
+}
@@ -0,0 +1,135 @@
+mod support;
+
+use diesel::prelude::*;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::media_request::{self, MediaRequestError, SubmitOutcome};
+use nyamedia_bot::models::{media_request_status, MediaRequest, NewMediaRequest};
+use nyamedia_bot::schema::media_requests;
+use support::fake_telegram::FakeTelegram;
+
+fn insert_request(conn: &mut SqliteConnection, request_user: i64) -> MediaRequest {
+    diesel::insert_into(media_requests::table)
+        .values(&NewMediaRequest {
+            source: "tmdb".to_string(),
+            media_id: "movie/550".to_string(),
+            request_user,
+            status: media_request_status::SUBMITTED,
+        })
+        .execute(conn)
+        .unwrap();
+    media_requests::table
+        .order(media_requests::id.desc())
+        .first(conn)
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn empty_note_is_a_bad_request() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let request = insert_request(&mut conn, 1);
+
+    assert!(matches!(
+        media_request::add_note(&mut conn, request.id, 99, "   "),
+        Err(MediaRequestError::BadRequest(_))
+    ));
+    assert!(matches!(
+        media_request::add_note(&mut conn, request.id + 1, 99, "备注"),
+        Err(MediaRequestError::NotFound(_))
+    ));
+
+    let note = media_request::add_note(&mut conn, request.id, 99, "  已联系上传者  ").unwrap();
+    assert_eq!(note.content, "已联系上传者");
+}
+
+fn submit(conn: &mut SqliteConnection, requester: i64) -> SubmitOutcome {
+    media_request::submit(conn, "tmdb", "movie/550", requester, None).unwrap()
+}
+
+#[actix_web::test]
+async fn duplicate_submissions_follow_or_reopen_the_existing_request() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    let request = match submit(&mut conn, 1) {
+        SubmitOutcome::Created(request) => request,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    assert!(matches!(submit(&mut conn, 1), SubmitOutcome::AlreadyRequested(_)));
+    assert!(matches!(submit(&mut conn, 2), SubmitOutcome::Followed { newly: true, .. }));
+    assert!(matches!(submit(&mut conn, 2), SubmitOutcome::Followed { newly: false, .. }));
+    assert_eq!(media_request::vote_count(&mut conn, request.id).unwrap(), 2);
+
+    // 管理员标记为不符合规范后，其他人重新提交时原请求者转为关注者
+    media_request::update_status(&mut conn, request.id, media_request_status::INVALID, Some(99), None).unwrap();
+    match submit(&mut conn, 3) {
+        SubmitOutcome::Reopened { previous, request } => {
+            assert_eq!(previous.request_user, 1);
+            assert_eq!(request.request_user, 3);
+            assert_eq!(request.status, media_request_status::SUBMITTED);
+        }
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    let mut followers = media_request::list_followers(&mut conn, request.id).unwrap();
+    followers.sort_unstable();
+    assert_eq!(followers, vec![1, 2]);
+
+    media_request::update_status(&mut conn, request.id, media_request_status::ARCHIVED, Some(99), None).unwrap();
+    assert!(matches!(submit(&mut conn, 4), SubmitOutcome::Unavailable(_)));
+}
+
+#[actix_web::test]
+async fn requester_who_cancelled_reopens_instead_of_following() {
+    let _db = support::setup_database().await;
+    let telegram = FakeTelegram::start();
+    let mut conn = establish_connection();
+
+    let request = match submit(&mut conn, 1) {
+        SubmitOutcome::Created(request) => request,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    media_request::cancel_by_requester(&telegram.bot(), &mut conn, request.id, 1).await.unwrap();
+
+    match submit(&mut conn, 1) {
+        SubmitOutcome::Reopened { request, .. } => assert_eq!(request.request_user, 1),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    assert!(media_request::list_followers(&mut conn, request.id).unwrap().is_empty());
+
+    // 撤回请求的用户不会因为其他人重新提交而变成关注者
+    media_request::cancel_by_requester(&telegram.bot(), &mut conn, request.id, 1).await.unwrap();
+    match submit(&mut conn, 2) {
+        SubmitOutcome::Reopened { request, .. } => assert_eq!(request.request_user, 2),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    assert!(media_request::list_followers(&mut conn, request.id).unwrap().is_empty());
+
+    // 再次想看时可以正常关注
+    assert!(matches!(submit(&mut conn, 1), SubmitOutcome::Followed { newly: true, .. }));
+}
+
+#[actix_web::test]
+async fn followers_are_notified_when_requester_cancels_or_reopens() {
+    let _db = support::setup_database().await;
+    let telegram = FakeTelegram::start();
+    let mut conn = establish_connection();
+
+    let request = match submit(&mut conn, 1) {
+        SubmitOutcome::Created(request) => request,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    submit(&mut conn, 2);
+
+    media_request::cancel_by_requester(&telegram.bot(), &mut conn, request.id, 1).await.unwrap();
+    let messages = telegram.messages_to(2);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains("已取消"));
+
+    media_request::reopen_by_requester(&telegram.bot(), &mut conn, request.id, 1).await.unwrap();
+    let messages = telegram.messages_to(2);
+    assert_eq!(messages.len(), 2);
+    assert!(messages[1].contains("已提交"));
+
+    // 请求者本人不会收到自己操作的通知
+    assert!(telegram.messages_to(1).is_empty());
+}
@@ -0,0 +1,19 @@
+mod support;
+
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::notification::{self, category};
+
+#[actix_web::test]
+async fn legacy_chats_are_imported_only_once() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+
+    assert_eq!(notification::import_legacy_chats(&mut conn, "100, bad, 200").unwrap(), 4);
+    assert_eq!(notification::subscribed_chats(&mut conn, category::NEW_MOVIES, None).unwrap(), vec![100, 200]);
+
+    // 管理员取消了全部订阅后，重启不应再恢复
+    notification::unsubscribe(&mut conn, 100, None, None).unwrap();
+    notification::unsubscribe(&mut conn, 200, None, None).unwrap();
+    assert_eq!(notification::import_legacy_chats(&mut conn, "100, bad, 200").unwrap(), 0);
+    assert!(notification::subscribed_chats(&mut conn, category::NEW_MOVIES, None).unwrap().is_empty());
+}
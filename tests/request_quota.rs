@@ -0,0 +1,94 @@
+mod support;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::media_request::{self, MediaRequestError, SubmitOutcome};
+use nyamedia_bot::models::media_request_status;
+use nyamedia_bot::rate_limit::TIME_FORMAT;
+use nyamedia_bot::request_quota::{self, RequestQuotaConfig};
+use nyamedia_bot::schema::media_request_events;
+
+fn submit(conn: &mut SqliteConnection, media_id: &str, requester: i64, quota: Option<&RequestQuotaConfig>) -> Result<SubmitOutcome, MediaRequestError> {
+    media_request::submit(conn, "tmdb", media_id, requester, quota)
+}
+
+fn submitted_at(conn: &mut SqliteConnection, request_id: i32, actor: i64, created_at: &str) {
+    diesel::insert_into(media_request_events::table)
+        .values((
+            media_request_events::media_request_id.eq(request_id),
+            media_request_events::actor.eq(Some(actor)),
+            media_request_events::new_status.eq(media_request_status::SUBMITTED),
+            media_request_events::created_at.eq(created_at),
+        ))
+        .execute(conn)
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn open_requests_are_capped() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let quota = RequestQuotaConfig { max_open: 2, max_per_window: 0, window_secs: 0 };
+
+    let first = match submit(&mut conn, "movie/1", 1, Some(&quota)).unwrap() {
+        SubmitOutcome::Created(request) => request,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    submit(&mut conn, "movie/2", 1, Some(&quota)).unwrap();
+
+    let err = submit(&mut conn, "movie/3", 1, Some(&quota)).unwrap_err();
+    assert!(matches!(&err, MediaRequestError::QuotaExceeded(message) if message.contains("上限 2 个")));
+
+    // 关注已有请求不占用配额
+    submit(&mut conn, "movie/1", 2, Some(&quota)).unwrap();
+    assert!(request_quota::check(&mut conn, &quota, 2).is_ok());
+
+    // 处理完成后名额释放
+    media_request::update_status(&mut conn, first.id, media_request_status::ARCHIVED, Some(99), None).unwrap();
+    submit(&mut conn, "movie/3", 1, Some(&quota)).unwrap();
+}
+
+#[actix_web::test]
+async fn window_cap_reports_when_to_retry() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let quota = RequestQuotaConfig { max_open: 0, max_per_window: 2, window_secs: 3600 };
+
+    let request = match submit(&mut conn, "movie/1", 1, Some(&quota)).unwrap() {
+        SubmitOutcome::Created(request) => request,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    // 窗口外的提交不计入
+    let expired = (Utc::now() - Duration::seconds(7200)).format(TIME_FORMAT).to_string();
+    submitted_at(&mut conn, request.id, 1, &expired);
+    assert!(request_quota::check(&mut conn, &quota, 1).is_ok());
+
+    let earliest = (Utc::now() - Duration::seconds(1800)).format(TIME_FORMAT).to_string();
+    submitted_at(&mut conn, request.id, 1, &earliest);
+
+    let retry_at = (NaiveDateTime::parse_from_str(&earliest, TIME_FORMAT).unwrap() + Duration::seconds(3600))
+        .format("%Y-%m-%d %H:%M:%S UTC")
+        .to_string();
+    match request_quota::check(&mut conn, &quota, 1) {
+        Err(MediaRequestError::QuotaExceeded(message)) => {
+            assert_eq!(message, format!("您最近提交的请求过多（每 1 小时 最多 2 个），请于 {} 之后再提交。", retry_at));
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert!(request_quota::check(&mut conn, &quota, 2).is_ok());
+}
+
+#[actix_web::test]
+async fn admins_bypass_quota() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let quota = RequestQuotaConfig { max_open: 1, max_per_window: 1, window_secs: 3600 };
+
+    submit(&mut conn, "movie/1", 1, Some(&quota)).unwrap();
+    assert!(matches!(submit(&mut conn, "movie/2", 1, Some(&quota)), Err(MediaRequestError::QuotaExceeded(_))));
+
+    // 管理员提交时不传配额
+    submit(&mut conn, "movie/2", 1, None).unwrap();
+    submit(&mut conn, "movie/3", 1, None).unwrap();
+}
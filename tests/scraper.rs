@@ -0,0 +1,451 @@
+mod support;
+
+use std::time::Duration;
+
+use diesel::prelude::*;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::models::{media_request_status, Media, MediaRequest, NewMediaRequest};
+use nyamedia_bot::schema::{media, media_requests, scrape_cache};
+use nyamedia_bot::scraper::bgm::BgmSubjectType;
+use nyamedia_bot::scraper::{self, provider, EpisodeNumbering, MediaInfo};
+use serde_json::Value;
+use support::fake_scraper::{FakeScraper, BGM_TOKEN, TMDB_TOKEN};
+
+fn insert_request(conn: &mut SqliteConnection, source: &str, media_id: &str) -> MediaRequest {
+    diesel::insert_into(media_requests::table)
+        .values(&NewMediaRequest {
+            source: source.to_string(),
+            media_id: media_id.to_string(),
+            request_user: 1001,
+            status: media_request_status::SUBMITTED,
+        })
+        .execute(conn)
+        .unwrap();
+
+    media_requests::table
+        .filter(media_requests::source.eq(source))
+        .filter(media_requests::media_id.eq(media_id))
+        .first(conn)
+        .unwrap()
+}
+
+fn media_for(conn: &mut SqliteConnection, request_id: i32) -> Option<Media> {
+    media::table
+        .filter(media::media_request_id.eq(request_id))
+        .first(conn)
+        .optional()
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn tmdb_movie_lookup_parses_metadata() {
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/550", 200, "tmdb_movie_550.json");
+
+    let info = provider::provider("TMDB/MV").unwrap().lookup(&fake.config(), "550").await.unwrap();
+
+    assert_eq!(info.title, "搏击俱乐部");
+    assert_eq!(info.display_title(), "搏击俱乐部 (1999)");
+    assert_eq!(info.original_title.as_deref(), Some("Fight Club"));
+    assert_eq!(info.poster, "https://image.tmdb.org/t/p/w500/5TiwfWEaPSwD20uwXjCTUqpQX70.jpg");
+    assert_eq!(info.genres, vec!["剧情", "惊悚"]);
+    assert_eq!(info.runtime, Some(139));
+    assert_eq!(info.season_count, None);
+    assert_eq!(info.external_ids.imdb.as_deref(), Some("tt0137523"));
+    assert_eq!(info.external_ids.tmdb.as_deref(), Some("550"));
+    assert_eq!(info.external_ids.tvdb, None);
+
+    let requests = fake.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].authorization.as_deref(), Some(format!("Bearer {}", TMDB_TOKEN).as_str()));
+    assert!(requests[0].query.contains("language=zh-CN"));
+    assert!(requests[0].query.contains("append_to_response=external_ids"));
+}
+
+#[actix_web::test]
+async fn tmdb_tv_lookup_reads_episode_counts_and_tvdb_id() {
+    let fake = FakeScraper::start();
+    fake.get("/3/tv/1399", 200, "tmdb_tv_1399.json");
+
+    let info = provider::provider("TMDB/TV").unwrap().lookup(&fake.config(), "1399").await.unwrap();
+
+    assert_eq!(info.title, "权力的游戏");
+    assert_eq!(info.original_title.as_deref(), Some("Game of Thrones"));
+    assert_eq!(info.year, Some(2011));
+    assert_eq!(info.runtime, Some(60));
+    assert_eq!(info.season_count, Some(8));
+    assert_eq!(info.episode_count, Some(73));
+    assert_eq!(info.external_ids.imdb.as_deref(), Some("tt0944947"));
+    assert_eq!(info.external_ids.tvdb.as_deref(), Some("121361"));
+}
+
+#[actix_web::test]
+async fn tmdb_missing_fields_fall_back_to_defaults() {
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/1000001", 200, "tmdb_movie_missing_fields.json");
+
+    let info = provider::provider("TMDB/MV").unwrap().lookup(&fake.config(), "1000001").await.unwrap();
+
+    assert_eq!(info.title, "Unknown Title");
+    assert_eq!(info.poster, "");
+    assert_eq!(info.year, None);
+    assert_eq!(info.original_title, None);
+    assert!(info.genres.is_empty());
+    assert_eq!(info.runtime, None);
+    assert_eq!(info.external_ids.imdb, None);
+}
+
+#[actix_web::test]
+async fn tmdb_error_status_is_reported() {
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/404404", 404, "tmdb_not_found.json");
+    fake.get("/3/movie/550", 401, "tmdb_unauthorized.json");
+    let tmdb = provider::provider("TMDB/MV").unwrap();
+
+    let not_found = tmdb.lookup(&fake.config(), "404404").await.unwrap_err();
+    assert!(not_found.contains("404"), "{}", not_found);
+
+    let unauthorized = tmdb.lookup(&fake.config(), "550").await.unwrap_err();
+    assert!(unauthorized.contains("401"), "{}", unauthorized);
+}
+
+#[actix_web::test]
+async fn missing_access_token_fails_without_request() {
+    let fake = FakeScraper::start();
+    let mut config = fake.config();
+    config.tmdb_access_token = None;
+    config.bgm_access_token = None;
+
+    let tmdb_error = provider::provider("TMDB/MV").unwrap().lookup(&config, "550").await.unwrap_err();
+    let bgm_error = provider::provider("BGM.TV").unwrap().search(&config, "bebop").await.unwrap_err();
+
+    assert!(tmdb_error.contains("TMDB_ACCESS_TOKEN"));
+    assert!(bgm_error.contains("BGM_ACCESS_TOKEN"));
+    assert!(fake.requests().is_empty());
+}
+
+#[actix_web::test]
+async fn tmdb_search_limits_results_and_extracts_years() {
+    let fake = FakeScraper::start();
+    fake.get("/3/search/movie", 200, "tmdb_search_movie.json");
+
+    let results = provider::provider("TMDB/MV").unwrap().search(&fake.config(), "fight club").await.unwrap();
+
+    assert_eq!(results.len(), 5);
+    assert_eq!(results[0].id, "550");
+    assert_eq!(results[0].year.as_deref(), Some("1999"));
+    assert_eq!(results[1].year, None);
+    assert_eq!(results[1].poster, "");
+    assert_eq!(results[3].year, None);
+    assert!(fake.requests()[0].query.contains("query=fight+club") || fake.requests()[0].query.contains("query=fight%20club"));
+}
+
+#[actix_web::test]
+async fn bgm_subject_lookup_parses_metadata() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/253", 200, "bgm_subject_253.json");
+
+    let info = provider::provider("BGM.TV").unwrap().lookup(&fake.config(), "253").await.unwrap();
+
+    assert_eq!(info.title, "星际牛仔");
+    assert_eq!(info.original_title.as_deref(), Some("カウボーイビバップ"));
+    assert_eq!(info.year, Some(1998));
+    assert_eq!(info.episode_count, Some(26));
+    assert_eq!(info.genres, vec!["科幻", "TV", "原创", "日本"]);
+    assert_eq!(info.poster, "https://lain.bgm.tv/r/400/pic/cover/l/c2/0a/253_t3XWz.jpg");
+    assert_eq!(info.external_ids.bgm.as_deref(), Some("253"));
+
+    let request = &fake.requests()[0];
+    assert_eq!(request.authorization.as_deref(), Some(format!("Bearer {}", BGM_TOKEN).as_str()));
+    assert!(request.user_agent.is_some());
+}
+
+#[actix_web::test]
+async fn bgm_missing_optional_fields_are_empty() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/999001", 200, "bgm_subject_missing_fields.json");
+
+    let info = provider::provider("BGM.TV").unwrap().lookup(&fake.config(), "999001").await.unwrap();
+
+    // 中文名为空字符串时使用原名
+    assert_eq!(info.title, "未公開作品");
+    assert_eq!(info.year, None);
+    assert_eq!(info.episode_count, None);
+    assert!(info.genres.is_empty());
+    assert_eq!(info.poster, "");
+}
+
+#[actix_web::test]
+async fn bgm_title_falls_back_to_original_name() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/876", 200, "bgm_subject_no_name_cn.json");
+
+    let info = provider::provider("BGM.TV").unwrap().lookup(&fake.config(), "876").await.unwrap();
+
+    assert_eq!(info.title, "Natsume Yuujinchou");
+    assert_eq!(info.original_title, None);
+    assert_eq!(info.poster, "");
+    assert_eq!(info.year, Some(2008));
+    assert_eq!(info.episode_count, Some(13));
+}
+
+#[actix_web::test]
+async fn bgm_title_falls_back_to_infobox_names() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/877", 200, "bgm_subject_infobox_name.json");
+    fake.get("/v0/subjects/878", 200, "bgm_subject_alias_only.json");
+    let bgm = provider::provider("BGM.TV").unwrap();
+
+    let chinese_name = bgm.lookup(&fake.config(), "877").await.unwrap();
+    assert_eq!(chinese_name.title, "深夜食堂");
+    assert_eq!(chinese_name.summary, "");
+    assert_eq!(chinese_name.bgm_subject_type, Some(BgmSubjectType::Real));
+
+    let alias = bgm.lookup(&fake.config(), "878").await.unwrap();
+    assert_eq!(alias.title, "无名之作");
+}
+
+#[actix_web::test]
+async fn bgm_non_video_subjects_are_not_requestable() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/17", 200, "bgm_subject_book.json");
+    fake.get("/v0/subjects/253", 200, "bgm_subject_253.json");
+    let bgm = provider::provider("BGM.TV").unwrap();
+
+    let book = bgm.lookup(&fake.config(), "17").await.unwrap();
+    assert_eq!(book.bgm_subject_type, Some(BgmSubjectType::Book));
+    let reason = bgm.check_requestable(&book).unwrap_err();
+    assert!(reason.contains("书籍"), "{}", reason);
+
+    let anime = bgm.lookup(&fake.config(), "253").await.unwrap();
+    assert_eq!(anime.bgm_subject_type, Some(BgmSubjectType::Anime));
+    assert!(bgm.check_requestable(&anime).is_ok());
+    assert!(provider::provider("TMDB/MV").unwrap().check_requestable(&book).is_ok());
+}
+
+#[test]
+fn providers_declare_episode_numbering() {
+    let numbering = |source: &str| provider::provider(source).unwrap().episode_numbering();
+
+    assert_eq!(numbering("TMDB/MV"), EpisodeNumbering::Unsupported);
+    assert_eq!(numbering("TMDB/TV"), EpisodeNumbering::Required);
+    assert_eq!(numbering("BGM.TV"), EpisodeNumbering::Optional);
+}
+
+#[actix_web::test]
+async fn bgm_not_found_is_reported() {
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/404404", 404, "bgm_not_found.json");
+
+    let error = provider::provider("BGM.TV").unwrap().lookup(&fake.config(), "404404").await.unwrap_err();
+
+    assert!(error.contains("404"), "{}", error);
+}
+
+#[actix_web::test]
+async fn bgm_search_filters_video_subjects() {
+    let fake = FakeScraper::start();
+    fake.post("/v0/search/subjects", 200, "bgm_search.json");
+
+    let results = provider::provider("BGM.TV").unwrap().search(&fake.config(), "bebop").await.unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].title, "星际牛仔");
+    // 没有中文名时使用原名
+    assert_eq!(results[1].title, "カウボーイビバップ 天国の扉");
+    assert_eq!(results[2].poster, "");
+
+    let request = &fake.requests()[0];
+    let payload: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(payload["keyword"], "bebop");
+    assert_eq!(payload["filter"]["type"], serde_json::json!([2, 6]));
+    assert!(request.query.contains("limit=5"));
+}
+
+#[actix_web::test]
+async fn save_media_to_db_inserts_then_updates() {
+    let _db = support::setup_database().await;
+    let mut conn = establish_connection();
+    let request = insert_request(&mut conn, "TMDB/MV", "save-test");
+
+    let mut info = MediaInfo {
+        title: "搏击俱乐部".to_string(),
+        summary: String::new(),
+        poster: String::new(),
+        year: Some(1999),
+        genres: vec!["剧情".to_string(), "惊悚".to_string()],
+        ..Default::default()
+    };
+    scraper::save_media_to_db(&mut conn, request.id, &info).unwrap();
+
+    let saved = media_for(&mut conn, request.id).unwrap();
+    assert_eq!(saved.summary, None);
+    assert_eq!(saved.poster, None);
+    assert_eq!(saved.year, Some(1999));
+    assert_eq!(scraper::split_genres(saved.genres.as_deref()), vec!["剧情", "惊悚"]);
+
+    info.summary = "简介".to_string();
+    info.runtime = Some(139);
+    info.external_ids.imdb = Some("tt0137523".to_string());
+    scraper::save_media_to_db(&mut conn, request.id, &info).unwrap();
+
+    let updated = media_for(&mut conn, request.id).unwrap();
+    assert_eq!(updated.id, saved.id);
+    assert_eq!(updated.summary.as_deref(), Some("简介"));
+    assert_eq!(updated.runtime, Some(139));
+    assert_eq!(updated.imdb_id.as_deref(), Some("tt0137523"));
+}
+
+#[actix_web::test]
+async fn scrape_missing_saves_successes_and_reports_failures() {
+    let _db = support::setup_database().await;
+    let fake = FakeScraper::start();
+    fake.get("/3/tv/1399", 200, "tmdb_tv_1399.json");
+    fake.get("/v0/subjects/404404", 404, "bgm_not_found.json");
+
+    let mut conn = establish_connection();
+    let tv = insert_request(&mut conn, "TMDB/TV", "1399");
+    let missing = insert_request(&mut conn, "BGM.TV", "404404");
+    let unsupported = insert_request(&mut conn, "UNKNOWN", "1");
+
+    let result = scraper::scrape_missing(&mut conn, &fake.config(), Duration::ZERO).await.unwrap();
+
+    assert_eq!(result.total_processed, 3);
+    assert_eq!(result.successful, 1);
+    assert_eq!(result.failed, 2);
+    assert!(result.errors.iter().any(|e| e.contains(&format!("请求ID {}", missing.id)) && e.contains("刮削失败")));
+    assert!(result.errors.iter().any(|e| e.contains(&format!("请求ID {}", unsupported.id)) && e.contains("不支持的媒体源")));
+
+    let saved = media_for(&mut conn, tv.id).unwrap();
+    assert_eq!(saved.title, "权力的游戏");
+    assert_eq!(saved.episode_count, Some(73));
+    assert_eq!(saved.tvdb_id.as_deref(), Some("121361"));
+    assert!(media_for(&mut conn, missing.id).is_none());
+
+    // 已刮削的请求不会再次处理
+    let again = scraper::scrape_missing(&mut conn, &fake.config(), Duration::ZERO).await.unwrap();
+    assert_eq!(again.total_processed, 2);
+    assert_eq!(again.successful, 0);
+}
+
+#[actix_web::test]
+async fn cached_lookup_reuses_fresh_results() {
+    let _db = support::setup_database().await;
+    let fake = FakeScraper::start();
+    fake.get("/v0/subjects/253", 200, "bgm_subject_253.json");
+    let mut config = fake.config();
+    config.cache_ttl_secs = 3600;
+    let bgm = provider::provider("BGM.TV").unwrap();
+    let mut conn = establish_connection();
+
+    let first = scraper::cache::lookup(&mut conn, &config, bgm, "253").await.unwrap();
+    let second = scraper::cache::lookup(&mut conn, &config, bgm, "253").await.unwrap();
+
+    assert_eq!(first.title, "星际牛仔");
+    assert_eq!(second.title, "星际牛仔");
+    assert_eq!(second.bgm_subject_type, Some(BgmSubjectType::Anime));
+    assert_eq!(fake.requests().len(), 1);
+
+    // 过期后重新请求数据源
+    diesel::update(scrape_cache::table.filter(scrape_cache::media_id.eq("253")))
+        .set(scrape_cache::fetched_at.eq("2000-01-01 00:00:00"))
+        .execute(&mut conn)
+        .unwrap();
+    scraper::cache::lookup(&mut conn, &config, bgm, "253").await.unwrap();
+    assert_eq!(fake.requests().len(), 2);
+
+    // refresh 总是跳过缓存
+    scraper::cache::refresh(&mut conn, &config, bgm, "253").await.unwrap();
+    assert_eq!(fake.requests().len(), 3);
+}
+
+#[actix_web::test]
+async fn cache_is_disabled_with_zero_ttl() {
+    let _db = support::setup_database().await;
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/550", 200, "tmdb_movie_550.json");
+    let tmdb = provider::provider("TMDB/MV").unwrap();
+    let mut conn = establish_connection();
+
+    scraper::cache::lookup(&mut conn, &fake.config(), tmdb, "550").await.unwrap();
+    scraper::cache::lookup(&mut conn, &fake.config(), tmdb, "550").await.unwrap();
+
+    assert_eq!(fake.requests().len(), 2);
+    let cached: i64 = scrape_cache::table
+        .filter(scrape_cache::media_id.eq("550"))
+        .count()
+        .get_result(&mut conn)
+        .unwrap();
+    assert_eq!(cached, 0);
+}
+
+#[actix_web::test]
+async fn refresh_stale_rescrapes_only_old_media() {
+    let _db = support::setup_database().await;
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/550", 200, "tmdb_movie_550.json");
+    fake.get("/v0/subjects/876", 200, "bgm_subject_no_name_cn.json");
+    let mut config = fake.config();
+    config.cache_ttl_secs = 3600;
+    let mut conn = establish_connection();
+
+    let stale = insert_request(&mut conn, "TMDB/MV", "550");
+    let fresh = insert_request(&mut conn, "BGM.TV", "876");
+    let outdated = MediaInfo { title: "旧标题".to_string(), ..Default::default() };
+    scraper::save_media_to_db(&mut conn, stale.id, &outdated).unwrap();
+    scraper::save_media_to_db(&mut conn, fresh.id, &outdated).unwrap();
+    diesel::update(media::table.filter(media::media_request_id.eq(stale.id)))
+        .set(media::updated_at.eq(diesel::dsl::sql::<diesel::sql_types::Timestamp>("datetime('now', '-60 days')")))
+        .execute(&mut conn)
+        .unwrap();
+
+    let result = scraper::refresh_stale(&mut conn, &config, 10, Duration::ZERO).await.unwrap();
+
+    assert_eq!(result.total_processed, 1);
+    assert_eq!(result.successful, 1);
+    let refreshed = media_for(&mut conn, stale.id).unwrap();
+    assert_eq!(refreshed.title, "搏击俱乐部");
+    assert_eq!(refreshed.imdb_id.as_deref(), Some("tt0137523"));
+    assert_eq!(media_for(&mut conn, fresh.id).unwrap().title, "旧标题");
+    assert_eq!(fake.requests().len(), 1);
+
+    // 刷新后的行不会被再次处理
+    let again = scraper::refresh_stale(&mut conn, &config, 10, Duration::ZERO).await.unwrap();
+    assert_eq!(again.total_processed, 0);
+}
+
+#[actix_web::test]
+async fn refresh_stale_backs_off_failed_media() {
+    let _db = support::setup_database().await;
+    let fake = FakeScraper::start();
+    fake.get("/3/movie/550", 200, "tmdb_movie_550.json");
+    fake.get("/v0/subjects/404404", 404, "bgm_not_found.json");
+    let mut conn = establish_connection();
+
+    let failing = insert_request(&mut conn, "BGM.TV", "404404");
+    let stale = insert_request(&mut conn, "TMDB/MV", "550");
+    let outdated = MediaInfo { title: "旧标题".to_string(), ..Default::default() };
+    scraper::save_media_to_db(&mut conn, failing.id, &outdated).unwrap();
+    scraper::save_media_to_db(&mut conn, stale.id, &outdated).unwrap();
+    // 刷新失败的行更旧，会优先被选中
+    for (request_id, age) in [(failing.id, "-90 days"), (stale.id, "-60 days")] {
+        diesel::update(media::table.filter(media::media_request_id.eq(request_id)))
+            .set(media::updated_at.eq(diesel::dsl::sql::<diesel::sql_types::Timestamp>(&format!("datetime('now', '{}')", age))))
+            .execute(&mut conn)
+            .unwrap();
+    }
+
+    let first = scraper::refresh_stale(&mut conn, &fake.config(), 1, Duration::ZERO).await.unwrap();
+    assert_eq!(first.total_processed, 1);
+    assert_eq!(first.failed, 1);
+    assert!(media_for(&mut conn, failing.id).unwrap().refresh_attempted_at.is_some());
+
+    // 刚失败的行暂不重试，名额留给其他过期的行
+    let second = scraper::refresh_stale(&mut conn, &fake.config(), 1, Duration::ZERO).await.unwrap();
+    assert_eq!(second.total_processed, 1);
+    assert_eq!(second.successful, 1);
+    assert_eq!(media_for(&mut conn, stale.id).unwrap().title, "搏击俱乐部");
+
+    let third = scraper::refresh_stale(&mut conn, &fake.config(), 1, Duration::ZERO).await.unwrap();
+    assert_eq!(third.total_processed, 0);
+}
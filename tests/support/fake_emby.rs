@@ -0,0 +1,296 @@
+//! 本地假 Emby 服务，只实现 bot 和 webhook 用到的接口，数据保存在内存中
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use nyamedia_bot::emby::{EmbyClient, EmbyConfig};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub const API_KEY: &str = "fake-emby-api-key";
+pub const TEMPLATE_USER_ID: &str = "template";
+
+#[derive(Default)]
+pub struct FakeState {
+    next_id: u32,
+    /// 用户 ID -> 用户信息（含 Policy）
+    pub users: HashMap<String, Value>,
+    /// 用户 ID -> 密码，未设置的用户密码为空
+    pub passwords: HashMap<String, String>,
+    pub password_resets: Vec<String>,
+    pub logged_out_tokens: Vec<String>,
+    pub libraries: Vec<Value>,
+    /// 条目 ID -> 条目
+    pub items: HashMap<String, Value>,
+    /// 条目 ID -> 上级条目
+    pub ancestors: HashMap<String, Vec<Value>>,
+    pub sessions: Vec<Value>,
+}
+
+pub struct FakeEmby {
+    pub base_url: String,
+    pub state: web::Data<Mutex<FakeState>>,
+}
+
+impl FakeEmby {
+    /// 在随机端口启动假服务，需要在 actix 运行时中调用
+    pub fn start() -> Self {
+        let state = web::Data::new(Mutex::new(FakeState::default()));
+        state.lock().unwrap().users.insert(
+            TEMPLATE_USER_ID.to_string(),
+            json!({
+                "Id": TEMPLATE_USER_ID,
+                "Name": "template",
+                "Policy": { "IsAdministrator": false, "IsDisabled": false, "EnableRemoteAccess": true, "EnabledFolders": ["lib-movies"] }
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind fake emby");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/Users", web::get().to(list_users))
+                .route("/Users/New", web::post().to(create_user))
+                .route("/Users/AuthenticateByName", web::post().to(authenticate_by_name))
+                .route("/Sessions/Logout", web::post().to(logout))
+                .route("/Users/{id}", web::get().to(get_user))
+                .route("/Users/{id}", web::delete().to(delete_user))
+                .route("/Users/{id}/Password", web::post().to(reset_password))
+                .route("/Users/{id}/Policy", web::post().to(update_policy))
+                .route("/Library/VirtualFolders", web::get().to(list_libraries))
+                .route("/Items", web::get().to(get_items))
+                .route("/Items/{id}/Ancestors", web::get().to(get_ancestors))
+                .route("/Sessions", web::get().to(list_sessions))
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(listener)
+        .expect("failed to listen fake emby")
+        .run();
+        actix_rt::spawn(server);
+
+        Self { base_url, state }
+    }
+
+    pub fn client(&self) -> EmbyClient {
+        self.client_with_key(API_KEY)
+    }
+
+    pub fn client_with_key(&self, api_key: &str) -> EmbyClient {
+        EmbyClient::new(EmbyConfig {
+            base_url: self.base_url.clone(),
+            api_key: api_key.to_string(),
+            copy_from_user_id: Some(TEMPLATE_USER_ID.to_string()),
+        })
+    }
+
+    pub fn user(&self, id: &str) -> Option<Value> {
+        self.state.lock().unwrap().users.get(id).cloned()
+    }
+
+    pub fn set_password(&self, id: &str, password: &str) {
+        self.state.lock().unwrap().passwords.insert(id.to_string(), password.to_string());
+    }
+
+    pub fn add_user(&self, id: &str, name: &str) {
+        self.state.lock().unwrap().users.insert(
+            id.to_string(),
+            json!({ "Id": id, "Name": name, "Policy": { "IsAdministrator": false, "IsDisabled": false, "EnableRemoteAccess": true } }),
+        );
+    }
+}
+
+fn authorized(req: &HttpRequest) -> bool {
+    req.headers()
+        .get("X-Emby-Token")
+        .and_then(|value| value.to_str().ok())
+        == Some(API_KEY)
+}
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().body("Access token is invalid or expired.")
+}
+
+#[derive(Deserialize)]
+struct NewUserPayload {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "CopyFromUserId")]
+    copy_from_user_id: Option<String>,
+}
+
+async fn create_user(req: HttpRequest, state: web::Data<Mutex<FakeState>>, payload: web::Json<NewUserPayload>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    let mut state = state.lock().unwrap();
+    if state.users.values().any(|user| user["Name"] == payload.name.as_str()) {
+        return HttpResponse::BadRequest().body(format!("A user with the name '{}' already exists.", payload.name));
+    }
+
+    let policy = payload
+        .copy_from_user_id
+        .as_ref()
+        .and_then(|id| state.users.get(id))
+        .map(|user| user["Policy"].clone())
+        .unwrap_or_else(|| json!({ "IsAdministrator": false, "IsDisabled": false }));
+
+    state.next_id += 1;
+    let id = format!("user-{}", state.next_id);
+    let user = json!({ "Id": id, "Name": payload.name, "Policy": policy });
+    state.users.insert(id, user.clone());
+    HttpResponse::Ok().json(user)
+}
+
+async fn list_users(req: HttpRequest, state: web::Data<Mutex<FakeState>>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    let users: Vec<Value> = state.lock().unwrap().users.values().cloned().collect();
+    HttpResponse::Ok().json(users)
+}
+
+#[derive(Deserialize)]
+struct AuthenticatePayload {
+    #[serde(rename = "Username")]
+    username: String,
+    #[serde(rename = "Pw", default)]
+    pw: String,
+}
+
+async fn authenticate_by_name(req: HttpRequest, state: web::Data<Mutex<FakeState>>, payload: web::Json<AuthenticatePayload>) -> HttpResponse {
+    // 真实的 Emby 要求客户端标识
+    if req.headers().get("X-Emby-Authorization").is_none() {
+        return HttpResponse::BadRequest().body("Missing X-Emby-Authorization header");
+    }
+    let state = state.lock().unwrap();
+    let user = state
+        .users
+        .values()
+        .find(|user| user["Name"].as_str().is_some_and(|name| name.eq_ignore_ascii_case(&payload.username)));
+    match user {
+        Some(user) if state.passwords.get(user["Id"].as_str().unwrap_or_default()).map(String::as_str).unwrap_or("") == payload.pw => {
+            HttpResponse::Ok().json(json!({ "User": user, "AccessToken": format!("token-{}", user["Id"].as_str().unwrap_or_default()) }))
+        }
+        _ => HttpResponse::Unauthorized().body("Invalid username or password entered."),
+    }
+}
+
+async fn logout(req: HttpRequest, state: web::Data<Mutex<FakeState>>) -> HttpResponse {
+    if let Some(token) = req.headers().get("X-Emby-Token").and_then(|value| value.to_str().ok()) {
+        state.lock().unwrap().logged_out_tokens.push(token.to_string());
+    }
+    HttpResponse::NoContent().finish()
+}
+
+async fn get_user(req: HttpRequest, state: web::Data<Mutex<FakeState>>, path: web::Path<String>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    match state.lock().unwrap().users.get(path.as_str()) {
+        Some(user) => HttpResponse::Ok().json(user),
+        None => HttpResponse::NotFound().body("User not found"),
+    }
+}
+
+async fn delete_user(req: HttpRequest, state: web::Data<Mutex<FakeState>>, path: web::Path<String>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    match state.lock().unwrap().users.remove(path.as_str()) {
+        Some(_) => HttpResponse::NoContent().finish(),
+        None => HttpResponse::NotFound().body("User not found"),
+    }
+}
+
+async fn reset_password(req: HttpRequest, state: web::Data<Mutex<FakeState>>, path: web::Path<String>, payload: web::Json<Value>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    let mut state = state.lock().unwrap();
+    if !state.users.contains_key(path.as_str()) {
+        return HttpResponse::NotFound().body("User not found");
+    }
+    let user_id = path.into_inner();
+    if payload["ResetPassword"] == true {
+        state.passwords.remove(&user_id);
+        state.password_resets.push(user_id);
+        return HttpResponse::NoContent().finish();
+    }
+
+    let current = state.passwords.get(&user_id).cloned().unwrap_or_default();
+    match (payload["CurrentPw"].as_str(), payload["NewPw"].as_str()) {
+        (Some(current_pw), Some(new_pw)) if current_pw == current => {
+            state.passwords.insert(user_id, new_pw.to_string());
+            HttpResponse::NoContent().finish()
+        }
+        (Some(_), Some(_)) => HttpResponse::Forbidden().body("Invalid user or password entered."),
+        // 使用管理员 API Key 时可以不提供当前密码
+        (None, Some(new_pw)) => {
+            state.passwords.insert(user_id, new_pw.to_string());
+            HttpResponse::NoContent().finish()
+        }
+        _ => HttpResponse::BadRequest().body("Unsupported password payload"),
+    }
+}
+
+async fn update_policy(req: HttpRequest, state: web::Data<Mutex<FakeState>>, path: web::Path<String>, payload: web::Json<Value>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    match state.lock().unwrap().users.get_mut(path.as_str()) {
+        Some(user) => {
+            user["Policy"] = payload.into_inner();
+            HttpResponse::NoContent().finish()
+        }
+        None => HttpResponse::NotFound().body("User not found"),
+    }
+}
+
+async fn list_libraries(req: HttpRequest, state: web::Data<Mutex<FakeState>>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    HttpResponse::Ok().json(&state.lock().unwrap().libraries)
+}
+
+#[derive(Deserialize)]
+struct ItemsQuery {
+    #[serde(rename = "Ids", default)]
+    ids: String,
+}
+
+async fn get_items(req: HttpRequest, state: web::Data<Mutex<FakeState>>, query: web::Query<ItemsQuery>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    let state = state.lock().unwrap();
+    let items: Vec<Value> = query
+        .ids
+        .split(',')
+        .filter_map(|id| state.items.get(id).cloned())
+        .collect();
+    HttpResponse::Ok().json(json!({ "Items": items, "TotalRecordCount": items.len() }))
+}
+
+async fn get_ancestors(req: HttpRequest, state: web::Data<Mutex<FakeState>>, path: web::Path<String>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    match state.lock().unwrap().ancestors.get(path.as_str()) {
+        Some(ancestors) => HttpResponse::Ok().json(ancestors),
+        None => HttpResponse::NotFound().body("Item not found"),
+    }
+}
+
+async fn list_sessions(req: HttpRequest, state: web::Data<Mutex<FakeState>>) -> HttpResponse {
+    if !authorized(&req) {
+        return unauthorized();
+    }
+    HttpResponse::Ok().json(&state.lock().unwrap().sessions)
+}
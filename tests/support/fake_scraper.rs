@@ -0,0 +1,124 @@
+//! 本地假 TMDB / Bangumi 服务，按请求路径返回 `tests/fixtures/scraper` 下录制的响应
+
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::Mutex;
+
+use actix_web::http::StatusCode;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use nyamedia_bot::scraper::ScraperConfig;
+
+pub const TMDB_TOKEN: &str = "fake-tmdb-token";
+pub const BGM_TOKEN: &str = "fake-bgm-token";
+
+/// 假服务收到的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub authorization: Option<String>,
+    pub user_agent: Option<String>,
+    pub body: String,
+}
+
+#[derive(Default)]
+pub struct FakeState {
+    /// "METHOD 路径" -> (状态码, 响应体)
+    pub routes: HashMap<String, (u16, String)>,
+    pub requests: Vec<RecordedRequest>,
+}
+
+pub struct FakeScraper {
+    pub base_url: String,
+    pub state: web::Data<Mutex<FakeState>>,
+}
+
+impl FakeScraper {
+    /// 在随机端口启动假服务，需要在 actix 运行时中调用
+    pub fn start() -> Self {
+        let state = web::Data::new(Mutex::new(FakeState::default()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind fake scraper");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .default_service(web::to(respond))
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(listener)
+        .expect("failed to listen fake scraper")
+        .run();
+        actix_rt::spawn(server);
+
+        Self { base_url, state }
+    }
+
+    /// 指向假服务的配置，TMDB 和 Bangumi 共用同一个服务
+    pub fn config(&self) -> ScraperConfig {
+        ScraperConfig {
+            tmdb_base_url: format!("{}/3", self.base_url),
+            tmdb_access_token: Some(TMDB_TOKEN.to_string()),
+            bgm_base_url: self.base_url.clone(),
+            bgm_access_token: Some(BGM_TOKEN.to_string()),
+            // 默认不缓存，避免测试之间通过数据库互相影响
+            cache_ttl_secs: 0,
+            refresh_after_days: 30,
+        }
+    }
+
+    /// 让 `GET path` 返回 fixture 文件的内容
+    pub fn get(&self, path: &str, status: u16, fixture_name: &str) {
+        self.route("GET", path, status, fixture_name);
+    }
+
+    /// 让 `POST path` 返回 fixture 文件的内容
+    pub fn post(&self, path: &str, status: u16, fixture_name: &str) {
+        self.route("POST", path, status, fixture_name);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn route(&self, method: &str, path: &str, status: u16, fixture_name: &str) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert(format!("{} {}", method, path), (status, fixture(fixture_name)));
+    }
+}
+
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/scraper").join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("failed to read fixture {}: {}", path.display(), e))
+}
+
+fn header(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers().get(name).and_then(|value| value.to_str().ok()).map(ToOwned::to_owned)
+}
+
+async fn respond(req: HttpRequest, body: web::Bytes, state: web::Data<Mutex<FakeState>>) -> HttpResponse {
+    let mut state = state.lock().unwrap();
+    state.requests.push(RecordedRequest {
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        query: req.query_string().to_string(),
+        authorization: header(&req, "Authorization"),
+        user_agent: header(&req, "User-Agent"),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+
+    match state.routes.get(&format!("{} {}", req.method(), req.path())) {
+        Some((status, body)) => HttpResponse::build(StatusCode::from_u16(*status).unwrap())
+            .content_type("application/json")
+            .body(body.clone()),
+        None => HttpResponse::NotFound().body("no fixture for this route"),
+    }
+}
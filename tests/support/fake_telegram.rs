@@ -0,0 +1,92 @@
+//! 本地假 Telegram Bot API，记录 bot 发出的消息
+
+use std::net::TcpListener;
+use std::sync::Mutex;
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde_json::{json, Value};
+use teloxide::Bot;
+
+/// 假服务收到的 API 调用
+#[derive(Debug, Clone)]
+pub struct RecordedCall {
+    pub method: String,
+    pub body: Value,
+}
+
+pub struct FakeTelegram {
+    pub base_url: String,
+    pub calls: web::Data<Mutex<Vec<RecordedCall>>>,
+}
+
+impl FakeTelegram {
+    /// 在随机端口启动假服务，需要在 actix 运行时中调用
+    pub fn start() -> Self {
+        let calls = web::Data::new(Mutex::new(Vec::new()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind fake telegram");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let app_calls = calls.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_calls.clone())
+                .default_service(web::to(respond))
+        })
+        .workers(1)
+        .disable_signals()
+        .listen(listener)
+        .expect("failed to listen fake telegram")
+        .run();
+        actix_rt::spawn(server);
+
+        Self { base_url, calls }
+    }
+
+    pub fn bot(&self) -> Bot {
+        Bot::new("123456:fake-token").set_api_url(reqwest::Url::parse(&self.base_url).unwrap())
+    }
+
+    /// 按顺序返回发出的消息 (chat_id, text)
+    pub fn sent_messages(&self) -> Vec<(i64, String)> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.method.eq_ignore_ascii_case("sendMessage"))
+            .map(|call| {
+                (
+                    call.body["chat_id"].as_i64().unwrap_or_default(),
+                    call.body["text"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// 发给某个会话的消息
+    pub fn messages_to(&self, chat_id: i64) -> Vec<String> {
+        self.sent_messages()
+            .into_iter()
+            .filter(|(chat, _)| *chat == chat_id)
+            .map(|(_, text)| text)
+            .collect()
+    }
+}
+
+async fn respond(req: HttpRequest, body: web::Bytes, calls: web::Data<Mutex<Vec<RecordedCall>>>) -> HttpResponse {
+    let method = req.path().rsplit('/').next().unwrap_or_default().to_string();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    calls.lock().unwrap().push(RecordedCall { method: method.clone(), body: body.clone() });
+
+    // teloxide 以 `SendMessage` 形式请求方法名
+    let result = match method.to_ascii_lowercase().as_str() {
+        "sendmessage" => json!({
+            "message_id": 1,
+            "date": 0,
+            "chat": { "id": body["chat_id"], "type": "private", "first_name": "test" },
+            "text": body["text"],
+        }),
+        _ => json!(true),
+    };
+    HttpResponse::Ok().json(json!({ "ok": true, "result": result }))
+}
@@ -0,0 +1,52 @@
+// 各个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+pub mod fake_emby;
+pub mod fake_scraper;
+pub mod fake_telegram;
+
+use std::sync::OnceLock;
+
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use tokio::sync::{Mutex, MutexGuard};
+
+#[derive(QueryableByName)]
+struct TableName {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+/// 为当前测试进程准备独立的临时数据库并执行迁移。
+/// 测试共用同一个数据库文件，返回的锁用于让访问数据库的测试依次执行；
+/// 每次取得锁后清空所有表，测试之间不会看到彼此写入的数据
+pub async fn setup_database() -> MutexGuard<'static, ()> {
+    static INIT: OnceLock<()> = OnceLock::new();
+    static LOCK: Mutex<()> = Mutex::const_new(());
+
+    INIT.get_or_init(|| {
+        let path = std::env::temp_dir().join(format!("nyamedia-test-{}.sqlite3", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        std::env::set_var("DATABASE_URL", &path);
+        nyamedia_bot::database::run_migrations().expect("failed to run migrations");
+    });
+
+    let guard = LOCK.lock().await;
+    clear_tables();
+    guard
+}
+
+fn clear_tables() {
+    let mut conn = nyamedia_bot::establish_connection();
+    let tables = diesel::sql_query(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '__diesel_%'",
+    )
+    .load::<TableName>(&mut conn)
+    .expect("failed to list tables");
+
+    for table in tables {
+        diesel::sql_query(format!("DELETE FROM \"{}\"", table.name))
+            .execute(&mut conn)
+            .expect("failed to clear table");
+    }
+}
@@ -0,0 +1,119 @@
+mod support;
+
+use actix_web::test::TestRequest;
+use chrono::Utc;
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use nyamedia_bot::cli_auth::service::TelegramLoginPayload;
+use nyamedia_bot::establish_connection;
+use nyamedia_bot::models::NewTelegramUser;
+use nyamedia_bot::schema::telegram_users;
+use nyamedia_bot::web_auth::middleware;
+use nyamedia_bot::web_auth::service::{self, WebAuthConfig, WebAuthError, SESSION_COOKIE_NAME};
+use sha2::{Digest, Sha256};
+
+const BOT_TOKEN: &str = "12345:test-bot-token";
+const SESSION_SECRET: &str = "test-session-secret";
+
+fn config() -> WebAuthConfig {
+    std::env::set_var("WEB_SESSION_SECRET", SESSION_SECRET);
+    std::env::set_var("TELOXIDE_TOKEN", BOT_TOKEN);
+    WebAuthConfig::from_env().unwrap()
+}
+
+fn insert_user(telegram_id: i64, username: &str, admin: bool) {
+    let mut conn = establish_connection();
+    diesel::insert_into(telegram_users::table)
+        .values(&NewTelegramUser {
+            telegram_id,
+            username: username.to_string(),
+            emby_user_id: format!("emby-{}", telegram_id),
+        })
+        .execute(&mut conn)
+        .unwrap();
+    diesel::update(telegram_users::table.filter(telegram_users::telegram_id.eq(telegram_id)))
+        .set(telegram_users::admin.eq(admin))
+        .execute(&mut conn)
+        .unwrap();
+}
+
+/// 按 Telegram Login Widget 的规则签名
+fn signed_login(telegram_id: i64, auth_date: i64) -> TelegramLoginPayload {
+    let data_check_string = format!("auth_date={}\nfirst_name=Test\nid={}\nusername=tester", auth_date, telegram_id);
+    let mut mac = Hmac::<Sha256>::new_from_slice(&Sha256::digest(BOT_TOKEN.as_bytes())).unwrap();
+    mac.update(data_check_string.as_bytes());
+
+    TelegramLoginPayload {
+        id: telegram_id,
+        first_name: "Test".to_string(),
+        last_name: None,
+        username: Some("tester".to_string()),
+        photo_url: None,
+        auth_date,
+        hash: hex::encode(mac.finalize().into_bytes()),
+    }
+}
+
+#[actix_web::test]
+async fn telegram_login_issues_session_for_registered_users() {
+    let _db = support::setup_database().await;
+    let config = config();
+    insert_user(7001, "alice", true);
+    let now = Utc::now().timestamp();
+
+    let result = service::login_with_telegram(&config, signed_login(7001, now)).unwrap();
+    assert_eq!(result.telegram_id, 7001);
+    assert_eq!(result.username, "alice");
+    assert!(result.admin);
+
+    let (claims, user) = service::authenticate(&config, &result.access_token).unwrap();
+    assert_eq!(claims.telegram_user_id, 7001);
+    assert_eq!(user.username, "alice");
+
+    // 未注册、签名错误或登录数据过期都不能登录
+    assert!(matches!(
+        service::login_with_telegram(&config, signed_login(7002, now)),
+        Err(WebAuthError::Unauthorized(_))
+    ));
+    let mut tampered = signed_login(7001, now);
+    tampered.id = 7003;
+    assert!(matches!(service::login_with_telegram(&config, tampered), Err(WebAuthError::Unauthorized(_))));
+    let stale = signed_login(7001, now - config.telegram_auth_max_age_secs - 60);
+    assert!(matches!(service::login_with_telegram(&config, stale), Err(WebAuthError::Unauthorized(_))));
+}
+
+#[actix_web::test]
+async fn verify_admin_requires_admin_session() {
+    let _db = support::setup_database().await;
+    let config = config();
+    insert_user(7101, "admin", true);
+    insert_user(7102, "member", false);
+    let now = Utc::now().timestamp();
+    let admin_token = service::login_with_telegram(&config, signed_login(7101, now)).unwrap().access_token;
+    let member_token = service::login_with_telegram(&config, signed_login(7102, now)).unwrap().access_token;
+
+    let anonymous = TestRequest::default().to_http_request();
+    assert!(matches!(middleware::verify_admin(&anonymous), Err(WebAuthError::Unauthorized(_))));
+
+    let forged = TestRequest::default().insert_header(("Authorization", "Bearer not-a-token")).to_http_request();
+    assert!(matches!(middleware::verify_admin(&forged), Err(WebAuthError::Unauthorized(_))));
+
+    let member = TestRequest::default()
+        .insert_header(("Authorization", format!("Bearer {}", member_token)))
+        .to_http_request();
+    assert!(middleware::verify_session(&member).is_ok());
+    assert!(matches!(middleware::verify_admin(&member), Err(WebAuthError::Forbidden(_))));
+
+    // 浏览器通过 session cookie 携带凭证
+    let admin = TestRequest::default()
+        .cookie(actix_web::cookie::Cookie::new(SESSION_COOKIE_NAME, admin_token))
+        .to_http_request();
+    let (_, user) = middleware::verify_admin(&admin).unwrap();
+    assert_eq!(user.telegram_id, 7101);
+
+    // 用户被删除后旧的 session 失效
+    diesel::delete(telegram_users::table.filter(telegram_users::telegram_id.eq(7101)))
+        .execute(&mut establish_connection())
+        .unwrap();
+    assert!(matches!(middleware::verify_admin(&admin), Err(WebAuthError::Unauthorized(_))));
+}
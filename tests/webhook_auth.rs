@@ -0,0 +1,107 @@
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use nyamedia_bot::webhook_auth::{self, WebhookAuthConfig, SIGNATURE_HEADER};
+use sha2::Sha256;
+
+const SECRET: &str = "webhook-secret";
+const BODY: &[u8] = br#"{"Event":"library.new"}"#;
+
+fn config() -> WebhookAuthConfig {
+    WebhookAuthConfig { secret: Some(SECRET.to_string()), replay_window_secs: 300 }
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[test]
+fn accepts_valid_signature_with_or_without_prefix() {
+    let signature = sign(SECRET, BODY);
+
+    let req = TestRequest::post()
+        .uri("/webhook")
+        .insert_header((SIGNATURE_HEADER, format!("sha256={}", signature)))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_ok());
+
+    let req = TestRequest::post()
+        .uri("/webhook")
+        .insert_header((SIGNATURE_HEADER, signature))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_ok());
+}
+
+#[test]
+fn rejects_wrong_or_malformed_signature() {
+    let req = TestRequest::post()
+        .uri("/webhook")
+        .insert_header((SIGNATURE_HEADER, format!("sha256={}", sign("other-secret", BODY))))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_err());
+
+    // 签名对应的是另一个请求体
+    let req = TestRequest::post()
+        .uri("/webhook")
+        .insert_header((SIGNATURE_HEADER, sign(SECRET, b"{}")))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_err());
+
+    let req = TestRequest::post()
+        .uri("/webhook")
+        .insert_header((SIGNATURE_HEADER, "sha256=not-hex"))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_err());
+}
+
+#[test]
+fn token_query_param_is_checked_before_signature() {
+    let req = TestRequest::post().uri("/webhook?token=webhook-secret").to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_ok());
+
+    // 带了 token 时不再回退到签名
+    let req = TestRequest::post()
+        .uri("/webhook?token=wrong")
+        .insert_header((SIGNATURE_HEADER, sign(SECRET, BODY)))
+        .to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_err());
+}
+
+#[test]
+fn rejects_request_without_credentials_only_when_secret_is_set() {
+    let req = TestRequest::post().uri("/webhook").to_http_request();
+    assert!(webhook_auth::verify_request(&config(), &req, BODY).is_err());
+
+    let disabled = WebhookAuthConfig { secret: None, replay_window_secs: 300 };
+    assert!(webhook_auth::verify_request(&disabled, &req, BODY).is_ok());
+}
+
+#[test]
+fn date_must_be_within_replay_window() {
+    let config = config();
+
+    let now = Utc::now().to_rfc3339();
+    assert!(webhook_auth::verify_date(&config, Some(&now)).is_ok());
+
+    let stale = (Utc::now() - Duration::seconds(600)).to_rfc3339();
+    assert!(webhook_auth::verify_date(&config, Some(&stale)).is_err());
+
+    let future = (Utc::now() + Duration::seconds(600)).to_rfc3339();
+    assert!(webhook_auth::verify_date(&config, Some(&future)).is_err());
+
+    assert!(webhook_auth::verify_date(&config, Some("yesterday")).is_err());
+    assert!(webhook_auth::verify_date(&config, None).is_err());
+}
+
+#[test]
+fn date_check_can_be_disabled() {
+    let stale = (Utc::now() - Duration::days(1)).to_rfc3339();
+
+    let no_window = WebhookAuthConfig { secret: Some(SECRET.to_string()), replay_window_secs: 0 };
+    assert!(webhook_auth::verify_date(&no_window, Some(&stale)).is_ok());
+
+    let no_secret = WebhookAuthConfig { secret: None, replay_window_secs: 300 };
+    assert!(webhook_auth::verify_date(&no_secret, None).is_ok());
+}
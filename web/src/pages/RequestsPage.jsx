@@ -7,6 +7,7 @@ const MEDIA_REQUEST_STATUS = {
     ARCHIVED: 1,    // 已入库
     CANCELLED: 2,   // 被取消
     INVALID: 3,     // 不符合规范
+    REMOVED: 4,     // 已从媒体库移除
 }
 
 export default function RequestsPage() {
@@ -21,25 +22,74 @@ export default function RequestsPage() {
     const [copiedId, setCopiedId] = useState(null)
     const telegramLoginRef = useRef(null)
 
-    // 检查用户注册状态的函数
-    const checkUserRegistration = async (telegramId) => {
+    // 根据服务端 session 获取用户状态（注册/管理员权限均以服务端为准）
+    const loadSession = async () => {
         setLoading(true)
         try {
-            const response = await axios.get(`/api/check_user/${telegramId}`)
-            setRegistrationStatus(response.data)
+            const response = await axios.get('/api/web/session')
+            setRegistrationStatus({
+                registered: true,
+                database_username: response.data.database_username,
+                admin: response.data.admin
+            })
 
-            // 如果用户已注册，获取数据
-            if (response.data.registered) {
+            // 只有管理员可以查看请求列表
+            if (response.data.admin) {
                 fetchData()
             }
         } catch (error) {
-            console.error('Failed to check user registration:', error)
-            setError('检查用户状态失败')
+            if (error.response?.status !== 401) {
+                console.error('Failed to load session:', error)
+                setError('检查用户状态失败')
+            }
+            sessionStorage.removeItem('telegramUser')
+            setUser(null)
+            setRegistrationStatus(null)
         } finally {
             setLoading(false)
         }
     }
 
+    // 使用 Telegram 登录回调数据换取服务端 session
+    const loginWithTelegram = async (telegramData) => {
+        setLoading(true)
+        try {
+            await axios.post('/api/web/login/telegram', {
+                telegram_login: {
+                    id: Number(telegramData.id),
+                    first_name: telegramData.first_name,
+                    last_name: telegramData.last_name || null,
+                    username: telegramData.username || null,
+                    photo_url: telegramData.photo_url || null,
+                    auth_date: Number(telegramData.auth_date),
+                    hash: telegramData.hash
+                }
+            })
+            await loadSession()
+        } catch (error) {
+            console.error('Failed to login with Telegram:', error)
+            if (error.response?.status === 401) {
+                setRegistrationStatus({ registered: false })
+            } else {
+                setError(error.response?.data?.error || '登录失败')
+            }
+            setLoading(false)
+        }
+    }
+
+    const logout = async () => {
+        try {
+            await axios.post('/api/web/logout')
+        } catch (error) {
+            console.error('Failed to logout:', error)
+        }
+        sessionStorage.removeItem('telegramUser')
+        setUser(null)
+        setRegistrationStatus(null)
+        setPendingList([])
+        setArchivedList([])
+    }
+
     // 获取所有数据的函数
     const fetchData = async () => {
         setDataLoading(true)
@@ -60,14 +110,16 @@ export default function RequestsPage() {
 
     // 更新媒体请求状态的函数
     const updateRequestStatus = async (requestId, newStatus, actionName) => {
-        if (!confirm(`确认要将此媒体请求标记为"${actionName}"吗？`)) {
+        const reason = prompt(`确认要将此媒体请求标记为"${actionName}"吗？\n可填写原因（可选，会发送给请求者）：`, '')
+        if (reason === null) {
             return
         }
 
         try {
             const response = await axios.post('/api/update-request', {
                 request_id: requestId,
-                new_status: newStatus
+                new_status: newStatus,
+                reason: reason.trim() || null
             })
 
             if (response.data.success) {
@@ -79,7 +131,7 @@ export default function RequestsPage() {
             }
         } catch (error) {
             console.error('Failed to update request status:', error)
-            alert('更新状态失败，请重试')
+            alert(error.response?.data?.error || error.response?.data?.message || '更新状态失败，请重试')
         }
     }
 
@@ -100,8 +152,8 @@ export default function RequestsPage() {
             try {
                 const userData = JSON.parse(storedUser)
                 setUser(userData)
-                // 如果已经有用户信息，检查注册状态
-                checkUserRegistration(userData.id)
+                // 如果已经有用户信息，向服务端确认登录状态
+                loadSession()
             } catch (e) {
                 console.error('Failed to parse stored user:', e)
                 sessionStorage.removeItem('telegramUser')
@@ -129,15 +181,15 @@ export default function RequestsPage() {
                 hash
             }
 
-            // 存储到 sessionStorage
+            // 存储到 sessionStorage（仅用于展示头像和昵称）
             sessionStorage.setItem('telegramUser', JSON.stringify(telegramData))
             setUser(telegramData)
 
             // 清理 URL 参数
             window.history.replaceState({}, document.title, window.location.pathname)
 
-            // 检查用户是否已注册
-            checkUserRegistration(telegramData.id)
+            // 由服务端校验登录数据并建立 session
+            loginWithTelegram(telegramData)
         }
     }, [])
 
@@ -146,9 +198,10 @@ export default function RequestsPage() {
         window.onTelegramAuth = function(user) {
             console.log('Telegram auth success:', user)
 
-            // 存储到 sessionStorage
+            // 存储到 sessionStorage（仅用于展示头像和昵称）
             sessionStorage.setItem('telegramUser', JSON.stringify(user))
             setUser(user)
+            loginWithTelegram(user)
 
             // 重定向到配置的 URL
             const redirectUrl = import.meta.env.VITE_REDIRECT_URL
@@ -246,11 +299,7 @@ export default function RequestsPage() {
                                 用户未注册，无法访问
                             </p>
                             <button
-                                onClick={() => {
-                                    sessionStorage.removeItem('telegramUser')
-                                    setUser(null)
-                                    setRegistrationStatus(null)
-                                }}
+                                onClick={logout}
                                 className="login-button"
                             >
                                 重新登录
@@ -284,13 +333,7 @@ export default function RequestsPage() {
                                 </div>
                             </div>
                             <button
-                                onClick={() => {
-                                    sessionStorage.removeItem('telegramUser')
-                                    setUser(null)
-                                    setRegistrationStatus(null)
-                                    setPendingList([])
-                                    setArchivedList([])
-                                }}
+                                onClick={logout}
                                 className="logout-button"
                             >
                                 登出
@@ -331,7 +374,13 @@ export default function RequestsPage() {
                                     </div>
                                 )}
 
-                                {!dataLoading && currentData.length === 0 && (
+                                {!registrationStatus.admin && (
+                                    <div className="empty-state">
+                                        <p>仅管理员可以查看媒体请求列表</p>
+                                    </div>
+                                )}
+
+                                {registrationStatus.admin && !dataLoading && currentData.length === 0 && (
                                     <div className="empty-state">
                                         <p>
                                             {activeTab === 'pending' ? '暂无未入库内容' :
@@ -340,7 +389,7 @@ export default function RequestsPage() {
                                     </div>
                                 )}
 
-                                {!dataLoading && currentData.length > 0 && (
+                                {registrationStatus.admin && !dataLoading && currentData.length > 0 && (
                                     <div className="media-grid">
                                         {currentData.map(item => (
                                             <div key={item.id} className="media-item">
@@ -380,10 +429,25 @@ export default function RequestsPage() {
                                                 </h3>
                                                 <div className="media-meta">
                                                     <span className="media-source">{item.source}</span>
+                                                    {item.votes > 1 && (
+                                                        <span className="media-votes">👍 {item.votes}</span>
+                                                    )}
                                                     <span className="media-date">
                             {new Date(item.created_at).toLocaleDateString()}
                           </span>
                                                 </div>
+                                                {(item.year || item.episode_count) && (
+                                                    <p className="media-details">
+                                                        {[
+                                                            item.year,
+                                                            item.season_count && `${item.season_count} 季`,
+                                                            item.episode_count && `${item.episode_count} 集`,
+                                                        ].filter(Boolean).join(' · ')}
+                                                    </p>
+                                                )}
+                                                {item.status_reason && (
+                                                    <p className="media-reason">原因：{item.status_reason}</p>
+                                                )}
 
                                                 {/* 管理员操作按钮 */}
                                                 {registrationStatus?.admin && item.status === MEDIA_REQUEST_STATUS.SUBMITTED && (
@@ -408,6 +472,16 @@ export default function RequestsPage() {
                                                         </button>
                                                     </div>
                                                 )}
+                                                {registrationStatus?.admin && item.status === MEDIA_REQUEST_STATUS.ARCHIVED && (
+                                                    <div className="admin-actions">
+                                                        <button
+                                                            className="action-btn cancel-btn"
+                                                            onClick={() => updateRequestStatus(item.id, MEDIA_REQUEST_STATUS.REMOVED, '已移除')}
+                                                        >
+                                                            已移除
+                                                        </button>
+                                                    </div>
+                                                )}
                                             </div>
                                         ))}
                                     </div>